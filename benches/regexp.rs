@@ -0,0 +1,223 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use rustregex::bench_support;
+use rustregex::regex::{init, RegExp, SearchCache};
+
+/// The system allocator, counting allocations so that benchmarks can report them.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the result of `f` along with the number of allocations it made.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = f();
+    (result, ALLOCATIONS.load(Ordering::Relaxed) - before)
+}
+
+/// Representative patterns, along with a unit of input which they match.
+const PATTERNS: [(&str, &str); 6] = [
+    ("literal", "needle"),
+    ("alternation", "cat|dog|bird"),
+    ("closure", "(a|b)*"),
+    ("digits", r"\d{1,10}"),
+    ("any", ".*x"),
+    ("prefix", r"foo\d+"),
+];
+
+const INPUT_SIZES: [usize; 3] = [16, 256, 4096];
+
+/// Returns an input of `size` bytes made of filler text, with `unit` at its end.
+fn input(unit: &str, size: usize) -> String {
+    let filler = "lorem ipsum 42 ".repeat(size / 15 + 1);
+    format!("{}{unit}", &filler[..size.saturating_sub(unit.len())])
+}
+
+fn compile(pattern: &str) -> RegExp {
+    init()
+        .compile(pattern)
+        .expect("benchmarked patterns should compile")
+}
+
+fn bench<F: Fn(&RegExp, &str)>(c: &mut Criterion, name: &str, units: &[&str], f: F) {
+    let mut group = c.benchmark_group(name);
+    for ((label, pattern), unit) in PATTERNS.iter().zip(units) {
+        let regexp = compile(pattern);
+        for size in INPUT_SIZES {
+            let expr = input(unit, size);
+            let (_, counters) = bench_support::measure(&regexp, |regexp| f(regexp, &expr));
+            eprintln!("{name}/{label}/{size}: {counters:?}");
+
+            group.throughput(Throughput::Bytes(expr.len() as u64));
+            group.bench_with_input(BenchmarkId::new(*label, size), &expr, |b, expr| b.iter(|| f(&regexp, expr)));
+        }
+    }
+    group.finish();
+}
+
+fn full_match(c: &mut Criterion) {
+    bench(c, "full_match", &["needle", "dog", "abab", "1234", "yyyx", "foo42"], |regexp, expr| {
+        regexp.full_match(expr);
+    });
+}
+
+fn global_search(c: &mut Criterion) {
+    bench(c, "global_search", &["needle", "dog", "abab", "1234", "x", "foo42"], |regexp, expr| {
+        regexp.global_search(expr);
+    });
+}
+
+fn find_iter(c: &mut Criterion) {
+    bench(c, "find_iter", &["needle", "dog", "abab", "1234", "x", "foo42"], |regexp, expr| {
+        regexp.find_iter(expr).count();
+    });
+}
+
+/// Benchmarks a search failing late at every start, where attempts starting later follow the states of earlier ones.
+fn dead_starts(c: &mut Criterion) {
+    let regexp = compile("a+c");
+    let mut group = c.benchmark_group("dead_starts");
+    for size in INPUT_SIZES {
+        let expr = format!("{}b", "a".repeat(size - 1));
+        let (_, counters) = bench_support::measure(&regexp, |regexp| regexp.is_match(&expr));
+        eprintln!("dead_starts/a+c/{size}: {counters:?}");
+
+        group.throughput(Throughput::Bytes(expr.len() as u64));
+        group.bench_with_input(BenchmarkId::new("a+c", size), &expr, |b, expr| b.iter(|| regexp.is_match(expr)));
+    }
+    group.finish();
+}
+
+/// Benchmarks a search anchored at the end of a long input, matched backwards from the end, against the same search in
+/// multi-line mode, where `$` also matches before a newline so every start is attempted.
+fn anchored_end(c: &mut Criterion) {
+    let mut group = c.benchmark_group("anchored_end");
+    for (label, pattern) in [("reversed", r"\d+$"), ("forward", r"(?m)\d+$")] {
+        let regexp = compile(pattern);
+        for size in INPUT_SIZES {
+            let expr = input("1234", size);
+            let (_, counters) = bench_support::measure(&regexp, |regexp| regexp.find(&expr));
+            eprintln!("anchored_end/{label}/{size}: {counters:?}");
+
+            group.throughput(Throughput::Bytes(expr.len() as u64));
+            group.bench_with_input(BenchmarkId::new(label, size), &expr, |b, expr| b.iter(|| regexp.find(expr)));
+        }
+    }
+    group.finish();
+}
+
+/// Benchmarks finding every match of a search anchored at the start of a long input, which only attempts matching at
+/// its start, against the same search in multi-line mode, where `^` also matches after a newline so every start is
+/// attempted.
+fn anchored_start(c: &mut Criterion) {
+    let mut group = c.benchmark_group("anchored_start");
+    for (label, pattern) in [("skipping", r"^\d+"), ("seeding", r"(?m)^\d+")] {
+        let regexp = compile(pattern);
+        for size in INPUT_SIZES {
+            let expr = format!("1234{}", input("", size - 4));
+            let (_, counters) = bench_support::measure(&regexp, |regexp| regexp.find_iter(&expr).count());
+            eprintln!("anchored_start/{label}/{size}: {counters:?}");
+
+            group.throughput(Throughput::Bytes(expr.len() as u64));
+            group.bench_with_input(BenchmarkId::new(label, size), &expr, |b, expr| {
+                b.iter(|| regexp.find_iter(expr).count());
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Benchmarks a closure over an alternation of character classes, where every attempt holds a state for each class.
+fn class_alternation(c: &mut Criterion) {
+    let regexp = compile(r"(\d|\w|\s)*");
+    let mut group = c.benchmark_group("class_alternation");
+    for size in INPUT_SIZES {
+        let expr = input("", size);
+        let (_, counters) = bench_support::measure(&regexp, |regexp| regexp.find_iter(&expr).count());
+        eprintln!("class_alternation/{size}: {counters:?}");
+
+        group.throughput(Throughput::Bytes(expr.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &expr, |b, expr| {
+            b.iter(|| regexp.find_iter(expr).count());
+        });
+    }
+    group.finish();
+}
+
+/// Benchmarks finding matches in many short inputs, with and without reusing a [`SearchCache`].
+fn find_cached(c: &mut Criterion) {
+    let regexp = compile(r"(a|b)*c\d");
+    let inputs: Vec<String> = (0..100)
+        .map(|i| format!("xx ab{}c{} yy", "ab".repeat(i % 5), i % 10))
+        .collect();
+
+    let mut cache = SearchCache::default();
+    let (_, uncached) = count_allocations(|| inputs.iter().filter_map(|expr| regexp.find(expr)).count());
+    let (_, cached) = count_allocations(|| {
+        inputs
+            .iter()
+            .filter_map(|expr| regexp.find_cached(expr, &mut cache))
+            .count()
+    });
+    eprintln!("find_cached: {uncached} allocations without cache, {cached} with cache");
+
+    let mut group = c.benchmark_group("find_cached");
+    group.bench_function("uncached", |b| b.iter(|| inputs.iter().filter_map(|expr| regexp.find(expr)).count()));
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            inputs
+                .iter()
+                .filter_map(|expr| regexp.find_cached(expr, &mut cache))
+                .count()
+        });
+    });
+    group.finish();
+}
+
+/// Benchmarks searching for a pattern with a required prefix which occurs sparsely in a large input, where finding the
+/// prefix dominates, labelled by whether the `memchr` feature is enabled so that both can be compared.
+fn sparse_prefix(c: &mut Criterion) {
+    let regexp = compile(r"needle\d+");
+    let label = if cfg!(feature = "memchr") { "memchr" } else { "str_find" };
+    let mut group = c.benchmark_group("sparse_prefix");
+    for size in [4096, 65536] {
+        let expr = input("needle42", size).repeat(4);
+        let (_, counters) = bench_support::measure(&regexp, |regexp| regexp.find_iter(&expr).count());
+        eprintln!("sparse_prefix/{label}/{size}: {counters:?}");
+
+        group.throughput(Throughput::Bytes(expr.len() as u64));
+        group.bench_with_input(BenchmarkId::new(label, size), &expr, |b, expr| b.iter(|| regexp.find_iter(expr).count()));
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    full_match,
+    global_search,
+    find_iter,
+    dead_starts,
+    anchored_start,
+    anchored_end,
+    class_alternation,
+    find_cached,
+    sparse_prefix
+);
+criterion_main!(benches);
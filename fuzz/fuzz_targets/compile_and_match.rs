@@ -0,0 +1,59 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustregex::regex::RegExpBuilder;
+
+// Compiles the first line of the data as a pattern, with flags picked by its first byte, and matches it against the rest
+// of the data, asserting that nothing panics.
+fuzz_target!(|data: &[u8]| {
+    let Some((&flags, data)) = data.split_first() else {
+        return;
+    };
+    let Ok(data) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (pattern, expr) = data.split_once('\n').unwrap_or((data, ""));
+
+    // Large repetition counts legitimately take long to compile, which is not what is being tested
+    let large_repetition = pattern
+        .split(['{', ','])
+        .skip(1)
+        .any(|bound| bound.chars().take_while(char::is_ascii_digit).count() > 2);
+    if pattern.len() > 64 || large_repetition {
+        return;
+    }
+
+    let Ok(regexp) = RegExpBuilder::new(pattern)
+        .case_insensitive(flags & 1 != 0)
+        .multi_line(flags & 2 != 0)
+        .dot_all(flags & 4 != 0)
+        .unicode(flags & 8 != 0)
+        .build()
+    else {
+        return;
+    };
+
+    regexp.is_match(expr);
+    regexp.full_match(expr);
+    regexp.greedy_search(expr);
+    regexp.global_search(expr);
+    regexp.find_iter(expr).count();
+    regexp.find_overlapping(expr);
+    regexp.captures(expr);
+    regexp.captures_iter_lazy(expr).count();
+    regexp.split(expr);
+    regexp.replace_all(expr, "[$0$1${2}]");
+    regexp.explain(expr);
+    regexp.is_match_bytes(expr.as_bytes());
+    for offset in 0..=expr.len() {
+        regexp.captures_at(expr, offset);
+        regexp.shortest_match_at(expr, offset);
+    }
+
+    // Building a DFA takes exponential time in the worst case, e.g. for `.*a.....`, and probes every character when Unicode
+    // is enabled, by the flag or inline, as there are no ASCII ranges to partition the alphabet
+    if pattern.len() <= 16 && flags & 8 == 0 && !pattern.contains('u') {
+        regexp.redundant_branches();
+        regexp.minimize();
+    }
+});
@@ -0,0 +1,6 @@
+// The library is used by other programs, so it must never write to their output
+#![deny(clippy::print_stdout, clippy::print_stderr, clippy::dbg_macro)]
+
+mod modules;
+
+pub use modules::{bench_support, regex};
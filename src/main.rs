@@ -1,8 +1,6 @@
-mod modules;
-
 use std::io::{self, Write};
 
-use modules::regex;
+use rustregex::regex;
 
 fn main() {
     let regex = regex::init();
@@ -40,7 +38,7 @@ fn get_user_input(prompt: &str) -> String {
     io::stdin()
         .read_line(&mut input)
         .expect("failed to get user input from stdin");
-    input = input.trim_end_matches(&['\r', '\n']).to_string();
+    input = input.trim_end_matches(['\r', '\n']).to_string();
 
     if input != input.trim() {
         println!("WARNING: input starts or ends with whitespace");
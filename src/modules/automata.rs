@@ -1,45 +1,203 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter, Result};
+use std::mem;
+use std::ops::Deref;
 use std::rc::Rc;
-use std::str::Chars;
+use std::slice;
+use std::str::CharIndices;
 
-use super::regex::Anchor;
-use super::state::{AnchorState, LambdaState, State, TokenState, TrivialState};
+use super::dfa::Dfa;
+use super::regex::{Anchor, MatchSemantics};
+use super::state::{
+    next_char, prev_char, AnchorState, BackrefState, CallState, GroupState, LambdaState, PredicateKind, State, TokenState,
+    TrivialState,
+};
 
 type StatePtr = Rc<RefCell<dyn State>>;
 
+/// A pair of byte offsets into an expression, delimiting a match.
+pub type Span = (usize, usize);
+
 pub struct Automata {
     start: StatePtr,
     end: Rc<RefCell<TrivialState>>,
+    anchored: bool,
+    /// Whether to match with the backtracking engine rather than by simulating the NFA.
+    backtracking: bool,
+    /// Which match is reported among those starting at the leftmost position.
+    semantics: MatchSemantics,
+    /// Predicate defining word characters for word boundaries, or `None` for ASCII alphanumeric characters.
+    word_chars: Option<Rc<dyn Fn(char) -> bool>>,
+    /// String which every match starts with, so that searches need only attempt matching where it occurs.
+    prefix: Option<String>,
+    /// Reversal of the [`Automata`], set when every match ends at the end of an expression, so that searches may match it
+    /// backwards from there instead of attempting matching from every position.
+    reversed: Option<Box<Automata>>,
+    /// Number of distinct predicates of the [`LambdaState`]s, once numbered by [`Automata::batch_predicates`].
+    predicates: usize,
+    transitions: Cell<usize>,
+    /// Number of attempts at matching from a given position made by all searches so far.
+    attempts: Cell<usize>,
+}
+
+/// A record of how far the furthest reaching attempt at matching a regular expression against an expression progressed.
+#[derive(Debug, PartialEq)]
+pub struct MatchTrace {
+    /// Whether any substring of the expression matched.
+    pub matched: bool,
+    /// Byte offset of the expression where the furthest reaching attempt started.
+    pub start: usize,
+    /// Byte offset of the expression where the furthest reaching attempt got stuck.
+    pub offset: usize,
+    /// Descriptions of the characters which would have allowed the furthest reaching attempt to progress, e.g.
+    /// [`PredicateKind::Digit`] for `\d`, in the order of the states expecting them.
+    pub expected: Vec<PredicateKind>,
+}
+
+/// Buffers of states reused across the searches it is passed to, e.g. by `RegExp::find_cached`, to avoid allocating new
+/// ones for every search.
+#[derive(Default)]
+pub struct SearchCache {
+    /// Empty buffers of states, ready to be reused.
+    buffers: Vec<Vec<StatePtr>>,
+    /// Scratch space to keep track of the states visited while following epsilon transitions.
+    visited: Vec<StatePtr>,
+    /// Empty buffer of attempts, ready to be reused.
+    attempts: Vec<Attempt>,
+    /// Results of the predicates on the current character, see [`Automata::batch_predicates`].
+    predicates: Vec<Option<bool>>,
+}
+
+impl SearchCache {
+    /// Returns an empty buffer of states, reusing a previous one if available.
+    fn buffer(&mut self) -> Vec<StatePtr> {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Empties `buffer` and keeps it for reuse.
+    fn recycle(&mut self, mut buffer: Vec<StatePtr>) {
+        buffer.clear();
+        self.buffers.push(buffer);
+    }
+}
+
+/// A call made by the backtracking engine, which returns once the called capturing group is matched.
+#[derive(Clone)]
+struct Call {
+    /// Index of the called capturing group, or 0 for the whole [`Automata`].
+    index: usize,
+    /// Byte offset of the expression where the call was made.
+    start: usize,
+    /// State to resume from once the call returns.
+    ret: StatePtr,
+    /// Capture slots at the time of the call, which are restored once the call returns.
+    slots: Vec<Option<usize>>,
+}
+
+/// A path explored by the backtracking engine.
+struct Branch {
+    state: StatePtr,
+    position: usize,
+    slots: Vec<Option<usize>>,
+    calls: Vec<Call>,
+}
+
+/// An attempt at matching an [`Automata`] starting from a given position of an expression.
+struct Attempt {
+    /// Byte offset of the expression where this attempt started.
+    start: usize,
+    /// Byte offset of the end of the longest match found by this attempt.
+    end: Option<usize>,
+    /// Byte offset of the furthest position where this attempt still had live states.
+    progress: usize,
+    /// States this attempt currently occupies.
+    states: Vec<StatePtr>,
+    /// States this attempt occupied before all of them died out.
+    stuck: Vec<StatePtr>,
 }
 
 impl Automata {
+    /// Returns an [`Automata`] from `start` to `end`, with every other setting at its default.
+    fn new(start: StatePtr, end: Rc<RefCell<TrivialState>>) -> Self {
+        Automata {
+            start,
+            end,
+            anchored: false,
+            backtracking: false,
+            semantics: MatchSemantics::default(),
+            word_chars: None,
+            prefix: None,
+            reversed: None,
+            predicates: 0,
+            transitions: Cell::new(0),
+            attempts: Cell::new(0),
+        }
+    }
+
+    /// Returns an [`Automata`] matching only the empty expression.
+    pub fn empty() -> Self {
+        let end = TrivialState::make_rc();
+        let start = TrivialState::make_rc();
+        start.borrow_mut().push(end.clone());
+
+        Automata::new(start, end)
+    }
+
+    /// Returns an [`Automata`] matching no expression at all, not even the empty one, as its end state is unreachable.
+    pub fn never() -> Self {
+        let end = TrivialState::make_rc();
+        let start = TrivialState::make_rc();
+
+        Automata::new(start, end)
+    }
+
     pub fn from_token(token: char) -> Self {
         let end = TrivialState::make_rc();
         let start = Rc::new(RefCell::new(TokenState::new(token, end.clone())));
 
-        Automata { start, end }
+        Automata::new(start, end)
     }
 
-    pub fn from_lambda<F: Fn(char) -> bool + 'static>(lambda: F) -> Self {
+    pub fn from_lambda<F: Fn(char) -> bool + 'static>(lambda: F, kind: PredicateKind) -> Self {
         let end = TrivialState::make_rc();
-        let start = Rc::new(RefCell::new(LambdaState::new(lambda, end.clone())));
+        let start = Rc::new(RefCell::new(LambdaState::new(lambda, kind, end.clone())));
 
-        Automata { start, end }
+        Automata::new(start, end)
     }
 
-    pub fn from_closure(closure: Box<dyn Fn(char) -> bool>) -> Self {
+    pub fn from_closure(closure: Box<dyn Fn(char) -> bool>, kind: PredicateKind) -> Self {
         let end = TrivialState::make_rc();
-        let start = Rc::new(RefCell::new(LambdaState::new_with_box(closure, end.clone())));
+        let start = Rc::new(RefCell::new(LambdaState::new_with_box(closure, kind, end.clone())));
 
-        Automata { start, end }
+        Automata::new(start, end)
+    }
+
+    /// Returns an [`Automata`] calling the capturing group of index `index`, or the whole regular expression if `index` is
+    /// 0, which can only be matched by the backtracking engine.
+    pub fn from_call(index: usize) -> Self {
+        let end = TrivialState::make_rc();
+        let start = Rc::new(RefCell::new(CallState::new(index, end.clone())));
+
+        Automata::new(start, end)
+    }
+
+    /// Returns an [`Automata`] matching the substring last captured by the capturing group of index `index`, which can
+    /// only be matched by the backtracking engine.
+    pub fn from_backreference(index: usize) -> Self {
+        let end = TrivialState::make_rc();
+        let start = Rc::new(RefCell::new(BackrefState::new(index, end.clone())));
+
+        Automata::new(start, end)
     }
 
     pub fn from_anchor(anchor: Anchor) -> Self {
         let end = TrivialState::make_rc();
         let start = Rc::new(RefCell::new(AnchorState::new(anchor, end.clone())));
 
-        Automata { start, end }
+        Automata::new(start, end)
     }
 
     pub fn concat(mut self, other: Automata) -> Self {
@@ -63,6 +221,12 @@ impl Automata {
         self
     }
 
+    /// Repeats the [`Automata`] any number of times.
+    ///
+    /// Unless the [`Automata`] matches the empty string, each repetition loops back onto the start state of the closure,
+    /// so that the backtracking engine sees the closure entered again at the same position, e.g. `a*` within `(a*|b)+`,
+    /// as the same state. Otherwise, repetitions loop onto the start of the [`Automata`] itself, as in `(x+)?`, which
+    /// keeps the preference for repeating over stopping.
     pub fn closure(mut self) -> Self {
         let start = TrivialState::make_rc();
         let end = TrivialState::make_rc();
@@ -70,8 +234,12 @@ impl Automata {
         start.borrow_mut().push(self.start.clone());
         start.borrow_mut().push(end.clone());
 
-        self.push_to_end(self.start.clone());
-        self.push_to_end(end.clone());
+        if self.matches_empty() {
+            self.push_to_end(self.start.clone());
+            self.push_to_end(end.clone());
+        } else {
+            self.push_to_end(start.clone());
+        }
 
         self.start = start;
         self.end = end;
@@ -106,7 +274,294 @@ impl Automata {
         self
     }
 
+    /// Same as [`Automata::closure`], but preferring to stop repeating over repeating once more, which only the
+    /// backtracking engine follows, as in [`MatchSemantics::PerlLeftmostFirst`].
+    pub fn closure_lazy(mut self) -> Self {
+        let start = TrivialState::make_rc();
+        let end = TrivialState::make_rc();
+
+        start.borrow_mut().push(end.clone());
+        start.borrow_mut().push(self.start.clone());
+
+        if self.matches_empty() {
+            self.push_to_end(end.clone());
+            self.push_to_end(self.start.clone());
+        } else {
+            self.push_to_end(start.clone());
+        }
+
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    /// Same as [`Automata::optional`], but preferring to skip over matching, as in [`Automata::closure_lazy`].
+    pub fn optional_lazy(mut self) -> Self {
+        let start = TrivialState::make_rc();
+        let end = TrivialState::make_rc();
+
+        start.borrow_mut().push(end.clone());
+        start.borrow_mut().push(self.start.clone());
+
+        self.push_to_end(end.clone());
+
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    /// Same as [`Automata::plus`], but preferring to stop repeating over repeating once more, as in
+    /// [`Automata::closure_lazy`].
+    pub fn plus_lazy(mut self) -> Self {
+        let start = TrivialState::make_rc();
+        let end = TrivialState::make_rc();
+
+        start.borrow_mut().push(self.start.clone());
+
+        self.push_to_end(end.clone());
+        self.push_to_end(self.start.clone());
+
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    /// Wraps the [`Automata`] in a capturing group with index `index`, whose span is recorded in the capture slots
+    /// `2 * index` and `2 * index + 1`.
+    pub fn capture(mut self, index: usize) -> Self {
+        let end = TrivialState::make_rc();
+        let close = Rc::new(RefCell::new(GroupState::new(2 * index + 1, end.clone())));
+        let open = Rc::new(RefCell::new(GroupState::new(2 * index, self.start.clone())));
+
+        self.push_to_end(close);
+
+        self.start = open;
+        self.end = end;
+        self
+    }
+
+    /// Marks the [`Automata`] as only able to match at the start of an expression, so searches need not attempt matching
+    /// from any other position.
+    pub fn anchored(mut self, anchored: bool) -> Self {
+        self.anchored = anchored;
+        self
+    }
+
+    /// Marks the [`Automata`] as matched by the backtracking engine, which is required to follow calls.
+    ///
+    /// The backtracking engine explores paths in order of priority, keeping the longest match with
+    /// [`MatchSemantics::PosixLeftmostLongest`], so it agrees with the simulation of the NFA on every [`Automata`] the
+    /// latter supports.
+    pub fn backtracking(mut self, backtracking: bool) -> Self {
+        self.backtracking = backtracking;
+        self
+    }
+
+    /// Sets which match is reported among those starting at the leftmost position.
+    ///
+    /// Only the backtracking engine follows paths in order of priority, so it is required to report the first match
+    /// found, as in [`MatchSemantics::PerlLeftmostFirst`].
+    pub fn semantics(mut self, semantics: MatchSemantics) -> Self {
+        self.semantics = semantics;
+        self
+    }
+
+    /// Sets the predicate defining word characters, which determines where word boundaries are.
+    pub fn word_chars(mut self, word_chars: Rc<dyn Fn(char) -> bool>) -> Self {
+        self.word_chars = Some(word_chars);
+        self
+    }
+
+    /// Sets the string which every match starts with, so that searches skip directly to its occurrences instead of
+    /// attempting matching from every position.
+    pub fn prefix(mut self, prefix: Option<String>) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Numbers the distinct predicates of the [`LambdaState`]s, so that searches evaluate each of them once per character
+    /// and reuse the result for every state sharing it, e.g. `\d` in `\d+\d` or the same state held by several attempts.
+    ///
+    /// The numbering is only valid within this [`Automata`], so this is to be set once it is otherwise fully built.
+    pub fn batch_predicates(mut self) -> Self {
+        let mut kinds: Vec<PredicateKind> = vec![];
+        for state in self.states() {
+            let state = state.borrow();
+            let Some(lambda) = state.as_any().downcast_ref::<LambdaState>() else {
+                continue;
+            };
+
+            // Opaque predicates of the same kind may accept different characters, so they are never shared
+            let index = kinds
+                .iter()
+                .position(|kind| !kind.is_opaque() && kind == lambda.kind())
+                .unwrap_or_else(|| {
+                    kinds.push(lambda.kind().clone());
+                    kinds.len() - 1
+                });
+            lambda.set_predicate(index);
+        }
+
+        self.predicates = kinds.len();
+        self
+    }
+
+    /// Sets whether every match ends at the end of an expression, in which case searches match the reversed [`Automata`]
+    /// backwards from the end of the expression, rather than attempting matching from every position.
+    ///
+    /// The [`Automata`] is reversed as it currently is, so this is to be set once it is otherwise fully built.
+    pub fn anchored_end(mut self, anchored_end: bool) -> Self {
+        self.reversed = if anchored_end { self.reverse().map(Box::new) } else { None };
+        self
+    }
+
+    /// Returns an [`Automata`] matching the reversal of the expressions matched by this [`Automata`], or `None` if it
+    /// contains calls or backreferences, which cannot be reversed, or if its end state is unreachable, e.g. for
+    /// [`Automata::never`].
+    ///
+    /// Anchors are kept as they are, so the reversed [`Automata`] is meant to be matched against an expression scanned
+    /// backwards, with anchors satisfied where they are in the expression. Capturing groups are dropped, as it is only
+    /// meant to locate matches.
+    pub fn reverse(&self) -> Option<Automata> {
+        let states = self.states();
+        let indices: HashMap<usize, usize> = states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| (address(state), i))
+            .collect();
+        let index = |state: &StatePtr| indices.get(&address(state)).copied();
+
+        // Every state becomes a trivial state, reached from the reversal of each of its successors
+        let reversed: Vec<Rc<RefCell<TrivialState>>> = states.iter().map(|_| TrivialState::make_rc()).collect();
+        for (state, target) in states.iter().zip(&reversed) {
+            // The state is released before any state is mutably borrowed, as no borrow is held across mutations
+            let (edge, dest) = {
+                let state = state.borrow();
+                let any = state.as_any();
+                let target = target.clone() as StatePtr;
+
+                let edge: StatePtr = if any.is::<CallState>() || any.is::<BackrefState>() {
+                    return None;
+                } else if let Some(token) = state.get_token() {
+                    Rc::new(RefCell::new(TokenState::new(token, target)))
+                } else if let Some(lambda) = any.downcast_ref::<LambdaState>() {
+                    let kind = state.get_kind()?;
+                    Rc::new(RefCell::new(LambdaState::new_with_rc(lambda.lambda(), kind, target)))
+                } else if let Some(anchor) = any.downcast_ref::<AnchorState>() {
+                    Rc::new(RefCell::new(AnchorState::new(anchor.anchor(), target)))
+                } else {
+                    target
+                };
+
+                (edge, state.get_dest().to_vec())
+            };
+
+            for dest in dest {
+                reversed[index(&dest)?].borrow_mut().push(edge.clone());
+            }
+        }
+
+        // The start state may have predecessors, so the reversed end state is a fresh one without any successor
+        let end = TrivialState::make_rc();
+        reversed[0].borrow_mut().push(end.clone());
+        let start = reversed[index(&self.get_end())?].clone();
+
+        let automata = Automata { word_chars: self.word_chars.clone(), ..Automata::new(start, end) };
+        Some(automata.simplify())
+    }
+
+    /// Removes the [`TrivialState`]s which only lead to a single state, by redirecting their predecessors to that state,
+    /// without changing which expressions are matched.
+    pub fn simplify(mut self) -> Self {
+        /// Returns the state reached from `state` by skipping trivial states other than `end`, remembering it in `skipped`
+        /// for every state along the way, so that long chains of trivial states, e.g. from nested optional repetitions,
+        /// are walked once.
+        fn skip_trivial(state: &StatePtr, end: &StatePtr, skipped: &mut HashMap<usize, StatePtr>) -> StatePtr {
+            let mut path = vec![state.clone()];
+            let mut on_path = HashSet::from([address(state)]);
+            let target = loop {
+                let current = path[path.len() - 1].clone();
+                if let Some(target) = skipped.get(&address(&current)) {
+                    break target.clone();
+                }
+
+                let next = match current.borrow().get_dest() {
+                    [next] if current.borrow().as_any().is::<TrivialState>() && !Rc::ptr_eq(&current, end) => Some(next.clone()),
+                    _ => None,
+                };
+                let Some(next) = next else {
+                    break current;
+                };
+
+                // Stop short of looping around a cycle of trivial states
+                if !on_path.insert(address(&next)) {
+                    break current;
+                }
+                path.push(next);
+            };
+
+            for state in path {
+                skipped.insert(address(&state), target.clone());
+            }
+            target
+        }
+
+        // Compute all redirections before applying any, as a state may be its own successor
+        let end = self.get_end();
+        let mut skipped = HashMap::new();
+        let redirections: Vec<(StatePtr, Vec<StatePtr>)> = self
+            .states()
+            .into_iter()
+            .map(|state| {
+                let dest = state
+                    .borrow()
+                    .get_dest()
+                    .iter()
+                    .map(|dest| skip_trivial(dest, &end, &mut skipped))
+                    .collect();
+                (state, dest)
+            })
+            .collect();
+
+        for (state, dest) in redirections {
+            state.borrow_mut().get_dest_mut().clone_from_slice(&dest);
+        }
+
+        // The end state is never skipped, even if it has successors, e.g. when looping onto itself
+        self.start = skip_trivial(&self.start, &end, &mut skipped);
+        self
+    }
+
+    /// Redirects every state reachable from the start state, and from that of its reversal, to a fresh state without
+    /// successors, so that dropping them does not recurse along the whole [`Automata`], which overflows the stack for
+    /// long ones, e.g. expanded from large range quantifiers, and so that cycles, e.g. from closures, are freed too.
+    ///
+    /// The states are left unusable, so this is only meant for an [`Automata`] about to be dropped, whose states are not
+    /// shared with any other [`Automata`] in use.
+    pub fn unlink(&mut self) {
+        let sink: StatePtr = TrivialState::make_rc();
+        for state in self.states() {
+            for dest in state.borrow_mut().get_dest_mut() {
+                *dest = sink.clone();
+            }
+        }
+
+        if let Some(reversed) = &mut self.reversed {
+            reversed.unlink();
+        }
+    }
+
+    /// Returns the number of states reachable from the start state.
+    pub fn state_count(&self) -> usize {
+        self.states().len()
+    }
+
     pub fn full_match(&self, expr: &str) -> bool {
+        // The first match found by backtracking need not be the longest, e.g. with lazy quantifiers
+        if self.backtracking {
+            return self.backtrack(expr, 0, &|end| end == expr.len()).is_some();
+        }
+
         if let Some(matched) = self.greedy_search(expr) {
             matched.len() == expr.len()
         } else {
@@ -115,60 +570,879 @@ impl Automata {
     }
 
     pub fn greedy_search(&self, expr: &str) -> Option<String> {
-        self.search(expr).0
+        // max_by_key will return the last maximal element. Reverse to get the first.
+        self.search(expr)
+            .into_iter()
+            .rev()
+            .max_by_key(|(l, r)| r - l)
+            .map(|(l, r)| String::from(&expr[l..r]))
     }
 
     pub fn global_search(&self, expr: &str) -> Vec<String> {
-        self.search(expr).1
+        self.search(expr)
+            .into_iter()
+            .map(|(l, r)| String::from(&expr[l..r]))
+            .collect()
+    }
+
+    /// Returns the number of state transitions performed by all searches so far.
+    pub fn transitions(&self) -> usize {
+        self.transitions.get()
+    }
+
+    /// Returns the number of attempts at matching from a given position made by all searches so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts.get()
+    }
+
+    /// Resets the number of state transitions and attempts counted so far.
+    pub fn reset_counters(&self) {
+        self.transitions.set(0);
+        self.attempts.set(0);
+    }
+
+    pub fn explain(&self, expr: &str) -> MatchTrace {
+        // The backtracking engine does not keep track of how far attempts progressed
+        if self.backtracking {
+            let span = self.find_at(expr, 0);
+            let (start, offset) = span.unwrap_or_default();
+            return MatchTrace { matched: span.is_some(), start, offset, expected: vec![] };
+        }
+
+        let attempts = self.run(expr);
+        let matched = attempts.iter().any(|a| a.end.is_some());
+
+        // max_by_key will return the last maximal element. Reverse to get the first.
+        let furthest = attempts.iter().rev().max_by_key(|a| a.progress - a.start);
+        furthest.map_or(MatchTrace { matched, start: 0, offset: 0, expected: vec![] }, |a| {
+            let stuck = if a.states.is_empty() { &a.stuck } else { &a.states };
+            let mut expected = vec![];
+            for kind in stuck.iter().filter_map(|s| s.borrow().get_kind()) {
+                if !expected.contains(&kind) {
+                    expected.push(kind);
+                }
+            }
+
+            MatchTrace { matched, start: a.start, offset: a.progress, expected }
+        })
+    }
+
+    /// Returns every path through the [`Automata`] matching `expr` exactly over `span`, each as the sequence of ids of the
+    /// states it traverses, from the start state to the end state, e.g. to visualize how `expr` was consumed.
+    ///
+    /// The id of a state is its index in breadth-first order from the start state. Paths are enumerated exhaustively,
+    /// separately from matching, so that matching is not slowed down, but there may be exponentially many of them.
+    /// Recursions and backreferences are never traversed.
+    pub fn match_paths(&self, expr: &str, span: Span) -> Vec<Vec<usize>> {
+        /// Records into `paths` every path extending `path` from `state` at the byte offset `position` of `expr`.
+        fn traverse(
+            automata: &Automata, expr: &str, end: usize, state: &StatePtr, position: usize, path: &mut Vec<(StatePtr, usize)>,
+            paths: &mut Vec<Vec<StatePtr>>,
+        ) {
+            // A state already traversed at the same offset closes a cycle of epsilon transitions
+            if path
+                .iter()
+                .any(|(s, p)| Rc::ptr_eq(s, state) && *p == position)
+            {
+                return;
+            }
+
+            path.push((state.clone(), position));
+            if Rc::ptr_eq(state, &automata.get_end()) && position == end {
+                paths.push(path.iter().map(|(s, _)| s.clone()).collect());
+            }
+
+            let current = expr[..position].chars().next_back();
+            let next = expr[position..].chars().next();
+            let anchors = Anchors::at(position, expr.len(), current, next, automata.is_word_char());
+            let state_locked = state.borrow();
+            for dest in state_locked.epsilon(&anchors) {
+                traverse(automata, expr, end, dest, position, path, paths);
+            }
+            if let Some(c) = next.filter(|_| position < end) {
+                if let Some(dest) = state_locked.transition(c) {
+                    traverse(automata, expr, end, &dest, position + c.len_utf8(), path, paths);
+                }
+            }
+            path.pop();
+        }
+
+        let mut paths = vec![];
+        traverse(self, expr, span.1, &self.start, span.0, &mut vec![], &mut paths);
+
+        let states = self.states();
+        let id = |state: &StatePtr| {
+            states
+                .iter()
+                .position(|s| Rc::ptr_eq(s, state))
+                .expect("every state should be reachable from the start state")
+        };
+        paths
+            .iter()
+            .map(|path| path.iter().map(id).collect())
+            .collect()
+    }
+
+    /// Returns the spans of all non-overlapping matches in `expr`, from left to right.
+    ///
+    /// Attempts are made, and thus matches are found, in order of their starting offset, so the spans are ordered.
+    pub fn search(&self, expr: &str) -> Vec<Span> {
+        // Every match ends at the end of the expression, so no other match can follow the leftmost one
+        if self.reversed.is_some() && !self.anchored {
+            return self.find_at(expr, 0).into_iter().collect();
+        }
+
+        // Matches start with the prefix, so none is empty and the next one starts at or after the end of the previous one
+        if self.prefix.is_some() {
+            let mut results = vec![];
+            let mut from = 0;
+            while let Some((left, right)) = self.find_at(expr, from) {
+                results.push((left, right));
+                from = right;
+            }
+
+            return results;
+        }
+
+        let matches: Vec<Span> = if self.backtracking {
+            positions(expr, 0)
+                .take(if self.anchored { 1 } else { usize::MAX })
+                .filter_map(|from| self.match_at(expr, from))
+                .collect()
+        } else {
+            self.run(expr)
+                .into_iter()
+                .filter_map(|a| a.end.map(|r| (a.start, r)))
+                .collect()
+        };
+
+        let mut results = vec![];
+        let mut results_rightmost = None;
+        for (left, right) in matches {
+            // Check if we overlap anything already in our results
+            if results_rightmost.is_none_or(|rm| rm <= left && rm < right) {
+                results.push((left, right));
+                results_rightmost = Some(right);
+            }
+        }
+
+        results
+    }
+
+    /// Returns the span of the leftmost match in `expr` starting at or after the byte offset `from`, preferring the longest
+    /// match among those starting at the same offset, or `None` if there is no such match.
+    ///
+    /// Unlike [`Automata::search`], the scan stops as soon as the leftmost match is settled.
+    pub fn find_at(&self, expr: &str, from: usize) -> Option<Span> {
+        self.find_at_cached(expr, from, &mut SearchCache::default())
+    }
+
+    /// Returns the span of the leftmost match in `expr` starting at or after the byte offset `from`, as in
+    /// [`Automata::find_at`], reusing the buffers of `cache` rather than allocating new ones.
+    pub fn find_at_cached(&self, expr: &str, from: usize, cache: &mut SearchCache) -> Option<Span> {
+        if self.anchored && from > 0 {
+            return None;
+        }
+
+        if let Some(reversed) = self.reversed.as_deref().filter(|_| !self.anchored) {
+            return self.find_backwards(reversed, expr, from);
+        }
+
+        if let Some(prefix) = self.prefix.as_deref().filter(|_| !self.anchored) {
+            let mut from = from;
+            loop {
+                let start = from + find_substring(&expr[from..], prefix)?;
+                if let Some(span) = self.match_at(expr, start) {
+                    return Some(span);
+                }
+
+                from = start + prefix.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+
+        if self.backtracking {
+            return positions(expr, from)
+                .take(if self.anchored { 1 } else { usize::MAX })
+                .find_map(|start| self.match_at(expr, start));
+        }
+
+        let mut attempts = mem::take(&mut cache.attempts);
+        let mut found = None;
+        for transition in transition_iter(expr, from, self.is_word_char()) {
+            match transition {
+                TransitionItem::Char(c) => {
+                    clear_predicates(&mut cache.predicates, self.predicates);
+                    for attempt in &mut attempts {
+                        self.transitions
+                            .set(self.transitions.get() + attempt.states.len());
+
+                        let mut states = cache.buffer();
+                        states.extend(
+                            attempt
+                                .states
+                                .iter()
+                                .filter_map(|s| s.borrow().transition_batched(c, &mut cache.predicates)),
+                        );
+                        cache.recycle(mem::replace(&mut attempt.states, states));
+                    }
+                }
+                TransitionItem::Anchors((r, anchors)) => {
+                    // An attempt starting later can never be leftmost once an attempt has matched
+                    if attempts.first().is_none_or(|a| a.end.is_none()) && (!self.anchored || attempts.is_empty()) {
+                        self.attempts.set(self.attempts.get() + 1);
+                        let mut states = cache.buffer();
+                        states.push(self.start.clone());
+                        attempts.push(Attempt { start: r, end: None, progress: r, states, stuck: vec![] });
+                    }
+
+                    for attempt in &mut attempts {
+                        let mut states = cache.buffer();
+                        exhaust_epsilons_into(&attempt.states, &anchors, &mut states, &mut cache.visited);
+                        cache.recycle(mem::replace(&mut attempt.states, states));
+
+                        if attempt.states.contains(&self.get_end()) {
+                            attempt.end = Some(r);
+                        }
+                    }
+
+                    attempts.retain_mut(|a| {
+                        let alive = !a.states.is_empty() || a.end.is_some();
+                        if !alive {
+                            cache.recycle(mem::take(&mut a.states));
+                        }
+                        alive
+                    });
+                    prune_dominated(&mut attempts);
+                    if let Some(Attempt { start, end: Some(end), states, .. }) = attempts.first() {
+                        // The leftmost match is settled once it cannot be extended any further
+                        if states.is_empty() {
+                            found = Some((*start, *end));
+                            break;
+                        }
+
+                        attempts.truncate(1);
+                    }
+                }
+            }
+        }
+
+        // Attempts still unmatched once the input is exhausted never match, so the leftmost match is the first one matched
+        if found.is_none() {
+            found = attempts.iter().find_map(|a| a.end.map(|r| (a.start, r)));
+        }
+
+        for attempt in attempts.drain(..) {
+            cache.recycle(attempt.states);
+        }
+        cache.attempts = attempts;
+
+        found
     }
 
-    fn search(&self, expr: &str) -> (Option<String>, Vec<String>) {
-        // First element is the end of the best match, second are the states
-        let mut current_states: Vec<(Option<usize>, Vec<StatePtr>)> = vec![];
+    /// Returns `true` if some substring of `expr` matches the [`Automata`].
+    ///
+    /// Unlike [`Automata::find_at`], the scan stops as soon as any match is found, without settling which match is the
+    /// leftmost nor extending it, e.g. once a branch of an alternation accepts, the other branches are not explored any
+    /// further. Attempts from every position share a single set of states, as it does not matter which one matches.
+    pub fn is_match(&self, expr: &str) -> bool {
+        if self.backtracking || self.prefix.is_some() || self.reversed.is_some() {
+            return self.find_at(expr, 0).is_some();
+        }
 
-        for transition in transition_iter(expr) {
+        let mut states: Vec<StatePtr> = vec![];
+        let mut predicates = vec![];
+        for transition in transition_iter(expr, 0, self.is_word_char()) {
             match transition {
                 TransitionItem::Char(c) => {
-                    for (_, states) in &mut current_states {
-                        *states = states
+                    self.transitions.set(self.transitions.get() + states.len());
+
+                    clear_predicates(&mut predicates, self.predicates);
+                    let mut next = vec![];
+                    for state in states
+                        .iter()
+                        .filter_map(|s| s.borrow().transition_batched(c, &mut predicates))
+                    {
+                        if !next.contains(&state) {
+                            next.push(state);
+                        }
+                    }
+                    states = next;
+                }
+                TransitionItem::Anchors((r, anchors)) => {
+                    if !self.anchored || r == 0 {
+                        self.attempts.set(self.attempts.get() + 1);
+                        states.push(self.start.clone());
+                    }
+
+                    states = exhaust_epsilons(&states, &anchors);
+                    if states.contains(&self.get_end()) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Returns the span of the leftmost match in `expr` starting at or after the byte offset `from`, as in
+    /// [`Automata::find_at`], by matching `reversed`, the reversal of the [`Automata`], backwards from the end of `expr`.
+    ///
+    /// Every match is assumed to end at the end of `expr`, so the leftmost match is the longest backwards match.
+    fn find_backwards(&self, reversed: &Automata, expr: &str, from: usize) -> Option<Span> {
+        self.attempts.set(self.attempts.get() + 1);
+        let mut states = vec![reversed.start.clone()];
+        let mut start = None;
+        let mut position = expr.len();
+        loop {
+            let current = expr[..position].chars().next_back();
+            let next = expr[position..].chars().next();
+            let anchors = Anchors::at(position, expr.len(), current, next, self.is_word_char());
+            states = exhaust_epsilons(&states, &anchors);
+
+            if states.contains(&reversed.get_end()) {
+                start = Some(position);
+            }
+
+            match current {
+                Some(c) if position > from && !states.is_empty() => {
+                    self.transitions.set(self.transitions.get() + states.len());
+                    states = states
+                        .iter()
+                        .filter_map(|s| s.borrow().transition(c))
+                        .collect();
+                    position -= c.len_utf8();
+                }
+                _ => break,
+            }
+        }
+
+        start.map(|l| (l, expr.len()))
+    }
+
+    /// Returns the span of the longest match in `expr` starting exactly at the byte offset `from`, or `None` if there is no
+    /// such match.
+    pub fn match_at(&self, expr: &str, from: usize) -> Option<Span> {
+        if self.anchored && from > 0 {
+            return None;
+        }
+
+        if self.backtracking {
+            return self
+                .backtrack(expr, from, &|_| true)
+                .map(|(end, _)| (from, end));
+        }
+
+        self.attempts.set(self.attempts.get() + 1);
+        let mut states = vec![self.start.clone()];
+        let mut end = None;
+        for transition in transition_iter(expr, from, self.is_word_char()) {
+            match transition {
+                TransitionItem::Char(c) => {
+                    self.transitions.set(self.transitions.get() + states.len());
+                    states = states
+                        .iter()
+                        .filter_map(|s| s.borrow().transition(c))
+                        .collect();
+                }
+                TransitionItem::Anchors((r, anchors)) => {
+                    states = exhaust_epsilons(&states, &anchors);
+
+                    if states.contains(&self.get_end()) {
+                        end = Some(r);
+                    }
+
+                    if states.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        end.map(|r| (from, r))
+    }
+
+    /// Returns the end of the shortest match in `expr` starting exactly at the byte offset `from`, or `None` if there is no
+    /// such match.
+    pub fn shortest_match_at(&self, expr: &str, from: usize) -> Option<usize> {
+        if self.anchored && from > 0 {
+            return None;
+        }
+
+        if self.backtracking {
+            return positions(expr, from).find(|&end| self.backtrack(expr, from, &|e| e == end).is_some());
+        }
+
+        self.attempts.set(self.attempts.get() + 1);
+        let mut states = vec![self.start.clone()];
+        for transition in transition_iter(expr, from, self.is_word_char()) {
+            match transition {
+                TransitionItem::Char(c) => {
+                    self.transitions.set(self.transitions.get() + states.len());
+                    states = states
+                        .iter()
+                        .filter_map(|s| s.borrow().transition(c))
+                        .collect();
+                }
+                TransitionItem::Anchors((r, anchors)) => {
+                    states = exhaust_epsilons(&states, &anchors);
+
+                    // The first end reached is the shortest match, so there is no need to look any further
+                    if states.contains(&self.get_end()) {
+                        return Some(r);
+                    }
+
+                    if states.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the spans of the leftmost match in `expr` and of each of its `group_count` capturing groups, or `None` if
+    /// there is no match.
+    ///
+    /// A capturing group which does not participate in the match has no span. A capturing group which participates more
+    /// than once, e.g. within a closure, has the span of its last participation, which is only empty if every
+    /// participation is, as nothing is repeated after an empty repetition.
+    pub fn captures(&self, expr: &str, group_count: usize) -> Option<Vec<Option<Span>>> {
+        // The match is located exactly as `find_at` would, so that both agree on the leftmost match
+        let span = self.find_at(expr, 0)?;
+        self.captures_at(expr, span, group_count)
+    }
+
+    /// Returns the spans captured by the match spanning `span` in `expr`, in the same format as [`Automata::captures`], or
+    /// `None` if there is no match spanning `span`.
+    ///
+    /// Every path through the [`Automata`] from the start of `span` is explored until one reaches its end, so `None` is
+    /// never returned for a span located by [`Automata::find_at`], which only reports matches along such a path.
+    pub fn captures_at(&self, expr: &str, (left, right): Span, group_count: usize) -> Option<Vec<Option<Span>>> {
+        let mut slots = self.capture_slots(expr, (left, right), group_count)?;
+        slots[0] = Some(left);
+        slots[1] = Some(right);
+
+        Some(slots.chunks(2).map(|slot| slot[0].zip(slot[1])).collect())
+    }
+
+    /// Replays the match spanning `span` in `expr` with the backtracking engine, and returns the capture slots of the path
+    /// to it preferred according to the [`MatchSemantics`].
+    fn capture_slots(&self, expr: &str, (left, right): Span, group_count: usize) -> Option<Vec<Option<usize>>> {
+        // Choosing the captures of the longest match requires comparing every path to it, which backtracking does
+        let (_, mut slots) = self.backtrack(expr, left, &|end| end == right)?;
+        slots.resize(2 * (group_count + 1), None);
+
+        Some(slots)
+    }
+
+    /// Returns the match in `expr` starting at the byte offset `from` whose end satisfies `accept`, along with its capture
+    /// slots, by exploring paths through the [`Automata`] depth-first, in order of priority.
+    ///
+    /// With [`MatchSemantics::PerlLeftmostFirst`], the match is the first one found. With
+    /// [`MatchSemantics::PosixLeftmostLongest`], it is the longest one, and the capture slots are those of the path to it
+    /// where each capturing group in turn spans the leftmost and then longest substring.
+    ///
+    /// Calls are followed using a call stack, and a call which would call the same capturing group again without
+    /// consuming anything fails, as it would never return. A backreference to a capturing group which has not captured
+    /// anything yet fails.
+    ///
+    /// A state is only explored once per position, call stack and capture slots read by backreferences, so that the match
+    /// does not depend on any other capturing group. With [`MatchSemantics::PosixLeftmostLongest`], it is explored again
+    /// whenever it is reached with capture slots preferred to those it was explored with, as they stand at that point,
+    /// e.g. including those of an earlier repetition of a group which a later one may capture again.
+    fn backtrack(&self, expr: &str, from: usize, accept: &dyn Fn(usize) -> bool) -> Option<(usize, Vec<Option<usize>>)> {
+        // The states called by each capturing group index, the whole automata being called by index 0
+        let states = self.states();
+        let mut targets = vec![Some(self.start.clone())];
+        let mut slot_count = 2;
+        for state in &states {
+            if let Some(slot) = state.borrow().get_slot() {
+                slot_count = slot_count.max(slot + 1);
+                if slot % 2 == 0 {
+                    targets.resize(targets.len().max(slot / 2 + 1), None);
+                    targets[slot / 2] = Some(state.clone());
+                }
+            }
+        }
+
+        // Capture slots only change which path matches if they are matched again by backreferences
+        let mut keyed = vec![false; slot_count];
+        for state in &states {
+            if let Some(backref) = state.borrow().as_any().downcast_ref::<BackrefState>() {
+                for slot in [2 * backref.index(), 2 * backref.index() + 1]
+                    .into_iter()
+                    .filter(|&s| s < slot_count)
+                {
+                    keyed[slot] = true;
+                }
+            }
+        }
+        let slots_key = |slots: &[Option<usize>]| -> Vec<Option<usize>> {
+            slots
+                .iter()
+                .zip(&keyed)
+                .filter_map(|(slot, keyed)| keyed.then_some(*slot))
+                .collect()
+        };
+
+        // The slots saved by a call are restored once it returns, so they are all part of the key when slots are compared
+        let posix = self.semantics == MatchSemantics::PosixLeftmostLongest;
+        let call_slots_key = |slots: &[Option<usize>]| if posix { slots.to_vec() } else { slots_key(slots) };
+
+        let end = address(&self.get_end());
+        let mut best: Option<(usize, Vec<Option<usize>>)> = None;
+        let mut visited: HashMap<_, Vec<Option<usize>>> = HashMap::new();
+        self.attempts.set(self.attempts.get() + 1);
+        let mut branches =
+            vec![Branch { state: self.start.clone(), position: from, slots: vec![None; slot_count], calls: vec![] }];
+        while let Some(Branch { state, position, mut slots, mut calls }) = branches.pop() {
+            // A state reached again at the same position, with the same calls and keyed slots, was first reached along a
+            // path of higher priority, from which every match was already explored. This prevents looping around epsilon
+            // cycles, and stops a repetition which matched the empty string from repeating again whatever it captured,
+            // e.g. `(a??)+` matches the empty string in `aa`. With `MatchSemantics::PosixLeftmostLongest`, the same matches
+            // are explored again only if the slots are preferred to those they were explored with, so that each state is
+            // explored a bounded number of times per position rather than once per distinct capture
+            let calls_key: Vec<_> = calls
+                .iter()
+                .map(|call| (call.index, call.start, address(&call.ret), call_slots_key(&call.slots)))
+                .collect();
+            match visited.entry((address(&state), position, slots_key(&slots), calls_key)) {
+                Entry::Occupied(mut explored) if posix && posix_prefers(&slots, explored.get()) => {
+                    explored.insert(slots.clone());
+                }
+                Entry::Occupied(_) => continue,
+                Entry::Vacant(unexplored) => {
+                    unexplored.insert(if posix { slots.clone() } else { vec![] });
+                }
+            }
+
+            let state_locked = state.borrow();
+            if let Some(slot) = state_locked.get_slot() {
+                slots[slot] = Some(position);
+            }
+
+            let returning = calls.last().is_some_and(|call| match call.index {
+                0 => address(&state) == end,
+                index => state_locked.get_slot() == Some(2 * index + 1),
+            });
+            if returning {
+                // Captures made within a call are discarded once it returns
+                let Call { ret, slots, .. } = calls
+                    .pop()
+                    .expect("a returning call should be on the call stack");
+                branches.push(Branch { state: ret, position, slots, calls });
+                continue;
+            }
+
+            if address(&state) == end {
+                if accept(position) {
+                    if self.semantics == MatchSemantics::PerlLeftmostFirst {
+                        return Some((position, slots));
+                    }
+
+                    let better = best.as_ref().is_none_or(|(best_end, best_slots)| {
+                        position > *best_end || (position == *best_end && posix_prefers(&slots, best_slots))
+                    });
+                    if better {
+                        best = Some((position, slots));
+                    }
+                }
+                continue;
+            }
+
+            if let Some(call) = state_locked.as_any().downcast_ref::<CallState>() {
+                let index = call.index();
+                let recursing = calls
+                    .iter()
+                    .any(|c| c.index == index && c.start == position);
+                if let Some(target) = targets.get(index).cloned().flatten().filter(|_| !recursing) {
+                    let ret = call.get_dest()[0].clone();
+                    calls.push(Call { index, start: position, ret, slots: slots.clone() });
+                    branches.push(Branch { state: target, position, slots, calls });
+                }
+                continue;
+            }
+
+            if let Some(backref) = state_locked.as_any().downcast_ref::<BackrefState>() {
+                let index = backref.index();
+                let slot = |slot: usize| slots.get(slot).copied().flatten();
+                let captured = slot(2 * index)
+                    .zip(slot(2 * index + 1))
+                    .filter(|(l, r)| l <= r);
+                if let Some(text) = captured.map(|(l, r)| &expr[l..r]) {
+                    if expr[position..].starts_with(text) {
+                        self.transitions.set(self.transitions.get() + 1);
+                        let dest = backref.get_dest()[0].clone();
+                        branches.push(Branch { state: dest, position: position + text.len(), slots, calls });
+                    }
+                }
+                continue;
+            }
+
+            let current = expr[..position].chars().next_back();
+            let next = expr[position..].chars().next();
+            let TransitionItem::Anchors((_, anchors)) =
+                TransitionItem::get_anchors(position, expr.len(), current, next, self.is_word_char())
+            else {
+                unreachable!("get_anchors should only return anchors");
+            };
+
+            let reachables = state_locked.epsilon(&anchors);
+            if !reachables.is_empty() {
+                // Branches are explored last in, first out, so push them in reverse order of priority
+                for reachable in reachables.iter().rev() {
+                    branches.push(Branch { state: reachable.clone(), position, slots: slots.clone(), calls: calls.clone() });
+                }
+            } else if let Some(c) = next {
+                self.transitions.set(self.transitions.get() + 1);
+                if let Some(dest) = state_locked.transition(c) {
+                    branches.push(Branch { state: dest, position: position + c.len_utf8(), slots, calls });
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Returns a [`Dfa`] accepting the same expressions as the [`Automata`] in its entirety, using subset construction, or
+    /// `None` if the [`Automata`] contains anchors, whose behaviour depends on the surrounding characters, calls or
+    /// backreferences.
+    ///
+    /// The alphabet is partitioned into classes of characters accepted by the same states, using the [`PredicateKind`] of
+    /// each state to only probe the first character of each range, unless some predicate is opaque, in which case every
+    /// [`prim@char`] is probed.
+    pub fn to_dfa(&self) -> Option<Dfa> {
+        let states = self.states();
+        if states.iter().any(|s| {
+            let state = s.borrow();
+            state.as_any().is::<AnchorState>() || state.as_any().is::<CallState>() || state.as_any().is::<BackrefState>()
+        }) {
+            return None;
+        }
+
+        let consuming: Vec<&StatePtr> = states
+            .iter()
+            .filter(|s| s.borrow().epsilon(&[]).is_empty() && !s.borrow().get_dest().is_empty())
+            .collect();
+
+        // Every state accepts either all or none of the characters between consecutive boundaries
+        let ranges: Option<Vec<(char, char)>> = self
+            .predicate_kinds()
+            .iter()
+            .map(PredicateKind::ranges)
+            .collect::<Option<Vec<_>>>()
+            .map(|ranges| ranges.concat());
+        let boundaries: Box<dyn Iterator<Item = char>> = match ranges {
+            Some(ranges) => {
+                let mut boundaries: Vec<char> = ranges
+                    .into_iter()
+                    .flat_map(|(lower, upper)| [Some(lower), next_char(upper)])
+                    .flatten()
+                    .chain(['\0'])
+                    .collect();
+                boundaries.sort_unstable();
+                boundaries.dedup();
+
+                Box::new(boundaries.into_iter())
+            }
+            None => Box::new('\0'..=char::MAX),
+        };
+
+        let mut classes: Vec<(char, char, usize)> = vec![];
+        let mut signatures: Vec<Vec<bool>> = vec![];
+        let mut boundaries = boundaries.peekable();
+        while let Some(c) = boundaries.next() {
+            let last = boundaries
+                .peek()
+                .map_or(char::MAX, |&next| prev_char(next).expect("a boundary after another should have a predecessor"));
+            let signature: Vec<bool> = consuming
+                .iter()
+                .map(|s| s.borrow().transition(c).is_some())
+                .collect();
+
+            match classes.last_mut() {
+                Some((_, upper, class)) if signatures[*class] == signature => *upper = last,
+                _ => {
+                    let class = signatures
+                        .iter()
+                        .position(|s| *s == signature)
+                        .unwrap_or_else(|| {
+                            signatures.push(signature);
+                            signatures.len() - 1
+                        });
+                    classes.push((c, last, class));
+                }
+            }
+        }
+
+        let mut representatives = vec!['\0'; signatures.len()];
+        for &(lower, _, class) in classes.iter().rev() {
+            representatives[class] = lower;
+        }
+
+        let closure = |set: &[StatePtr]| {
+            let mut indices: Vec<usize> = exhaust_epsilons(set, &[])
+                .iter()
+                .filter_map(|s| states.iter().position(|t| t == s))
+                .collect();
+            indices.sort_unstable();
+            indices.dedup();
+            indices
+        };
+
+        let mut sets = vec![closure(slice::from_ref(&self.start))];
+        let mut transitions = vec![];
+        let mut accepting = vec![];
+        while transitions.len() < sets.len() {
+            let set = sets[transitions.len()].clone();
+            accepting.push(set.iter().any(|&s| states[s] == self.get_end()));
+
+            let mut row = vec![];
+            for &c in &representatives {
+                let next: Vec<StatePtr> = set
+                    .iter()
+                    .filter_map(|&s| states[s].borrow().transition(c))
+                    .collect();
+                row.push(if next.is_empty() {
+                    None
+                } else {
+                    let next = closure(&next);
+                    Some(sets.iter().position(|s| *s == next).unwrap_or_else(|| {
+                        sets.push(next);
+                        sets.len() - 1
+                    }))
+                });
+            }
+            transitions.push(row);
+        }
+
+        Some(Dfa::new(classes, transitions, accepting))
+    }
+
+    /// Returns the minimal [`Dfa`] accepting the same expressions as the [`Automata`] in its entirety, or `None` if the
+    /// [`Automata`] contains anchors.
+    pub fn minimize(&self) -> Option<Dfa> {
+        self.to_dfa().map(|dfa| dfa.minimize())
+    }
+
+    /// Returns the [`PredicateKind`] of every state consuming a character, in the order they are reached from the start
+    /// state.
+    pub fn predicate_kinds(&self) -> Vec<PredicateKind> {
+        self.states()
+            .iter()
+            .filter_map(|s| s.borrow().get_kind())
+            .collect()
+    }
+
+    /// Returns `true` if the end state is reachable from the start state without consuming any character, whatever the
+    /// anchors, calls and backreferences along the way, which may all match the empty string.
+    fn matches_empty(&self) -> bool {
+        let end = self.get_end();
+        let mut states = vec![self.start.clone()];
+        let mut seen = HashSet::from([address(&self.start)]);
+        while let Some(state) = states.pop() {
+            if Rc::ptr_eq(&state, &end) {
+                return true;
+            }
+
+            let state = state.borrow();
+            if state.get_kind().is_some() {
+                continue;
+            }
+            for dest in state.get_dest() {
+                if seen.insert(address(dest)) {
+                    states.push(dest.clone());
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Returns all states reachable from the start state.
+    fn states(&self) -> Vec<StatePtr> {
+        let mut states = vec![self.start.clone()];
+        let mut seen = HashSet::from([address(&self.start)]);
+        let mut index = 0;
+        while index < states.len() {
+            let dest: Vec<StatePtr> = states[index].borrow().get_dest().to_vec();
+            for state in dest {
+                if seen.insert(address(&state)) {
+                    states.push(state);
+                }
+            }
+            index += 1;
+        }
+
+        states
+    }
+
+    fn run(&self, expr: &str) -> Vec<Attempt> {
+        let mut attempts: Vec<Attempt> = vec![];
+        let mut predicates = vec![];
+
+        for transition in transition_iter(expr, 0, self.is_word_char()) {
+            match transition {
+                TransitionItem::Char(c) => {
+                    clear_predicates(&mut predicates, self.predicates);
+                    for attempt in &mut attempts {
+                        self.transitions
+                            .set(self.transitions.get() + attempt.states.len());
+
+                        let states: Vec<StatePtr> = attempt
+                            .states
                             .iter()
-                            .filter_map(|s| s.borrow().transition(c))
+                            .filter_map(|s| s.borrow().transition_batched(c, &mut predicates))
                             .collect();
+
+                        if states.is_empty() && !attempt.states.is_empty() {
+                            // Keep hold of the states which died out, so we can report what was expected
+                            attempt.stuck = mem::take(&mut attempt.states);
+                        } else {
+                            attempt.states = states;
+                        }
                     }
                 }
                 TransitionItem::Anchors((r, anchors)) => {
-                    current_states.push((None, vec![self.start.clone()]));
+                    // No point attempting a match which does not start at the start of an anchored expression
+                    if !self.anchored || attempts.is_empty() {
+                        self.attempts.set(self.attempts.get() + 1);
+                        attempts.push(Attempt {
+                            start: r,
+                            end: None,
+                            progress: r,
+                            states: vec![self.start.clone()],
+                            stuck: vec![],
+                        });
+                    }
 
-                    for (match_r, states) in &mut current_states {
-                        *states = exhaust_epsilons(states, &anchors);
+                    for attempt in &mut attempts {
+                        attempt.states = exhaust_epsilons(&attempt.states, &anchors);
 
-                        if states.contains(&self.get_end()) {
+                        if !attempt.states.is_empty() {
+                            attempt.progress = r;
+                        }
+
+                        if attempt.states.contains(&self.get_end()) {
                             // We have a better match
-                            *match_r = Some(r);
+                            attempt.end = Some(r);
                         }
                     }
                 }
             }
         }
 
-        let matches = current_states
-            .iter()
-            .enumerate()
-            .filter_map(|(l, (maybe_r, _))| maybe_r.map(|r| (l, r)));
+        attempts
+    }
 
-        let mut results = vec![];
-        let mut results_rightmost = None;
-        for (left, right) in matches {
-            // Check if we overlap anything already in our results
-            if results_rightmost.map_or(true, |rm| rm <= left && rm < right) {
-                results.push(String::from(&expr[left..right]));
-                results_rightmost = Some(right);
-            }
+    fn is_word_char(&self) -> &dyn Fn(char) -> bool {
+        fn is_ascii_word_char(c: char) -> bool {
+            c.is_ascii_alphanumeric()
         }
 
-        // max_by_key will return the last maximal element. Reverse to get the first.
-        let greedy_result = results.iter().rev().max_by_key(|s| s.len()).cloned();
-        (greedy_result, results)
+        self.word_chars.as_deref().unwrap_or(&is_ascii_word_char)
     }
 
     fn get_end(&self) -> StatePtr {
@@ -178,6 +1452,10 @@ impl Automata {
 
     // It's not necessary to consume a mutable reference, but this function does modify
     // the underlying states. Requiring a mutable reference makes this clearer.
+    //
+    // `state` may be the end state itself, e.g. in `closure` when the start state was simplified into the end state, in
+    // which case it loops onto itself. This is fine as `state` is only cloned, never borrowed, while the end state is
+    // mutably borrowed.
     fn push_to_end(&mut self, state: StatePtr) {
         self.end.borrow_mut().push(state);
     }
@@ -192,6 +1470,24 @@ impl Debug for Automata {
 }
 
 fn exhaust_epsilons(states: &[StatePtr], anchors: &[Anchor]) -> Vec<StatePtr> {
+    let mut destinations: Vec<StatePtr> = Vec::new();
+    exhaust_epsilons_into(states, anchors, &mut destinations, &mut Vec::new());
+
+    destinations
+}
+
+/// Forgets the results of the `count` predicates of an [`Automata`] on the previous character, see
+/// [`State::transition_batched`].
+fn clear_predicates(results: &mut Vec<Option<bool>>, count: usize) {
+    results.clear();
+    results.resize(count, None);
+}
+
+/// Pushes the states reached from `states` by following epsilon transitions allowed by `anchors` into `destinations`,
+/// as in [`exhaust_epsilons`], using `visited_states` as scratch space.
+fn exhaust_epsilons_into(
+    states: &[StatePtr], anchors: &[Anchor], destinations: &mut Vec<StatePtr>, visited_states: &mut Vec<StatePtr>,
+) {
     fn traverse_epsilons(
         destinations: &mut Vec<StatePtr>, visited_states: &mut Vec<StatePtr>, state: &StatePtr, anchors: &[Anchor],
     ) {
@@ -212,24 +1508,91 @@ fn exhaust_epsilons(states: &[StatePtr], anchors: &[Anchor]) -> Vec<StatePtr> {
         }
     }
 
-    let mut destinations: Vec<StatePtr> = Vec::new();
-    let mut visited_states: Vec<StatePtr> = Vec::new();
-
+    visited_states.clear();
     states
         .iter()
-        .for_each(|s| traverse_epsilons(&mut destinations, &mut visited_states, s, anchors));
+        .for_each(|s| traverse_epsilons(destinations, visited_states, s, anchors));
+    visited_states.clear();
+}
 
-    destinations
+/// Returns `true` if the capture slots `slots` are preferred to `other` for the same match under
+/// [`MatchSemantics::PosixLeftmostLongest`], i.e. if the first capturing group spanning differently starts earlier, or
+/// starts at the same offset and ends later, or participates only in `slots`.
+fn posix_prefers(slots: &[Option<usize>], other: &[Option<usize>]) -> bool {
+    let key = |slot: &[Option<usize>]| (slot[0].map(Reverse), slot[1]);
+    slots
+        .chunks(2)
+        .zip(other.chunks(2))
+        .skip(1)
+        .map(|(slot, other)| key(slot).cmp(&key(other)))
+        .find(|ordering| ordering.is_ne())
+        == Some(Ordering::Greater)
+}
+
+/// Returns the address of `state`, which identifies it as long as it is alive.
+fn address(state: &StatePtr) -> usize {
+    Rc::as_ptr(state).cast::<()>() as usize
+}
+
+/// Drops the attempts yet to match whose states are all occupied by an earlier attempt, as the earlier attempt matches
+/// whenever they would, so they can never find the leftmost match.
+fn prune_dominated(attempts: &mut Vec<Attempt>) {
+    let mut i = 1;
+    while i < attempts.len() {
+        let dominated = attempts[i].end.is_none()
+            && attempts[..i].iter().any(|earlier| {
+                attempts[i]
+                    .states
+                    .iter()
+                    .all(|s| earlier.states.iter().any(|e| Rc::ptr_eq(s, e)))
+            });
+
+        if dominated {
+            attempts.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Returns the byte offset of the first occurrence of `needle` in `haystack`, searched with SIMD through the `memchr`
+/// crate.
+#[cfg(feature = "memchr")]
+fn find_substring(haystack: &str, needle: &str) -> Option<usize> {
+    // Both are valid UTF-8, so an occurrence of the bytes of `needle` always starts at a character boundary
+    memchr::memmem::find(haystack.as_bytes(), needle.as_bytes())
+}
+
+/// Returns the byte offset of the first occurrence of `needle` in `haystack`.
+#[cfg(not(feature = "memchr"))]
+fn find_substring(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.find(needle)
+}
+
+/// Returns an iterator over the byte offsets of `expr` from `from` onwards at which a match may start, i.e. the offsets
+/// of its characters and of its end.
+fn positions(expr: &str, from: usize) -> impl Iterator<Item = usize> + '_ {
+    expr[from..]
+        .char_indices()
+        .map(move |(i, _)| from + i)
+        .chain([expr.len()])
 }
 
-fn transition_iter(expr: &str) -> impl Iterator<Item = TransitionItem> + '_ {
+/// Returns an iterator over the characters of `expr` from the byte offset `from` onwards, interleaved with the anchors
+/// satisfied between them, where word boundaries are delimited by `is_word_char`.
+fn transition_iter<'a>(
+    expr: &'a str, from: usize, is_word_char: &'a dyn Fn(char) -> bool,
+) -> impl Iterator<Item = TransitionItem> + 'a {
     struct IntoIter<'a> {
-        it: Chars<'a>,
+        it: CharIndices<'a>,
+        is_word_char: &'a dyn Fn(char) -> bool,
+        offset: usize,
+        len: usize,
         current: Option<char>,
         index: usize,
     }
 
-    impl<'a> Iterator for IntoIter<'a> {
+    impl Iterator for IntoIter<'_> {
         type Item = TransitionItem;
 
         fn next(&mut self) -> Option<Self::Item> {
@@ -239,8 +1602,11 @@ fn transition_iter(expr: &str) -> impl Iterator<Item = TransitionItem> + '_ {
             if return_char {
                 self.current.map(Self::Item::Char)
             } else {
-                let next = self.it.next();
-                let eps = TransitionItem::get_anchors(self.index / 2, self.current, next);
+                let (offset, next) = self
+                    .it
+                    .next()
+                    .map_or((self.len, None), |(i, c)| (self.offset + i, Some(c)));
+                let eps = TransitionItem::get_anchors(offset, self.len, self.current, next, self.is_word_char);
                 self.current = next;
 
                 Some(eps)
@@ -248,33 +1614,88 @@ fn transition_iter(expr: &str) -> impl Iterator<Item = TransitionItem> + '_ {
         }
     }
 
-    IntoIter { it: expr.chars(), current: None, index: 0 }
+    IntoIter {
+        it: expr[from..].char_indices(),
+        is_word_char,
+        offset: from,
+        len: expr.len(),
+        current: expr[..from].chars().next_back(),
+        index: 0,
+    }
 }
 
 enum TransitionItem {
     Char(char),
-    Anchors((usize, Vec<Anchor>)),
+    Anchors((usize, Anchors)),
 }
 
-impl TransitionItem {
-    fn get_anchors(index: usize, current: Option<char>, next: Option<char>) -> Self {
-        let mut anchors = vec![];
+/// The anchors satisfied at a given position, stored inline as there are only a handful of them, so that scanning an
+/// expression does not allocate at every position.
+struct Anchors {
+    anchors: [Anchor; Anchor::COUNT],
+    len: usize,
+}
 
-        if let (Some(c), Some(n)) = (current, next) {
-            if c.is_alphanumeric() != n.is_alphanumeric() {
-                anchors.push(Anchor::WordBoundary);
-            }
+impl Anchors {
+    fn new() -> Self {
+        Anchors { anchors: [Anchor::Start; Anchor::COUNT], len: 0 }
+    }
+
+    /// Returns the anchors satisfied at the byte offset `index` of an expression of `len` bytes, between the characters
+    /// `current` and `next`.
+    fn at(index: usize, len: usize, current: Option<char>, next: Option<char>, is_word_char: &dyn Fn(char) -> bool) -> Self {
+        let mut anchors = Anchors::new();
+
+        if current.is_none() {
+            anchors.push(Anchor::Start);
+            anchors.push(Anchor::TextStart);
+        }
+        if next.is_none() {
+            anchors.push(Anchor::End);
+            anchors.push(Anchor::TextEnd);
+        }
+        if next.is_none() || (next == Some('\n') && index + 1 == len) {
+            anchors.push(Anchor::TextEndBeforeNewline);
+        }
+
+        // A word boundary separates a word character from a non-word character, or from either end of the expression
+        if current.is_some_and(is_word_char) == next.is_some_and(is_word_char) {
+            anchors.push(Anchor::NotWordBoundary);
         } else {
-            if current.is_none() {
-                anchors.push(Anchor::Start);
-            }
-            if next.is_none() {
-                anchors.push(Anchor::End);
-            }
             anchors.push(Anchor::WordBoundary);
         }
 
-        TransitionItem::Anchors((index, anchors))
+        if current.is_none_or(|c| c == '\n') {
+            anchors.push(Anchor::LineStart);
+        }
+        if next.is_none_or(|n| n == '\n') {
+            anchors.push(Anchor::LineEnd);
+        }
+
+        anchors
+    }
+
+    fn push(&mut self, anchor: Anchor) {
+        debug_assert!(self.len < Anchor::COUNT && !self.contains(&anchor), "each anchor is pushed at most once");
+        self.anchors[self.len] = anchor;
+        self.len += 1;
+    }
+}
+
+impl Deref for Anchors {
+    type Target = [Anchor];
+
+    fn deref(&self) -> &[Anchor] {
+        &self.anchors[..self.len]
+    }
+}
+
+impl TransitionItem {
+    /// Wraps the anchors returned by [`Anchors::at`] along with the byte offset `index` they are satisfied at.
+    fn get_anchors(
+        index: usize, len: usize, current: Option<char>, next: Option<char>, is_word_char: &dyn Fn(char) -> bool,
+    ) -> Self {
+        TransitionItem::Anchors((index, Anchors::at(index, len, current, next, is_word_char)))
     }
 }
 
@@ -477,4 +1898,214 @@ mod tests {
         assert_eq!(nfa.global_search("aaaaaaac"), vec!["aaaaaaa", "c"]);
         assert_eq!(nfa.global_search("cc"), vec!["c", "c"]);
     }
+
+    #[test]
+    fn nfa_minimize() {
+        let a_or_b = || Automata::from_token('a').or(Automata::from_token('b'));
+        let nfa = a_or_b().concat(a_or_b());
+
+        let dfa = nfa.to_dfa();
+        assert!(dfa.is_some());
+        let dfa = dfa.unwrap();
+
+        let minimal = nfa.minimize();
+        assert!(minimal.is_some());
+        let minimal = minimal.unwrap();
+        assert_eq!(minimal.state_count(), 3);
+        assert_eq!(minimal, dfa.minimize().minimize());
+
+        for expr in ["aa", "ab", "ba", "bb", "", "a", "abc", "ca", "aab"] {
+            assert_eq!(minimal.full_match(expr), nfa.full_match(expr));
+            assert_eq!(dfa.full_match(expr), nfa.full_match(expr));
+        }
+
+        // Reading `a` or `c` leads to distinct sets of states, which are nonetheless equivalent
+        let nfa = Automata::from_token('a')
+            .concat(Automata::from_token('b'))
+            .or(Automata::from_token('c').concat(Automata::from_token('b')));
+        assert_eq!(nfa.to_dfa().unwrap().state_count(), 4);
+        assert_eq!(nfa.minimize().unwrap().state_count(), 3);
+
+        // Both branches loop on the same state once their first character is consumed
+        let nfa = Automata::from_token('a')
+            .concat(Automata::from_token('b').closure())
+            .or(Automata::from_token('c').concat(Automata::from_token('b').closure()));
+        let minimal = nfa.minimize().unwrap();
+        assert_eq!(minimal.state_count(), 2);
+        assert!(minimal.full_match("abbb"));
+        assert!(minimal.full_match("c"));
+        assert!(!minimal.full_match("bb"));
+
+        assert!(Automata::from_anchor(Anchor::Start).minimize().is_none());
+    }
+
+    #[test]
+    fn nfa_simplify() {
+        // (ab?)*|c
+        let make = || {
+            Automata::from_token('a')
+                .concat(Automata::from_token('b').optional())
+                .closure()
+                .or(Automata::from_token('c'))
+        };
+
+        let nfa = make();
+        let simplified = make().simplify();
+        assert!(simplified.state_count() < nfa.state_count());
+
+        for expr in ["abaaaaaa", "abab", "abad", "c", "", "bb", "aaaaaaac", "cc"] {
+            assert_eq!(simplified.full_match(expr), nfa.full_match(expr));
+            assert_eq!(simplified.greedy_search(expr), nfa.greedy_search(expr));
+            assert_eq!(simplified.global_search(expr), nfa.global_search(expr));
+        }
+
+        // (a|b)*cd?e+f*
+        let make = || {
+            Automata::from_token('a')
+                .or(Automata::from_token('b'))
+                .closure()
+                .concat(Automata::from_token('c'))
+                .concat(Automata::from_token('d').optional())
+                .concat(Automata::from_token('e').plus())
+                .concat(Automata::from_token('f').closure())
+        };
+
+        let nfa = make();
+        let simplified = make().simplify();
+        assert!(simplified.state_count() < nfa.state_count());
+
+        for expr in ["ce", "aababacdefffff", "cdde", "aacbdde", "e", "xxbacdefffffy", ""] {
+            assert_eq!(simplified.full_match(expr), nfa.full_match(expr));
+            assert_eq!(simplified.global_search(expr), nfa.global_search(expr));
+        }
+
+        // Capturing groups are recorded by their own states, which are kept
+        let nfa = Automata::from_token('a').capture(1).closure().simplify();
+        assert_eq!(nfa.captures("baa", 1), Some(vec![Some((0, 0)), None]));
+        assert_eq!(nfa.captures("aab", 1), Some(vec![Some((0, 2)), Some((1, 2))]));
+    }
+
+    #[test]
+    fn nfa_prune_dominated() {
+        // a+c
+        let nfa = Automata::from_token('a')
+            .plus()
+            .concat(Automata::from_token('c'));
+
+        let expr = format!("{}b", "a".repeat(100));
+        assert_eq!(nfa.find_at(&expr, 0), None);
+        // Attempts starting within a run of a's follow the states of the first one, so only the first one is kept
+        assert!(nfa.transitions() < 2 * expr.len());
+
+        assert_eq!(nfa.find_at("aabaac", 0), Some((3, 6)));
+        assert_eq!(nfa.find_at("aaac", 1), Some((1, 4)));
+        assert_eq!(nfa.find_at("ac", 0), Some((0, 2)));
+
+        // (ab|b)c*
+        let nfa = Automata::from_token('a')
+            .concat(Automata::from_token('b'))
+            .or(Automata::from_token('b'))
+            .concat(Automata::from_token('c').closure());
+        assert_eq!(nfa.find_at("xabcc", 0), Some((1, 5)));
+        assert_eq!(nfa.find_at("bcc", 0), Some((0, 3)));
+    }
+
+    #[test]
+    fn nfa_reverse() {
+        let nfa = Automata::from_token('c')
+            .concat(Automata::from_token('d').plus())
+            .or(Automata::from_token('e').capture(1))
+            .reverse();
+        assert!(nfa.is_some());
+        let nfa = nfa.unwrap();
+
+        assert!(nfa.full_match("dc"));
+        assert!(nfa.full_match("dddc"));
+        assert!(nfa.full_match("e"));
+        assert!(!nfa.full_match("cd"));
+        assert!(!nfa.full_match("c"));
+        assert!(!nfa.full_match(""));
+
+        assert!(Automata::from_call(0).reverse().is_none());
+    }
+
+    #[test]
+    fn nfa_batch_predicates() {
+        // (\d|\w|\s|\d|[aeiou]|x)*, where the last two are opaque
+        let make = || {
+            Automata::from_lambda(|c| c.is_ascii_digit(), PredicateKind::Digit)
+                .or(Automata::from_lambda(|c| c.is_ascii_alphanumeric(), PredicateKind::Word))
+                .or(Automata::from_lambda(|c| c.is_ascii_whitespace(), PredicateKind::Whitespace))
+                .or(Automata::from_lambda(|c| c.is_ascii_digit(), PredicateKind::Digit))
+                .or(Automata::from_lambda(|c| "aeiou".contains(c), PredicateKind::Custom))
+                .or(Automata::from_lambda(|c| c == 'x', PredicateKind::Custom))
+                .closure()
+                .concat(Automata::from_token('!'))
+        };
+        let nfa = make();
+        let batched = make().batch_predicates();
+        assert_eq!(batched.predicates, 5);
+
+        for expr in ["", "!", "a1 b2!", "x!", "-x!", "é1!", "12 ab\tcd!?", "no match"] {
+            assert_eq!(batched.find_at(expr, 0), nfa.find_at(expr, 0));
+            assert_eq!(batched.is_match(expr), nfa.is_match(expr));
+            assert_eq!(batched.explain(expr), nfa.explain(expr));
+        }
+    }
+
+    #[test]
+    fn nfa_transition_iter_empty() {
+        // The empty expression still has a single position, which is both its start and its end
+        let items: Vec<TransitionItem> = transition_iter("", 0, &|c| c.is_ascii_alphanumeric()).collect();
+        assert_eq!(items.len(), 1);
+        match &items[0] {
+            TransitionItem::Anchors((index, anchors)) => {
+                assert_eq!(*index, 0);
+                assert!(anchors.contains(&Anchor::Start));
+                assert!(anchors.contains(&Anchor::End));
+            }
+            TransitionItem::Char(c) => panic!("Unexpected character {c}"),
+        }
+    }
+
+    #[test]
+    fn nfa_self_loop() {
+        // The start state of the empty automata is simplified into its end state, so closures loop it onto itself
+        let empty = Automata::empty().simplify();
+        assert!(Rc::ptr_eq(&empty.start, &empty.get_end()));
+        let nfa = empty.closure().simplify().closure().plus();
+
+        assert!(nfa.full_match(""));
+        assert!(!nfa.full_match("a"));
+        assert_eq!(nfa.find_at("a", 0), Some((0, 0)));
+        assert!(nfa
+            .reverse()
+            .is_some_and(|reversed| reversed.full_match("")));
+        assert!(nfa.to_dfa().is_some());
+    }
+
+    #[test]
+    fn nfa_never() {
+        let nfa = Automata::never();
+
+        assert!(!nfa.full_match(""));
+        assert!(!nfa.is_match("abc"));
+        assert_eq!(nfa.find_at("abc", 0), None);
+
+        let nfa = Automata::never().or(Automata::from_token('a')).closure();
+        assert!(nfa.full_match(""));
+        assert!(nfa.full_match("aa"));
+        assert!(!nfa.full_match("b"));
+    }
+
+    #[test]
+    fn nfa_find_substring() {
+        let haystack = "lorem ipsum 42 foo1 fóo fóo2";
+        for needle in ["foo", "fóo", "42", "o", "ó", "lorem", "2", "bar", ""] {
+            assert_eq!(find_substring(haystack, needle), haystack.find(needle), "{needle:?}");
+        }
+
+        assert_eq!(find_substring("", "a"), None);
+        assert_eq!(find_substring("", ""), Some(0));
+    }
 }
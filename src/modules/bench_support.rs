@@ -0,0 +1,26 @@
+//! Instrumentation of the matching of a [`RegExp`], for benchmarks to report the work done alongside timings.
+
+use super::regex::RegExp;
+
+/// The work done matching a [`RegExp`] since its counters were last reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counters {
+    /// Number of state transitions performed.
+    pub transitions: usize,
+    /// Number of attempts at matching from a given position of an expression.
+    pub attempts: usize,
+}
+
+/// Returns the [`Counters`] of `regexp`.
+pub fn counters(regexp: &RegExp) -> Counters {
+    Counters { transitions: regexp.transition_count(), attempts: regexp.attempt_count() }
+}
+
+/// Runs `f` on `regexp` from freshly reset counters, and returns its result along with the [`Counters`] of the work it
+/// did.
+pub fn measure<T>(regexp: &RegExp, f: impl FnOnce(&RegExp) -> T) -> (T, Counters) {
+    regexp.reset_counters();
+    let result = f(regexp);
+
+    (result, counters(regexp))
+}
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+
+/// A deterministic finite automaton, whose transitions are taken on classes of [`prim@char`] rather than on individual
+/// characters.
+///
+/// State `0` is the start state. There is no explicit dead state: a missing transition rejects the expression.
+#[derive(Debug, PartialEq)]
+pub struct Dfa {
+    /// Sorted and disjoint ranges of characters covering every [`prim@char`], along with the class they belong to.
+    classes: Vec<(char, char, usize)>,
+    /// Destination of each state on each class, if any.
+    transitions: Vec<Vec<Option<usize>>>,
+    /// Whether each state is accepting.
+    accepting: Vec<bool>,
+}
+
+impl Dfa {
+    pub fn new(classes: Vec<(char, char, usize)>, transitions: Vec<Vec<Option<usize>>>, accepting: Vec<bool>) -> Self {
+        Dfa { classes, transitions, accepting }
+    }
+
+    /// Returns the number of states, not counting the implicit dead state.
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns `true` if `expr` is accepted by the [`Dfa`].
+    pub fn full_match(&self, expr: &str) -> bool {
+        expr.chars()
+            .try_fold(0, |state, c| self.transitions[state][self.class_of(c)])
+            .is_some_and(|state| self.accepting[state])
+    }
+
+    /// Returns `true` if every expression accepted by the [`Dfa`] is also accepted by `other`.
+    ///
+    /// Explores the product of both [`Dfa`], looking for a reachable pair of states where only `self` accepts.
+    pub fn is_subset_of(&self, other: &Dfa) -> bool {
+        // Characters between consecutive boundaries belong to the same class in both automata
+        let mut boundaries: Vec<char> = self
+            .classes
+            .iter()
+            .chain(&other.classes)
+            .map(|&(lower, _, _)| lower)
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut visited = vec![(0, Some(0))];
+        let mut stack = vec![(0, Some(0))];
+        while let Some((state, other_state)) = stack.pop() {
+            if self.accepting[state] && !other_state.is_some_and(|s: usize| other.accepting[s]) {
+                return false;
+            }
+
+            for &c in &boundaries {
+                let Some(next) = self.transitions[state][self.class_of(c)] else {
+                    continue;
+                };
+                let other_next = other_state.and_then(|s| other.transitions[s][other.class_of(c)]);
+
+                if !visited.contains(&(next, other_next)) {
+                    visited.push((next, other_next));
+                    stack.push((next, other_next));
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Returns the minimal [`Dfa`] accepting the same expressions, using Hopcroft's algorithm.
+    ///
+    /// States of the minimal [`Dfa`] are numbered in breadth-first order from the start state, following classes in
+    /// order, and classes on which every state behaves the same are merged, so that equivalent [`Dfa`] minimize to the
+    /// same [`Dfa`].
+    pub fn minimize(&self) -> Dfa {
+        let class_count = self.class_count();
+
+        // Complete the automaton with an explicit dead state, so that every state has a transition on every class
+        let dead = self.state_count();
+        let delta = |state: usize, class: usize| {
+            if state == dead {
+                dead
+            } else {
+                self.transitions[state][class].unwrap_or(dead)
+            }
+        };
+
+        let mut inverse = vec![vec![vec![]; dead + 1]; class_count];
+        for state in 0..=dead {
+            for (class, sources) in inverse.iter_mut().enumerate() {
+                sources[delta(state, class)].push(state);
+            }
+        }
+
+        // Start from the partition into accepting and rejecting states, then refine it until it is stable
+        let (accepting, rejecting): (Vec<usize>, Vec<usize>) = (0..=dead).partition(|&s| s != dead && self.accepting[s]);
+        let mut blocks: Vec<Vec<usize>> = [accepting, rejecting]
+            .into_iter()
+            .filter(|b| !b.is_empty())
+            .collect();
+        let mut block_of = vec![0; dead + 1];
+        for (index, block) in blocks.iter().enumerate() {
+            block.iter().for_each(|&s| block_of[s] = index);
+        }
+
+        let mut worklist: Vec<usize> = (0..blocks.len()).collect();
+        while let Some(splitter) = worklist.pop() {
+            let splitter = blocks[splitter].clone();
+
+            for sources in &inverse {
+                let mut marked = vec![false; dead + 1];
+                splitter
+                    .iter()
+                    .flat_map(|&s| &sources[s])
+                    .for_each(|&s| marked[s] = true);
+
+                for index in 0..blocks.len() {
+                    let (inside, outside): (Vec<usize>, Vec<usize>) = blocks[index].iter().partition(|&&s| marked[s]);
+                    if inside.is_empty() || outside.is_empty() {
+                        continue;
+                    }
+
+                    let new_index = blocks.len();
+                    outside.iter().for_each(|&s| block_of[s] = new_index);
+
+                    // Refining with the smaller half is enough, unless the block is already waiting to be a splitter
+                    let smaller = if inside.len() <= outside.len() { index } else { new_index };
+                    blocks[index] = inside;
+                    blocks.push(outside);
+                    worklist.push(if worklist.contains(&index) { new_index } else { smaller });
+                }
+            }
+        }
+
+        // Number the blocks in breadth-first order, dropping the block of the dead state
+        let dead_block = block_of[dead];
+        let mut numbering = vec![None; blocks.len()];
+        numbering[block_of[0]] = Some(0);
+        let mut queue = VecDeque::from([block_of[0]]);
+
+        let mut transitions = vec![];
+        let mut accepting = vec![];
+        while let Some(block) = queue.pop_front() {
+            let representative = blocks[block][0];
+            accepting.push(representative != dead && self.accepting[representative]);
+
+            let row = (0..class_count)
+                .map(|class| {
+                    let target = block_of[delta(representative, class)];
+                    if target == dead_block {
+                        return None;
+                    }
+
+                    Some(*numbering[target].get_or_insert_with(|| {
+                        queue.push_back(target);
+                        transitions.len() + queue.len()
+                    }))
+                })
+                .collect();
+            transitions.push(row);
+        }
+
+        Dfa::new(self.classes.clone(), transitions, accepting).merge_classes()
+    }
+
+    /// Returns an equivalent [`Dfa`] where classes on which every state behaves the same are merged, and classes are
+    /// numbered in order of their first character.
+    fn merge_classes(self) -> Dfa {
+        let column = |class: usize| -> Vec<Option<usize>> { self.transitions.iter().map(|row| row[class]).collect() };
+
+        let mut columns: Vec<Vec<Option<usize>>> = vec![];
+        let mut renumbering = vec![None; self.class_count()];
+        let mut classes: Vec<(char, char, usize)> = vec![];
+        for &(lower, upper, class) in &self.classes {
+            let merged = *renumbering[class].get_or_insert_with(|| {
+                let col = column(class);
+                columns.iter().position(|c| *c == col).unwrap_or_else(|| {
+                    columns.push(col);
+                    columns.len() - 1
+                })
+            });
+
+            match classes.last_mut() {
+                Some((_, last_upper, last_class)) if *last_class == merged => *last_upper = upper,
+                _ => classes.push((lower, upper, merged)),
+            }
+        }
+
+        let transitions = (0..self.state_count())
+            .map(|state| columns.iter().map(|column| column[state]).collect())
+            .collect();
+
+        Dfa::new(classes, transitions, self.accepting)
+    }
+
+    fn class_count(&self) -> usize {
+        self.classes
+            .iter()
+            .map(|&(_, _, class)| class + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn class_of(&self, c: char) -> usize {
+        self.classes[self.classes.partition_point(|&(_, upper, _)| upper < c)].2
+    }
+}
@@ -1,20 +1,162 @@
-use std::fmt::{Debug, Formatter, Result};
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Formatter, Result};
+use std::rc::Rc;
+
+/// The reason a regular expression could not be compiled, or used as requested.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    /// A character which could not be parsed, along with its byte offset in the regular expression.
+    UnexpectedChar(char, usize),
+    /// The regular expression ended where more was expected, e.g. when it is empty.
+    UnexpectedEnd,
+    /// A group, along with the byte offset of its `(` in the regular expression, which is still open at the end of the
+    /// regular expression.
+    UnclosedGroup(usize),
+    /// A number, as written, of a repetition, recursion or backreference which does not fit in a [`u32`], along with its
+    /// byte offset in the regular expression.
+    NumberTooLarge(String, usize),
+    /// A backslash ending the regular expression, along with its byte offset, which has nothing to escape.
+    TrailingBackslash(usize),
+    /// A character class, e.g. `\d` or `[:alpha:]`, used as an endpoint of a range within a character group, as written,
+    /// along with its byte offset in the regular expression.
+    ClassRangeEndpoint(String, usize),
+    /// A POSIX class name, e.g. `alhpa` in `[[:alhpa:]]`, which names no POSIX class, along with the byte offset of its
+    /// `[:` in the regular expression.
+    UnknownPosixClass(String, usize),
+    /// A range within a character group whose lower endpoint comes after its upper one, e.g. `[z-a]`, which would
+    /// otherwise match nothing, along with its byte offset in the regular expression.
+    InvertedCharacterRange(char, char, usize),
+    /// An empty character group `[]`, which matches nothing, without allowing it.
+    EmptyCharacterGroup,
+    /// A capturing group name used by more than one capturing group.
+    DuplicateGroupName(String),
+    /// A recursion `(?n)` to a capturing group which does not exist.
+    UndefinedRecursion(u32),
+    /// A backreference `\n` to a capturing group which does not exist.
+    UndefinedBackreference(u32),
+    /// A backreference `\k<name>` to a capturing group name which does not exist.
+    UndefinedNamedBackreference(String),
+    /// A reference `$n` or `$name`, as written with `$` and any braces, e.g. `${n}`, in a replacement string to a
+    /// capturing group which does not exist.
+    UndefinedReplacementReference(String),
+    /// Case insensitive matching of non-ASCII letters without Unicode enabled.
+    UnicodeRequired,
+    /// The NFA engine forced on a regular expression which only the backtracking engine can match, e.g. with lazy
+    /// quantifiers.
+    BacktrackingRequired,
+    /// A regular expression estimated to compile into more states than the limit set by
+    /// [`Flags::max_states`](crate::regex::Flags::max_states), e.g. through range quantifiers such as `a{5,10}`.
+    ExpansionTooLarge { estimated: usize, limit: usize },
+    /// A failure of the crate itself, rather than of the regular expression.
+    Internal(String),
+}
+
+/// Formats the message of an [`Error`] from its [`ErrorKind`], e.g. to translate it.
+pub trait ErrorFormatter {
+    fn format(&self, kind: &ErrorKind) -> String;
+}
+
+/// The [`ErrorFormatter`] used by default, which formats messages in English.
+pub struct DefaultErrorFormatter;
+
+impl ErrorFormatter for DefaultErrorFormatter {
+    fn format(&self, kind: &ErrorKind) -> String {
+        match kind {
+            ErrorKind::UnexpectedChar(c, position) => format!("Unexpected `{c}` at position {position}"),
+            ErrorKind::UnexpectedEnd => String::from("Unexpected end of expression"),
+            ErrorKind::UnclosedGroup(position) => {
+                format!("Missing `)` at end of expression, to close the group opened at position {position}")
+            }
+            ErrorKind::NumberTooLarge(number, position) => {
+                format!("Number `{number}` at position {position} is too large, as it does not fit in 32 bits")
+            }
+            ErrorKind::TrailingBackslash(position) => format!("Trailing backslash: nothing to escape at position {position}"),
+            ErrorKind::ClassRangeEndpoint(class, position) => {
+                format!("Character class `{class}` cannot be a range endpoint at position {position}")
+            }
+            ErrorKind::UnknownPosixClass(name, position) => {
+                format!("Unknown POSIX class `[:{name}:]` at position {position}")
+            }
+            ErrorKind::InvertedCharacterRange(lower, upper, position) => format!(
+                "Character range `{lower}-{upper}` at position {position} is out of order, as `{lower}` comes after `{upper}`"
+            ),
+            ErrorKind::EmptyCharacterGroup => String::from("Empty character group `[]` matches nothing"),
+            ErrorKind::DuplicateGroupName(name) => format!("Capturing group name `{name}` is defined more than once"),
+            ErrorKind::UndefinedRecursion(n) => format!("Recursion (?{n}) refers to a non-existent capturing group"),
+            ErrorKind::UndefinedBackreference(n) => format!("Backreference \\{n} refers to a non-existent capturing group"),
+            ErrorKind::UndefinedNamedBackreference(name) => {
+                format!("Backreference \\k<{name}> refers to a non-existent capturing group")
+            }
+            ErrorKind::UndefinedReplacementReference(reference) => {
+                format!("Replacement reference {reference} refers to a non-existent capturing group")
+            }
+            ErrorKind::UnicodeRequired => {
+                String::from("Case insensitive matching of non-ASCII letters requires Unicode to be enabled")
+            }
+            ErrorKind::BacktrackingRequired => String::from(
+                "Backreferences, recursions, lazy quantifiers and Perl leftmost-first semantics require the backtracking engine",
+            ),
+            ErrorKind::ExpansionTooLarge { estimated, limit } => {
+                format!("Regular expression expands into an estimated {estimated} states, exceeding the limit of {limit}")
+            }
+            ErrorKind::Internal(msg) => format!("Internal Error: {msg}"),
+        }
+    }
+}
+
+thread_local! {
+    static FORMATTER: RefCell<Rc<dyn ErrorFormatter>> = RefCell::new(Rc::new(DefaultErrorFormatter));
+}
+
+/// Sets the [`ErrorFormatter`] used by the current thread to format the messages of [`Error`]s.
+pub fn set_error_formatter<F: ErrorFormatter + 'static>(formatter: F) {
+    FORMATTER.with(|f| *f.borrow_mut() = Rc::new(formatter));
+}
+
+/// Restores [`DefaultErrorFormatter`] as the [`ErrorFormatter`] used by the current thread.
+pub fn reset_error_formatter() {
+    set_error_formatter(DefaultErrorFormatter);
+}
 
 #[derive(PartialEq)]
 pub struct Error {
-    msg: String,
+    kind: ErrorKind,
 }
 
 impl Error {
+    pub fn new(kind: ErrorKind) -> Error {
+        Error { kind }
+    }
+
+    /// Returns an [`Error`] of kind [`ErrorKind::Internal`] with the message `msg`.
+    #[deprecated(note = "use `Error::new` with an `ErrorKind` instead")]
     pub fn from(msg: &str) -> Error {
-        Error { msg: String::from(msg) }
+        Error::new(ErrorKind::Internal(String::from(msg)))
+    }
+
+    /// Returns the reason the regular expression could not be compiled.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Returns the message of the [`Error`], as formatted by the [`ErrorFormatter`] of the current thread.
+    pub fn msg(&self) -> String {
+        // The formatter is cloned out, so that it may itself format errors
+        let formatter = FORMATTER.with(|f| f.borrow().clone());
+        formatter.format(&self.kind)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{}", self.msg())
     }
 }
 
 impl Debug for Error {
     fn fmt(&self, f: &mut Formatter) -> Result {
         f.debug_struct("RegexError")
-            .field("Error Message", &self.msg)
+            .field("Error Message", &self.msg())
             .finish()
     }
 }
@@ -1,5 +1,8 @@
 mod automata;
+pub mod bench_support;
+mod dfa;
 mod error;
 pub mod regex;
 #[allow(clippy::module_name_repetitions)]
 mod state;
+mod trace;
@@ -4,7 +4,10 @@ use super::parser::MonadicParser;
 
 /// Constructs a new [`MonadicParser`] for [`prim@char`].
 pub fn any() -> MonadicParser<char> {
-    MonadicParser::new(|expr| Some((expr.chars().next()?, &expr[1..])))
+    MonadicParser::new(|expr| {
+        let c = expr.chars().next()?;
+        Some((c, &expr[c.len_utf8()..]))
+    })
 }
 
 /// Returns a [`MonadicParser`] which parses `ch`.
@@ -22,11 +25,17 @@ pub fn digit() -> MonadicParser<u32> {
     any().map(|c| c.to_digit(10))
 }
 
-/// Returns a [`MonadicParser`] which parses numbers.
-pub fn number() -> MonadicParser<u32> {
-    digit()
-        .one_or_more()
-        .map(|v| Some(v.iter().fold(0, |acc, d| acc * 10 + d)))
+/// Returns a [`MonadicParser`] which parses names, i.e. an ASCII letter or `_` followed by ASCII letters, digits or `_`.
+pub fn name() -> MonadicParser<String> {
+    let is_name_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+
+    (any().filter(move |c| is_name_char(c) && !c.is_ascii_digit()) & any().filter(is_name_char).repeat())
+        .map(|(first, rest)| Some(std::iter::once(first).chain(rest).collect()))
+}
+
+/// Returns a [`MonadicParser`] which parses the digits of a number as written, whatever its size.
+pub fn digits() -> MonadicParser<String> {
+    digit().one_or_more().recognize()
 }
 
 /// Returns a [`MonadicParser`] which parses escaped character satisfying `predicate`.
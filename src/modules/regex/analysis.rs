@@ -0,0 +1,248 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::grammar::{Anchor, BasicExpression, Expression, Match, Quantifiable, Quantifier};
+
+/// A potential issue with a regular expression, which does not prevent it from compiling.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// `.` repeated without an upper bound, e.g. `.*`, which can be expensive to match against long expressions.
+    UnboundedAny(String),
+    /// A group repeated without an upper bound containing a quantifier without an upper bound itself, e.g. `(a*)*`, which
+    /// can take exponential time to match with the backtracking engine.
+    NestedUnboundedQuantifier,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Warning::UnboundedAny(quantified) => {
+                write!(f, "`{quantified}` can be expensive to match against long expressions")
+            }
+            Warning::NestedUnboundedQuantifier => write!(
+                f,
+                "Nested unbounded quantifiers, e.g. `(a*)*`, can take exponential time to match with the backtracking engine"
+            ),
+        }
+    }
+}
+
+/// Where the matches of a regular expression lie within an expression, which determines the fastest way to scan for them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchKind {
+    /// Matches span the entire expression, e.g. for `^abc$`.
+    Anchored,
+    /// Matches start at the start of the expression, e.g. for `^abc`.
+    Prefix,
+    /// Matches end at the end of the expression, e.g. for `abc$`.
+    Suffix,
+    /// Matches may lie anywhere in the expression, e.g. for `abc`.
+    Unanchored,
+}
+
+// Structural analysis of the syntax representation of Regex
+
+/// Returns `true` if every alternative of `expr` starts with the [`Anchor::Start`] or [`Anchor::TextStart`] anchor.
+///
+/// An anchor within a group enabling multi-line mode, or following inline flags enabling it, only matches at the start
+/// of a line, so it does not count.
+pub fn is_anchored_start(expr: &Expression) -> bool {
+    is_anchored(expr, <[BasicExpression]>::first, &[Anchor::Start, Anchor::TextStart])
+}
+
+/// Returns `true` if every alternative of `expr` ends with the [`Anchor::End`] or [`Anchor::TextEnd`] anchor.
+///
+/// As for [`is_anchored_start`], an anchor only matching at the end of a line in multi-line mode does not count.
+pub fn is_anchored_end(expr: &Expression) -> bool {
+    is_anchored(expr, <[BasicExpression]>::last, &[Anchor::End, Anchor::TextEnd])
+}
+
+/// Returns `true` if every alternative of `expr` has one of `anchors` at the edge given by `edge`, outside of multi-line
+/// mode.
+fn is_anchored(expr: &Expression, edge: fn(&[BasicExpression]) -> Option<&BasicExpression>, anchors: &[Anchor]) -> bool {
+    let inline_multi_line = expr
+        .iter()
+        .flatten()
+        .any(|basic_expr| matches!(basic_expr, BasicExpression::InlineFlags((enabled, _)) if enabled.contains(&'m')));
+
+    !inline_multi_line
+        && expr.iter().all(|subexpr| match edge(subexpr) {
+            Some(BasicExpression::Anchor(anchor)) => anchors.contains(anchor),
+            Some(BasicExpression::Quantified((Quantifiable::Group(group), None))) => {
+                let multi_line = group
+                    .flags
+                    .as_ref()
+                    .is_some_and(|(enabled, _)| enabled.contains(&'m'));
+
+                !multi_line && is_anchored(&group.expr, edge, anchors)
+            }
+            _ => false,
+        })
+}
+
+/// Returns `true` if some alternative of `expr` matches the empty string at the start of any expression, so that `expr`
+/// matches every expression.
+///
+/// This is conservative, as only anchors satisfied at the start of any expression, i.e. `^` and `\A`, are matched, and
+/// recursions and backreferences never are, e.g. `a*$` matches every expression, but at its end, so it does not count.
+pub fn always_matches(expr: &Expression) -> bool {
+    expr.iter().any(|subexpr| {
+        subexpr.iter().all(|basic_expr| match basic_expr {
+            BasicExpression::Anchor(anchor) => matches!(anchor, Anchor::Start | Anchor::TextStart | Anchor::LineStart),
+            BasicExpression::InlineFlags(_) => true,
+            BasicExpression::Quantified((quantifiable, quantifier)) => {
+                let optional = matches!(
+                    quantifier.as_ref().map(Quantifier::greedy),
+                    Some(Quantifier::ZeroOrMore | Quantifier::ZeroOrOne | Quantifier::Range((0, _)))
+                );
+
+                optional || matches!(quantifiable, Quantifiable::Group(group) if always_matches(&group.expr))
+            }
+        })
+    })
+}
+
+/// Returns the string matched by `expr` if it is a concatenation of characters, without any anchor, quantifier or group.
+pub fn literal(expr: &Expression) -> Option<String> {
+    match expr.as_slice() {
+        [subexpr] => subexpr.iter().map(as_char).collect(),
+        _ => None,
+    }
+}
+
+/// Returns the string which every match of `expr` starts with, made of the characters `expr` starts with, or `None` if
+/// there is no such character.
+pub fn required_prefix(expr: &Expression) -> Option<String> {
+    match expr.as_slice() {
+        [subexpr] => Some(subexpr.iter().map_while(as_char).collect()).filter(|prefix: &String| !prefix.is_empty()),
+        _ => None,
+    }
+}
+
+/// Returns the character matched by `basic_expr` if it is a single unquantified character.
+fn as_char(basic_expr: &BasicExpression) -> Option<char> {
+    match basic_expr {
+        BasicExpression::Quantified((Quantifiable::Match(Match::Char(c)), None)) => Some(*c),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `quantifier` repeats without an upper bound.
+fn is_unbounded(quantifier: &Quantifier) -> bool {
+    matches!(quantifier.greedy(), Quantifier::ZeroOrMore | Quantifier::OneOrMore | Quantifier::Range((_, None)))
+}
+
+/// Returns `true` if `expr` contains a lazy quantifier, e.g. `a+?`, at any depth.
+pub fn has_lazy_quantifier(expr: &Expression) -> bool {
+    expr.iter().flatten().any(|basic_expr| match basic_expr {
+        BasicExpression::Quantified((quantifiable, quantifier)) => {
+            matches!(quantifier, Some(Quantifier::Lazy(_)))
+                || matches!(quantifiable, Quantifiable::Group(group) if has_lazy_quantifier(&group.expr))
+        }
+        _ => false,
+    })
+}
+
+/// Returns `true` if `expr` contains a quantifier without an upper bound, at any depth.
+fn contains_unbounded(expr: &Expression) -> bool {
+    expr.iter().flatten().any(|basic_expr| match basic_expr {
+        BasicExpression::Quantified((quantifiable, quantifier)) => {
+            quantifier.as_ref().is_some_and(is_unbounded)
+                || matches!(quantifiable, Quantifiable::Group(group) if contains_unbounded(&group.expr))
+        }
+        _ => false,
+    })
+}
+
+/// Returns `true` if `expr` contains a group repeated without an upper bound which itself contains a quantifier without
+/// an upper bound, e.g. `(a*)*` or `(a+b)+`, whose repetitions can split an expression in exponentially many ways.
+pub fn has_nested_unbounded_quantifier(expr: &Expression) -> bool {
+    expr.iter().flatten().any(|basic_expr| match basic_expr {
+        BasicExpression::Quantified((Quantifiable::Group(group), quantifier)) => {
+            (quantifier.as_ref().is_some_and(is_unbounded) && contains_unbounded(&group.expr))
+                || has_nested_unbounded_quantifier(&group.expr)
+        }
+        _ => false,
+    })
+}
+
+/// Returns `true` if `expr` contains recursions or backreferences, which only the backtracking engine can match.
+pub fn requires_backtracking(expr: &Expression) -> bool {
+    expr.iter().flatten().any(|basic_expr| match basic_expr {
+        BasicExpression::Quantified((Quantifiable::Recursion(_) | Quantifiable::Backreference(_), _)) => true,
+        BasicExpression::Quantified((Quantifiable::Group(group), _)) => requires_backtracking(&group.expr),
+        _ => false,
+    })
+}
+
+/// Returns an estimate of the number of states `expr` compiles into, computed without compiling it, so that expressions
+/// expanding into too many states, e.g. through nested range quantifiers, can be rejected before any state is created.
+pub fn estimated_states(expr: &Expression) -> usize {
+    // Each alternation, quantifier and group wraps what it applies to in two states, as does each single element
+    let branches = expr.iter().map(|subexpr| {
+        subexpr
+            .iter()
+            .map(|basic_expr| match basic_expr {
+                BasicExpression::Quantified((quantifiable, quantifier)) => {
+                    let states = match quantifiable {
+                        Quantifiable::Group(group) => estimated_states(&group.expr).saturating_add(2),
+                        _ => 2,
+                    };
+                    // A range quantifier repeats what it applies to, each repetition being quantified itself
+                    let repeated = states.saturating_add(2);
+                    match quantifier.as_ref().map(Quantifier::greedy) {
+                        None => states,
+                        Some(Quantifier::Range((lower, None))) => repeated.saturating_mul(*lower as usize + 1),
+                        Some(Quantifier::Range((_, Some(upper)))) => repeated.saturating_mul((*upper as usize).max(1)),
+                        Some(_) => repeated,
+                    }
+                }
+                BasicExpression::Anchor(_) | BasicExpression::InlineFlags(_) => 2,
+            })
+            .fold(0, usize::saturating_add)
+    });
+
+    branches.fold(2 * expr.len().saturating_sub(1), usize::saturating_add)
+}
+
+/// Returns the branches of the outermost alternation of `expr`, looking through groups wrapping the whole of `expr`.
+pub fn alternation(expr: &Expression) -> &Expression {
+    match expr.as_slice() {
+        [subexpr] => match subexpr.as_slice() {
+            [BasicExpression::Quantified((Quantifiable::Group(group), None))] => alternation(&group.expr),
+            _ => expr,
+        },
+        _ => expr,
+    }
+}
+
+/// Returns the [`Warning`]s about potential issues with `expr`, in the order they appear in `expr`.
+pub fn warnings(expr: &Expression) -> Vec<Warning> {
+    let mut warnings = vec![];
+    for basic_expr in expr.iter().flatten() {
+        match basic_expr {
+            BasicExpression::Quantified((Quantifiable::Group(group), quantifier)) => {
+                if quantifier.as_ref().is_some_and(is_unbounded) && contains_unbounded(&group.expr) {
+                    warnings.push(Warning::NestedUnboundedQuantifier);
+                }
+
+                warnings.extend(self::warnings(&group.expr));
+            }
+            BasicExpression::Quantified((Quantifiable::Match(Match::Any), Some(quantifier))) => {
+                let unbounded = match quantifier.greedy() {
+                    Quantifier::ZeroOrMore => Some(String::from("*")),
+                    Quantifier::OneOrMore => Some(String::from("+")),
+                    Quantifier::Range((lower, None)) => Some(format!("{{{lower},}}")),
+                    Quantifier::ZeroOrOne | Quantifier::Range((_, Some(_))) | Quantifier::Lazy(_) => None,
+                };
+                let lazy = if matches!(quantifier, Quantifier::Lazy(_)) { "?" } else { "" };
+
+                if let Some(quantifier) = unbounded {
+                    warnings.push(Warning::UnboundedAny(format!(".{quantifier}{lazy}")));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    warnings
+}
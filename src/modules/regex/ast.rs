@@ -1,149 +1,445 @@
+use std::fmt::Debug;
 use std::iter;
 
-use super::{Automata, Error};
+use crate::modules::trace::trace;
+
+use super::{Automata, Error, ErrorKind, Flags, PredicateKind};
 
 use super::grammar::{
     Anchor, BasicExpression, CharacterClass, CharacterGroup, CharacterGroupItem, CharacterRange, Expression, Group, Match,
-    Quantifiable, Quantified, Quantifier, SubExpression,
+    PosixClass, PosixClassName, Quantifiable, Quantified, Quantifier, SubExpression,
 };
 
 /// A trait that allows types to be compiled into an [`Automata`].
-pub trait AbstractSyntaxTree {
-    /// Compiles type into an [`Automata`]
-    fn compile(&self) -> Result<Automata, Error>;
+pub trait AbstractSyntaxTree: Debug {
+    /// Compiles type into an [`Automata`] according to `flags`, logging each compiled element with the `trace` feature.
+    fn compile(&self, flags: &Flags) -> Result<Automata, Error> {
+        trace!("compiling {self:?}");
+        self.build(flags)
+    }
+
+    /// Builds the [`Automata`] of type according to `flags`, which is only to be called through
+    /// [`AbstractSyntaxTree::compile`].
+    fn build(&self, flags: &Flags) -> Result<Automata, Error>;
+}
+
+/// A trait that allows types to be compiled into a predicate on [`prim@char`].
+trait Predicate {
+    /// Compiles type into a predicate on [`prim@char`] according to `flags`.
+    fn predicate(&self, flags: &Flags) -> Box<dyn Fn(char) -> bool>;
+
+    /// Returns the [`PredicateKind`] describing the characters accepted by the predicate according to `flags`.
+    fn kind(&self, flags: &Flags) -> PredicateKind;
 }
 
 /// Folds a non-empty `Iterator<Item = Result<T, Error>>` into a single [`Result<T, Error>`] using `f`.
 fn fold<T, I: Iterator<Item = Result<T, Error>>, F: Fn(T, T) -> T>(mut it: I, f: F) -> Result<T, Error> {
     let initial = it
         .next()
-        .ok_or_else(|| Error::from("Internal Error: Iterator was expected to be non-empty"))?;
+        .ok_or_else(|| Error::new(ErrorKind::Internal(String::from("Iterator was expected to be non-empty"))))?;
 
     it.fold(initial, |acc, elem| Ok(f(acc?, elem?)))
 }
 
+/// Rejects `c` if it is a non-ASCII letter matched case insensitively without Unicode, e.g. under an inline `(?i)`, as
+/// its case variants would otherwise be silently ignored.
+fn check_case_variants(c: char, flags: &Flags) -> Result<(), Error> {
+    if flags.case_insensitive && !flags.unicode && !c.is_ascii() && c.is_alphabetic() {
+        Err(Error::new(ErrorKind::UnicodeRequired))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns an iterator over `c` and its lowercase and uppercase variants, restricted to ASCII unless `unicode` is set.
+fn case_variants(c: char, unicode: bool) -> impl Iterator<Item = char> {
+    let (lower, upper) = if unicode {
+        (c.to_lowercase().collect::<Vec<_>>(), c.to_uppercase().collect::<Vec<_>>())
+    } else {
+        (vec![c.to_ascii_lowercase()], vec![c.to_ascii_uppercase()])
+    };
+
+    // Only single character variants are considered, as matching is done one character at a time
+    let single = |v: Vec<char>| if v.len() == 1 { v } else { vec![] };
+    iter::once(c).chain(single(lower)).chain(single(upper))
+}
+
 // Implementation of AbstractSyntaxTree for elements of Regex
 
 impl AbstractSyntaxTree for Expression {
-    fn compile(&self) -> Result<Automata, Error> {
-        fold(self.iter().map(AbstractSyntaxTree::compile), Automata::or)
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
+        // Inline flags carry over to the following alternatives, until the end of the enclosing group
+        let mut flags = flags.clone();
+        fold(
+            self.iter().map(|subexpr| {
+                let automata = subexpr.compile(&flags);
+                for basic_expr in subexpr {
+                    if let BasicExpression::InlineFlags(inline_flags) = basic_expr {
+                        flags = flags.scoped(inline_flags);
+                    }
+                }
+
+                automata
+            }),
+            Automata::or,
+        )
     }
 }
 
 impl AbstractSyntaxTree for SubExpression {
-    fn compile(&self) -> Result<Automata, Error> {
-        fold(self.iter().map(AbstractSyntaxTree::compile), Automata::concat)
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
+        let mut flags = flags.clone();
+        fold(
+            self.iter().map(|basic_expr| {
+                if let BasicExpression::InlineFlags(inline_flags) = basic_expr {
+                    flags = flags.scoped(inline_flags);
+                }
+
+                basic_expr.compile(&flags)
+            }),
+            Automata::concat,
+        )
     }
 }
 
 impl AbstractSyntaxTree for BasicExpression {
-    fn compile(&self) -> Result<Automata, Error> {
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
         match self {
-            BasicExpression::Anchor(anchor) => anchor.compile(),
-            BasicExpression::Quantified(quantified) => quantified.compile(),
+            BasicExpression::Anchor(anchor) => anchor.compile(flags),
+            // Inline flags only affect how the following expressions are compiled
+            BasicExpression::InlineFlags(_) => Ok(Automata::empty()),
+            BasicExpression::Quantified(quantified) => quantified.compile(flags),
         }
     }
 }
 
 impl AbstractSyntaxTree for Anchor {
-    fn compile(&self) -> Result<Automata, Error> {
-        Ok(Automata::from_anchor(*self))
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
+        Ok(Automata::from_anchor(match self {
+            Anchor::Start if flags.multi_line => Anchor::LineStart,
+            Anchor::End if flags.multi_line => Anchor::LineEnd,
+            anchor => *anchor,
+        }))
     }
 }
 
 impl AbstractSyntaxTree for Quantified {
-    fn compile(&self) -> Result<Automata, Error> {
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
         let (quantifiable, quantifier) = self;
-        let make = || quantifiable.compile();
+        let make = || quantifiable.compile(flags);
 
-        match quantifier {
+        // Lazy quantifiers only differ in which path they prefer, hence in the match the backtracking engine reports
+        let lazy = matches!(quantifier, Some(Quantifier::Lazy(_)));
+        let closure = |automata: Automata| if lazy { automata.closure_lazy() } else { automata.closure() };
+        let plus = |automata: Automata| if lazy { automata.plus_lazy() } else { automata.plus() };
+        let optional = |automata: Automata| if lazy { automata.optional_lazy() } else { automata.optional() };
+
+        match quantifier.as_ref().map(Quantifier::greedy) {
             None => make(),
-            Some(Quantifier::ZeroOrMore) => Ok(make()?.closure()),
-            Some(Quantifier::OneOrMore) => Ok(make()?.plus()),
-            Some(Quantifier::ZeroOrOne) => Ok(make()?.optional()),
-            Some(Quantifier::Range((lower, maybe_upper))) => {
+            Some(Quantifier::ZeroOrMore) => Ok(closure(make()?)),
+            Some(Quantifier::OneOrMore) => Ok(plus(make()?)),
+            Some(Quantifier::ZeroOrOne) => Ok(optional(make()?)),
+            Some(Quantifier::Range((0, Some(0)))) => Ok(Automata::empty()),
+            Some(Quantifier::Range((lower, Some(upper)))) => {
                 let lower_autos = (0..*lower).map(|_| make());
 
-                let upper: Vec<Result<Automata, Error>> = if let Some(upper) = maybe_upper {
-                    (*lower..*upper).map(|_| Ok(make()?.optional())).collect()
-                } else {
-                    iter::once(Ok(make()?.closure())).collect()
-                };
+                // Optional repetitions are nested, so that none is attempted once one is skipped, e.g. `a{1,3}` is
+                // `a(?:a(?:a)?)?`, rather than `aa?a?`
+                let mut optionals: Option<Automata> = None;
+                for _ in *lower..*upper {
+                    let automata = match optionals {
+                        Some(inner) => make()?.concat(inner),
+                        None => make()?,
+                    };
+                    optionals = Some(optional(automata));
+                }
 
-                fold(lower_autos.chain(upper), Automata::concat)
+                fold(lower_autos.chain(optionals.map(Ok)), Automata::concat)
             }
+            Some(Quantifier::Range((lower, None))) => {
+                // The last repetition repeats, e.g. `a{2,}` is `aa+`, so that it is the one repeating as in `a+`
+                let lower_autos = (1..*lower).map(|_| make());
+                let last = if *lower == 0 { closure(make()?) } else { plus(make()?) };
+
+                fold(lower_autos.chain(iter::once(Ok(last))), Automata::concat)
+            }
+            Some(Quantifier::Lazy(_)) => Err(Error::new(ErrorKind::Internal(String::from("Lazy quantifier was nested")))),
         }
     }
 }
 
 impl AbstractSyntaxTree for Quantifiable {
-    fn compile(&self) -> Result<Automata, Error> {
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
         match self {
-            Quantifiable::Group(g) => g.compile(),
-            Quantifiable::Match(m) => m.compile(),
-            Quantifiable::Backreference(_) => Err(Error::from("Internal Error: Backreference not implemented!")),
+            Quantifiable::Recursion(index) => Ok(Automata::from_call(*index as usize)),
+            Quantifiable::Group(g) => g.compile(flags),
+            Quantifiable::Match(m) => m.compile(flags),
+            Quantifiable::Backreference(index) => Ok(Automata::from_backreference(*index as usize)),
+            Quantifiable::NamedBackreference(_) => {
+                Err(Error::new(ErrorKind::Internal(String::from("Named backreference was not resolved"))))
+            }
         }
     }
 }
 
 impl AbstractSyntaxTree for Group {
-    fn compile(&self) -> Result<Automata, Error> {
-        println!("Non capturing mode: {}", self.0);
-
-        self.1.compile()
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
+        let automata = if let Some(group_flags) = &self.flags {
+            self.expr.compile(&flags.scoped(group_flags))?
+        } else {
+            self.expr.compile(flags)?
+        };
+        Ok(match self.index {
+            Some(index) => automata.capture(index),
+            None => automata,
+        })
     }
 }
 
 impl AbstractSyntaxTree for Match {
-    fn compile(&self) -> Result<Automata, Error> {
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
         match self {
-            Match::Any => Ok(Automata::from_lambda(|_| true)),
-            Match::CharacterClass(cc) => cc.compile(),
-            Match::CharacterGroup(cg) => cg.compile(),
-            Match::Char(c) => c.compile(),
+            Match::Any if flags.dot_all => Ok(Automata::from_lambda(|_| true, PredicateKind::Any)),
+            Match::Any | Match::AnyExceptNewline => {
+                Ok(Automata::from_lambda(|c| c != '\n', PredicateKind::Not(Box::new(PredicateKind::Char('\n')))))
+            }
+            Match::CharacterClass(cc) => cc.compile(flags),
+            Match::CharacterGroup(cg) => cg.compile(flags),
+            Match::Char(c) => c.compile(flags),
         }
     }
 }
 
 impl AbstractSyntaxTree for CharacterClass {
-    fn compile(&self) -> Result<Automata, Error> {
-        Ok(match self {
-            CharacterClass::Alphanumeric => Automata::from_lambda(|x| x.is_ascii_alphanumeric()),
-            CharacterClass::NotAlphanumeric => Automata::from_lambda(|x| !x.is_ascii_alphanumeric()),
-            CharacterClass::Digit => Automata::from_lambda(|x| x.is_ascii_digit()),
-            CharacterClass::NotDigit => Automata::from_lambda(|x| !x.is_ascii_digit()),
-            CharacterClass::Whitespace => Automata::from_lambda(|x| x.is_ascii_whitespace()),
-            CharacterClass::NotWhitespace => Automata::from_lambda(|x| !x.is_ascii_whitespace()),
-        })
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
+        Ok(Automata::from_closure(self.predicate(flags), self.kind(flags)))
     }
 }
 
 impl AbstractSyntaxTree for CharacterGroup {
-    fn compile(&self) -> Result<Automata, Error> {
-        fold(self.iter().map(AbstractSyntaxTree::compile), Automata::or)
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
+        if self.items.is_empty() {
+            return if flags.allow_empty_class {
+                Ok(Automata::never())
+            } else {
+                Err(Error::new(ErrorKind::EmptyCharacterGroup))
+            };
+        }
+
+        for item in &self.items {
+            match item {
+                CharacterGroupItem::CharacterRange((lower, upper)) => {
+                    check_case_variants(*lower, flags)?;
+                    check_case_variants(*upper, flags)?;
+                }
+                CharacterGroupItem::Char(c) => check_case_variants(*c, flags)?,
+                CharacterGroupItem::PosixClass(_) | CharacterGroupItem::CharacterClass(_) => {}
+            }
+        }
+
+        Ok(Automata::from_closure(self.predicate(flags), self.kind(flags)))
     }
 }
 
-impl AbstractSyntaxTree for CharacterGroupItem {
-    fn compile(&self) -> Result<Automata, Error> {
+impl AbstractSyntaxTree for char {
+    fn build(&self, flags: &Flags) -> Result<Automata, Error> {
+        check_case_variants(*self, flags)?;
+        if flags.case_insensitive && case_variants(*self, flags.unicode).any(|c| c != *self) {
+            Ok(Automata::from_closure(self.predicate(flags), self.kind(flags)))
+        } else {
+            Ok(Automata::from_token(*self))
+        }
+    }
+}
+
+// Implementation of Predicate for elements of Regex
+
+impl Predicate for CharacterClass {
+    fn predicate(&self, flags: &Flags) -> Box<dyn Fn(char) -> bool> {
+        match (self, flags.unicode) {
+            (CharacterClass::Alphanumeric, _) => {
+                let is_word_char = flags.word_char_predicate();
+                Box::new(move |x| is_word_char(x))
+            }
+            (CharacterClass::NotAlphanumeric, _) => {
+                let is_word_char = flags.word_char_predicate();
+                Box::new(move |x| !is_word_char(x))
+            }
+            (CharacterClass::Digit, false) => Box::new(|x| x.is_ascii_digit()),
+            (CharacterClass::Digit, true) => Box::new(|x| x.is_numeric()),
+            (CharacterClass::NotDigit, false) => Box::new(|x| !x.is_ascii_digit()),
+            (CharacterClass::NotDigit, true) => Box::new(|x| !x.is_numeric()),
+            (CharacterClass::Whitespace, false) => Box::new(|x| x.is_ascii_whitespace()),
+            (CharacterClass::Whitespace, true) => Box::new(|x| x.is_whitespace()),
+            (CharacterClass::NotWhitespace, false) => Box::new(|x| !x.is_ascii_whitespace()),
+            (CharacterClass::NotWhitespace, true) => Box::new(|x| !x.is_whitespace()),
+        }
+    }
+
+    fn kind(&self, flags: &Flags) -> PredicateKind {
+        // Unicode classes and custom word characters are opaque
+        let ascii = |kind| if flags.unicode { PredicateKind::Custom } else { kind };
+        let word = if flags.word_chars.is_some() { PredicateKind::Custom } else { ascii(PredicateKind::Word) };
+        let not = |kind| PredicateKind::Not(Box::new(kind));
+
         match self {
-            CharacterGroupItem::CharacterClass(cc) => cc.compile(),
-            CharacterGroupItem::CharacterRange(cr) => cr.compile(),
-            CharacterGroupItem::Char(c) => c.compile(),
+            CharacterClass::Alphanumeric => word,
+            CharacterClass::NotAlphanumeric => not(word),
+            CharacterClass::Digit => ascii(PredicateKind::Digit),
+            CharacterClass::NotDigit => not(ascii(PredicateKind::Digit)),
+            CharacterClass::Whitespace => ascii(PredicateKind::Whitespace),
+            CharacterClass::NotWhitespace => not(ascii(PredicateKind::Whitespace)),
         }
     }
 }
 
-impl AbstractSyntaxTree for CharacterRange {
-    fn compile(&self) -> Result<Automata, Error> {
+impl Predicate for CharacterGroup {
+    fn predicate(&self, flags: &Flags) -> Box<dyn Fn(char) -> bool> {
+        let predicates: Vec<_> = self
+            .items
+            .iter()
+            .map(|item| item.predicate(flags))
+            .collect();
+        let inverted = self.inverted;
+
+        // The inversion wraps the union of all items, so that e.g. `[^\d\s]` excludes both digits and whitespaces
+        Box::new(move |c| predicates.iter().any(|p| p(c)) != inverted)
+    }
+
+    fn kind(&self, flags: &Flags) -> PredicateKind {
+        let union = PredicateKind::Union(self.items.iter().map(|item| item.kind(flags)).collect());
+
+        if self.inverted {
+            PredicateKind::Not(Box::new(union))
+        } else {
+            union
+        }
+    }
+}
+
+impl Predicate for CharacterGroupItem {
+    fn predicate(&self, flags: &Flags) -> Box<dyn Fn(char) -> bool> {
+        match self {
+            CharacterGroupItem::PosixClass(pc) => pc.predicate(flags),
+            CharacterGroupItem::CharacterClass(cc) => cc.predicate(flags),
+            CharacterGroupItem::CharacterRange(cr) => cr.predicate(flags),
+            CharacterGroupItem::Char(c) => c.predicate(flags),
+        }
+    }
+
+    fn kind(&self, flags: &Flags) -> PredicateKind {
+        match self {
+            CharacterGroupItem::PosixClass(pc) => pc.kind(flags),
+            CharacterGroupItem::CharacterClass(cc) => cc.kind(flags),
+            CharacterGroupItem::CharacterRange(cr) => cr.kind(flags),
+            CharacterGroupItem::Char(c) => c.kind(flags),
+        }
+    }
+}
+
+/// Returns the ranges of characters in the POSIX class `name`, which are always ASCII, even with Unicode enabled.
+fn posix_ranges(name: PosixClassName) -> &'static [CharacterRange] {
+    match name {
+        PosixClassName::Alnum => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+        PosixClassName::Alpha => &[('A', 'Z'), ('a', 'z')],
+        PosixClassName::Ascii => &[('\0', '\x7f')],
+        PosixClassName::Blank => &[('\t', '\t'), (' ', ' ')],
+        PosixClassName::Cntrl => &[('\0', '\x1f'), ('\x7f', '\x7f')],
+        PosixClassName::Digit => &[('0', '9')],
+        PosixClassName::Graph => &[('!', '~')],
+        PosixClassName::Lower => &[('a', 'z')],
+        PosixClassName::Print => &[(' ', '~')],
+        PosixClassName::Punct => &[('!', '/'), (':', '@'), ('[', '`'), ('{', '~')],
+        PosixClassName::Space => &[('\t', '\r'), (' ', ' ')],
+        PosixClassName::Upper => &[('A', 'Z')],
+        PosixClassName::Word => &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+        PosixClassName::XDigit => &[('0', '9'), ('A', 'F'), ('a', 'f')],
+    }
+}
+
+impl Predicate for PosixClass {
+    fn predicate(&self, flags: &Flags) -> Box<dyn Fn(char) -> bool> {
+        // The inversion applies once case variants are accepted, so that e.g. `(?i)[[:^upper:]]` excludes all letters
+        let predicates: Vec<_> = posix_ranges(self.name)
+            .iter()
+            .map(|range| range.predicate(flags))
+            .collect();
+        let inverted = self.inverted;
+
+        Box::new(move |c| predicates.iter().any(|p| p(c)) != inverted)
+    }
+
+    fn kind(&self, flags: &Flags) -> PredicateKind {
+        let union = PredicateKind::Union(
+            posix_ranges(self.name)
+                .iter()
+                .map(|range| range.kind(flags))
+                .collect(),
+        );
+
+        if self.inverted {
+            PredicateKind::Not(Box::new(union))
+        } else {
+            union
+        }
+    }
+}
+
+impl Predicate for CharacterRange {
+    fn predicate(&self, flags: &Flags) -> Box<dyn Fn(char) -> bool> {
+        let (lower, upper) = *self;
+
+        if flags.case_insensitive {
+            let unicode = flags.unicode;
+            Box::new(move |c| case_variants(c, unicode).any(|v| (lower..=upper).contains(&v)))
+        } else {
+            Box::new(move |c| (lower..=upper).contains(&c))
+        }
+    }
+
+    fn kind(&self, flags: &Flags) -> PredicateKind {
         let (lower, upper) = *self;
 
-        Ok(Automata::from_closure(Box::new(move |c| (lower..=upper).contains(&c))))
+        match (flags.case_insensitive, flags.unicode) {
+            (false, _) => PredicateKind::Range(lower, upper),
+            (true, false) => {
+                // ASCII letters whose other case lies within the range are accepted as well
+                let other_case = |from: char, to: char, convert: fn(&char) -> char| {
+                    let (from, to) = (lower.max(from), upper.min(to));
+                    (from <= to).then(|| PredicateKind::Range(convert(&from), convert(&to)))
+                };
+
+                let ranges = iter::once(PredicateKind::Range(lower, upper))
+                    .chain(other_case('a', 'z', char::to_ascii_uppercase))
+                    .chain(other_case('A', 'Z', char::to_ascii_lowercase));
+                PredicateKind::Union(ranges.collect())
+            }
+            (true, true) => PredicateKind::Custom,
+        }
     }
 }
 
-impl AbstractSyntaxTree for char {
-    fn compile(&self) -> Result<Automata, Error> {
-        Ok(Automata::from_token(*self))
+impl Predicate for char {
+    fn predicate(&self, flags: &Flags) -> Box<dyn Fn(char) -> bool> {
+        let token = *self;
+
+        if flags.case_insensitive {
+            let unicode = flags.unicode;
+            Box::new(move |c| case_variants(c, unicode).any(|v| v == token))
+        } else {
+            Box::new(move |c| c == token)
+        }
+    }
+
+    fn kind(&self, flags: &Flags) -> PredicateKind {
+        match (flags.case_insensitive, flags.unicode) {
+            (false, _) => PredicateKind::Char(*self),
+            (true, false) => PredicateKind::Union(
+                case_variants(*self, false)
+                    .map(PredicateKind::Char)
+                    .collect(),
+            ),
+            (true, true) => PredicateKind::Custom,
+        }
     }
 }
@@ -0,0 +1,92 @@
+use super::flags::WordChars;
+use super::{Engine, Error, ErrorKind, Flags, MatchSemantics, RegExp, REGEX};
+
+/// A builder to configure the compilation of a regular expression into a [`RegExp`].
+pub struct RegExpBuilder {
+    pattern: String,
+    flags: Flags,
+}
+
+impl RegExpBuilder {
+    /// Constructs a [`RegExpBuilder`] for the regular expression `pattern`, with all flags disabled.
+    pub fn new(pattern: &str) -> Self {
+        RegExpBuilder { pattern: String::from(pattern), flags: Flags::default() }
+    }
+
+    /// Sets whether letters match both their uppercase and lowercase variants.
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.flags.case_insensitive = yes;
+        self
+    }
+
+    /// Sets whether `^` and `$` match at the start and end of every line.
+    pub fn multi_line(mut self, yes: bool) -> Self {
+        self.flags.multi_line = yes;
+        self
+    }
+
+    /// Sets whether `.` matches `\n`.
+    pub fn dot_all(mut self, yes: bool) -> Self {
+        self.flags.dot_all = yes;
+        self
+    }
+
+    /// Sets whether character classes and case insensitivity follow Unicode, rather than being restricted to ASCII.
+    pub fn unicode(mut self, yes: bool) -> Self {
+        self.flags.unicode = yes;
+        self
+    }
+
+    /// Sets the characters matched by `\w` and delimited by `\b` to those satisfying `predicate`, rather than alphanumeric
+    /// characters.
+    pub fn word_chars<F: Fn(char) -> bool + 'static>(mut self, predicate: F) -> Self {
+        self.flags.word_chars = Some(WordChars::new(predicate));
+        self
+    }
+
+    /// Sets which match is reported among those starting at the leftmost position, which is the longest by default.
+    pub fn semantics(mut self, semantics: MatchSemantics) -> Self {
+        self.flags.semantics = semantics;
+        self
+    }
+
+    /// Sets whether an empty character group `[]` compiles into a pattern matching nothing, e.g. `[]x` never matches,
+    /// rather than being rejected.
+    pub fn allow_empty_class(mut self, yes: bool) -> Self {
+        self.flags.allow_empty_class = yes;
+        self
+    }
+
+    /// Sets which engine matches the regular expression, which is only the backtracking engine when required by default.
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.flags.engine = engine;
+        self
+    }
+
+    /// Sets the number of states the regular expression may compile into, as estimated before compiling it, which is
+    /// [`DEFAULT_MAX_STATES`](super::DEFAULT_MAX_STATES) by default.
+    pub fn max_states(mut self, limit: usize) -> Self {
+        self.flags.max_states = limit;
+        self
+    }
+
+    /// Compiles the regular expression into a [`RegExp`] according to the configured flags.
+    ///
+    /// Case insensitive matching of non-ASCII letters requires Unicode, so enabling case insensitivity without Unicode on
+    /// a regular expression containing non-ASCII letters is rejected, whether by flag or inline, e.g. `(?i)é`. Likewise,
+    /// forcing [`Engine::Nfa`] on a regular expression requiring the backtracking engine, e.g. with a backreference, is
+    /// rejected.
+    pub fn build(&self) -> Result<RegExp, Error> {
+        if self.flags.case_insensitive
+            && !self.flags.unicode
+            && self
+                .pattern
+                .chars()
+                .any(|c| !c.is_ascii() && c.is_alphabetic())
+        {
+            return Err(Error::new(ErrorKind::UnicodeRequired));
+        }
+
+        REGEX.with(|regex| regex.compile_with_flags(&self.pattern, self.flags.clone()))
+    }
+}
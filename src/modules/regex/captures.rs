@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::replacement::{self, Reference, ReplacementItem};
+use super::Match;
+
+/// The substrings of an expression captured by a match of a regular expression, indexed by capturing group.
+///
+/// The capturing group of index 0 is the entire match.
+#[derive(Debug)]
+pub struct Captures<'h> {
+    expr: &'h str,
+    matches: Vec<Option<Match>>,
+    /// Index of each named capturing group, by name.
+    names: Rc<HashMap<String, usize>>,
+}
+
+impl<'h> Captures<'h> {
+    /// Constructs a [`Captures`] of `expr`, where `matches` holds the [`Match`] of each capturing group, if any, and
+    /// `names` the index of each named capturing group.
+    pub fn new(expr: &'h str, matches: Vec<Option<Match>>, names: Rc<HashMap<String, usize>>) -> Self {
+        Captures { expr, matches, names }
+    }
+
+    /// Returns the substring captured by the capturing group of index `index`, or `None` if there is no such group or it
+    /// did not participate in the match.
+    pub fn get(&self, index: usize) -> Option<&'h str> {
+        self.get_match(index).map(|m| m.as_str(self.expr))
+    }
+
+    /// Returns the substring captured by the capturing group named `name`, or `None` if there is no such group or it did
+    /// not participate in the match.
+    pub fn name(&self, name: &str) -> Option<&'h str> {
+        self.get(*self.names.get(name)?)
+    }
+
+    /// Returns the [`Match`] of the capturing group of index `index`, or `None` if there is no such group or it did not
+    /// participate in the match.
+    pub fn get_match(&self, index: usize) -> Option<Match> {
+        self.matches.get(index).copied().flatten()
+    }
+
+    /// Returns the [`Match`] of the capturing group named `name`, or `None` if there is no such group or it did not
+    /// participate in the match.
+    pub fn name_match(&self, name: &str) -> Option<Match> {
+        self.get_match(*self.names.get(name)?)
+    }
+
+    /// Returns `template` with each reference to a capturing group replaced by the substring it captured, e.g. `"$2-$1"`
+    /// gives `"abc-123"` for the captures of `(\d+)\-(\w+)` on `"123-abc"`.
+    ///
+    /// References are written as in [`RegExp::replace_all`](super::RegExp::replace_all), i.e. `$n` or `${n}` for the
+    /// capturing group of index `n`, `$name` or `${name}` for the capturing group named `name`, and `$$` for a literal
+    /// `$`. A reference to a capturing group which does not exist or did not participate in the match expands to nothing.
+    pub fn expand(&self, template: &str) -> String {
+        let mut expanded = String::new();
+        for item in replacement::parse(template) {
+            match item {
+                ReplacementItem::Literal(c) => expanded.push(c),
+                ReplacementItem::Reference(Reference::Index(index)) => expanded.push_str(self.get(index).unwrap_or_default()),
+                ReplacementItem::Reference(Reference::Name(name)) => expanded.push_str(self.name(&name).unwrap_or_default()),
+            }
+        }
+
+        expanded
+    }
+
+    /// Returns the number of capturing groups, including the entire match.
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Returns `true` if there are no capturing groups, not even the entire match.
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+}
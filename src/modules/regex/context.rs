@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Error, ErrorKind};
+
+use super::grammar::{BasicExpression, Expression, Group, Match, Quantifiable, Quantified, Quantifier, Regex, SubExpression};
+
+/// Context gathered from the syntax representation of a Regex, e.g. its capturing groups.
+#[derive(Debug, Default)]
+pub struct RegexContext {
+    group_count: usize,
+    names: Rc<HashMap<String, usize>>,
+}
+
+impl RegexContext {
+    /// Processes `regex` and returns the gathered [`RegexContext`].
+    ///
+    /// Capturing groups are indexed in the order of their opening parenthesis, starting from 1, and backreferences are
+    /// resolved against them, by index or by name.
+    pub fn process(regex: &mut Regex) -> Result<Self, Error> {
+        let mut context = RegexContext::default();
+        context.index_expression(regex)?;
+        context.resolve_expression(regex)?;
+
+        Ok(context)
+    }
+
+    /// Returns the number of capturing groups.
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    /// Returns the index of the capturing group named `name`, or `None` if there is no such group.
+    pub fn group_index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns the index of each named capturing group, by name.
+    pub fn names(&self) -> Rc<HashMap<String, usize>> {
+        self.names.clone()
+    }
+
+    fn index_expression(&mut self, expr: &mut Expression) -> Result<(), Error> {
+        for basic_expr in expr.iter_mut().flatten() {
+            if let BasicExpression::Quantified((Quantifiable::Group(group), _)) = basic_expr {
+                self.index_group(group)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn index_group(&mut self, group: &mut Group) -> Result<(), Error> {
+        group.index = if group.non_capturing {
+            None
+        } else {
+            self.group_count += 1;
+            Some(self.group_count)
+        };
+
+        if let Some(name) = &group.name {
+            if Rc::make_mut(&mut self.names)
+                .insert(name.clone(), self.group_count)
+                .is_some()
+            {
+                return Err(Error::new(ErrorKind::DuplicateGroupName(name.clone())));
+            }
+        }
+
+        // Groups are indexed in pre-order, so that an outer group is indexed before its inner groups
+        self.index_expression(&mut group.expr)
+    }
+
+    fn resolve_expression(&self, expr: &mut Expression) -> Result<(), Error> {
+        expr.iter_mut()
+            .try_for_each(|subexpr| self.resolve_subexpression(subexpr))
+    }
+
+    fn resolve_subexpression(&self, subexpr: &mut SubExpression) -> Result<(), Error> {
+        let mut resolved = Vec::with_capacity(subexpr.len());
+
+        for basic_expr in subexpr.drain(..) {
+            match basic_expr {
+                BasicExpression::Quantified((Quantifiable::Group(mut group), quantifier)) => {
+                    self.resolve_expression(&mut group.expr)?;
+                    resolved.push(BasicExpression::Quantified((Quantifiable::Group(group), quantifier)));
+                }
+                BasicExpression::Quantified((Quantifiable::Backreference(n), quantifier)) => {
+                    resolved.extend(self.resolve_backreference((n, quantifier))?);
+                }
+                BasicExpression::Quantified((Quantifiable::NamedBackreference(name), quantifier)) => {
+                    let index = self
+                        .group_index(&name)
+                        .ok_or(Error::new(ErrorKind::UndefinedNamedBackreference(name)))?;
+                    resolved.push(BasicExpression::Quantified((Quantifiable::Backreference(index as u32), quantifier)));
+                }
+                BasicExpression::Quantified((Quantifiable::Recursion(n), _)) if n as usize > self.group_count => {
+                    return Err(Error::new(ErrorKind::UndefinedRecursion(n)));
+                }
+                _ => resolved.push(basic_expr),
+            }
+        }
+
+        *subexpr = resolved;
+        Ok(())
+    }
+
+    /// Resolves the backreference `\n`, following PCRE's rules.
+    ///
+    /// `\n` is a backreference if `n < 10` or if there are at least `n` capturing groups. Otherwise, it is read as an
+    /// octal escape of up to three octal digits, with any following digits being literal characters.
+    fn resolve_backreference(&self, (n, quantifier): (u32, Option<Quantifier>)) -> Result<Vec<BasicExpression>, Error> {
+        if n as usize <= self.group_count {
+            return Ok(vec![BasicExpression::Quantified((Quantifiable::Backreference(n), quantifier))]);
+        } else if n < 10 {
+            return Err(Error::new(ErrorKind::UndefinedBackreference(n)));
+        }
+
+        let digits = n.to_string();
+        let octal_len = digits.chars().take(3).take_while(|d| d.is_digit(8)).count();
+
+        let mut chars: Vec<char> = vec![];
+        if octal_len > 0 {
+            let code = u32::from_str_radix(&digits[..octal_len], 8).expect("octal digits should parse");
+            chars.push(char::from_u32(code).expect("three octal digits should be a valid char"));
+        }
+        chars.extend(digits[octal_len..].chars());
+
+        // Only the last character is subject to the quantifier
+        let last = chars.len() - 1;
+        Ok(chars
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let quantified: Quantified = (Quantifiable::Match(Match::Char(c)), None);
+                BasicExpression::Quantified(if i == last { (quantified.0, quantifier.clone()) } else { quantified })
+            })
+            .collect())
+    }
+}
+
+/// Shifts the indices of the backreferences and recursions to capturing groups of `expr` by `offset`, e.g. once `expr`
+/// follows `offset` capturing groups of another [`Regex`] it is composed with.
+///
+/// A recursion `(?0)` to the entire regular expression is left as it is.
+pub fn shift_references(expr: &mut Expression, offset: usize) {
+    let offset = u32::try_from(offset).expect("the number of capturing groups should fit in a u32");
+
+    for basic_expr in expr.iter_mut().flatten() {
+        match basic_expr {
+            BasicExpression::Quantified((Quantifiable::Group(group), _)) => shift_references(&mut group.expr, offset as usize),
+            BasicExpression::Quantified((Quantifiable::Backreference(n), _)) => *n += offset,
+            BasicExpression::Quantified((Quantifiable::Recursion(n), _)) if *n > 0 => *n += offset,
+            _ => {}
+        }
+    }
+}
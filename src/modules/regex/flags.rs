@@ -0,0 +1,134 @@
+use std::fmt::{Debug, Formatter, Result};
+use std::rc::Rc;
+
+use super::grammar::GroupFlags;
+
+/// The number of states a regular expression may compile into by default, see [`Flags::max_states`].
+///
+/// This is far beyond what regular expressions written by hand compile into, but bounds the memory and time taken by
+/// range quantifiers expanding into many states, e.g. `(ab){0,100000}`, which is rejected unless the limit is raised.
+pub const DEFAULT_MAX_STATES: usize = 200_000;
+
+/// Flags altering how a regular expression is compiled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flags {
+    /// Letters match both their uppercase and lowercase variants.
+    pub case_insensitive: bool,
+    /// `^` and `$` match at the start and end of every line, rather than only at the start and end of the expression.
+    pub multi_line: bool,
+    /// `.` matches any character, including `\n`.
+    pub dot_all: bool,
+    /// Character classes and case insensitivity follow Unicode, rather than being restricted to ASCII.
+    pub unicode: bool,
+    /// Characters matched by `\w` and delimited by `\b`, or `None` for alphanumeric characters.
+    pub word_chars: Option<WordChars>,
+    /// Which match is reported among those starting at the leftmost position, and what its capturing groups capture.
+    pub semantics: MatchSemantics,
+    /// Which engine matches the regular expression.
+    pub engine: Engine,
+    /// An empty character group `[]` compiles into a pattern matching nothing, rather than being rejected.
+    pub allow_empty_class: bool,
+    /// The number of states the regular expression may compile into, as estimated before compiling it, beyond which
+    /// compilation is rejected, e.g. to bound the work done on untrusted regular expressions with range quantifiers.
+    pub max_states: usize,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Flags {
+            case_insensitive: false,
+            multi_line: false,
+            dot_all: false,
+            unicode: false,
+            word_chars: None,
+            semantics: MatchSemantics::default(),
+            engine: Engine::default(),
+            allow_empty_class: false,
+            max_states: DEFAULT_MAX_STATES,
+        }
+    }
+}
+
+/// The engine matching a regular expression.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Engine {
+    /// The simulation of the NFA, which matches in time linear in the length of the expression, but supports neither
+    /// backreferences, recursions nor [`MatchSemantics::PerlLeftmostFirst`].
+    Nfa,
+    /// The backtracking engine, which supports every regular expression, but may take exponential time.
+    Backtracking,
+    /// The simulation of the NFA, unless the regular expression requires the backtracking engine.
+    #[default]
+    Auto,
+}
+
+/// Rules selecting which match is reported among those starting at the leftmost position of an expression.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MatchSemantics {
+    /// The match found first when trying alternatives in order and quantifiers greedily, unless lazy, as in Perl, e.g.
+    /// `a|ab` matches only `"a"` of `"ab"`. Capturing groups capture what they matched along the way.
+    PerlLeftmostFirst,
+    /// The longest match, as in POSIX, e.g. `a|ab` matches the whole of `"ab"`. Among the ways of matching it, each
+    /// capturing group in turn captures the leftmost and then longest substring it can, e.g. `(a|ab)(c|bcd)(d*)` captures
+    /// `"ab"`, `"c"` and `"d"` of `"abcd"`.
+    ///
+    /// Lazy quantifiers, e.g. `a+?`, have no meaning for the longest match, so regular expressions containing them are
+    /// matched with [`MatchSemantics::PerlLeftmostFirst`] instead.
+    #[default]
+    PosixLeftmostLongest,
+}
+
+impl Flags {
+    /// Enables or disables the flag denoted by `flag` within a group, i.e. `i`, `m`, `s` or `u`.
+    pub fn set(&mut self, flag: char, enabled: bool) {
+        match flag {
+            'i' => self.case_insensitive = enabled,
+            'm' => self.multi_line = enabled,
+            's' => self.dot_all = enabled,
+            'u' => self.unicode = enabled,
+            _ => {}
+        }
+    }
+
+    /// Returns a copy of the [`Flags`] where the flags of `group_flags` are enabled and disabled.
+    pub fn scoped(&self, (enabled, disabled): &GroupFlags) -> Flags {
+        let mut scoped = self.clone();
+        enabled.iter().for_each(|&flag| scoped.set(flag, true));
+        disabled.iter().for_each(|&flag| scoped.set(flag, false));
+
+        scoped
+    }
+
+    /// Returns the predicate defining word characters, which defaults to ASCII alphanumeric characters, or all
+    /// alphanumeric characters with Unicode.
+    pub fn word_char_predicate(&self) -> Rc<dyn Fn(char) -> bool> {
+        match &self.word_chars {
+            Some(WordChars(predicate)) => predicate.clone(),
+            None if self.unicode => Rc::new(char::is_alphanumeric),
+            None => Rc::new(|c: char| c.is_ascii_alphanumeric()),
+        }
+    }
+}
+
+/// A predicate defining which characters are word characters.
+#[derive(Clone)]
+pub struct WordChars(Rc<dyn Fn(char) -> bool>);
+
+impl WordChars {
+    /// Constructs a [`WordChars`] where word characters are those satisfying `predicate`.
+    pub fn new<F: Fn(char) -> bool + 'static>(predicate: F) -> Self {
+        WordChars(Rc::new(predicate))
+    }
+}
+
+impl Debug for WordChars {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        f.write_str("WordChars(..)")
+    }
+}
+
+impl PartialEq for WordChars {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -2,8 +2,9 @@ use std::iter;
 
 use crate::union;
 
-use super::alphabet::{any, character, end, escaped, number, string};
+use super::alphabet::{any, character, digit, digits, end, escaped, name, string};
 use super::parser::MonadicParser;
+use super::ErrorKind;
 
 /// A [`MonadicParser`] defining the rules of a formal grammar.
 pub type Grammar<T> = MonadicParser<T>;
@@ -14,8 +15,11 @@ pub type Grammar<T> = MonadicParser<T>;
 pub type Regex = Expression;
 
 /// Returns the [`Grammar`] defining Regex's grammar.
+///
+/// The `END` of the expression is left for [`Language`](super::language::Language) to check, so that it can report
+/// where parsing stopped.
 pub fn regex() -> Grammar<Regex> {
-    expression() << end()
+    expression()
 }
 
 /// `Expression ::= Subexpression ( '|' Subexpression )*`
@@ -35,10 +39,12 @@ fn subexpression() -> MonadicParser<SubExpression> {
     basic_expression().one_or_more()
 }
 
-/// `BasicExpression ::= Anchor | Quantified`
-#[derive(Debug)]
+/// `BasicExpression ::= Anchor | InlineFlags | Quantified`
+#[derive(Clone, Debug)]
 pub enum BasicExpression {
     Anchor(Anchor),
+    /// Flags enabled and disabled from this point until the end of the enclosing group, if declared as `(?flags)`.
+    InlineFlags(InlineFlags),
     Quantified(Quantified),
 }
 
@@ -46,17 +52,44 @@ pub enum BasicExpression {
 fn basic_expression() -> MonadicParser<BasicExpression> {
     union!(
         anchor().map(|a| Some(BasicExpression::Anchor(a))),
+        inline_flags().map(|f| Some(BasicExpression::InlineFlags(f))),
         quantified().map(|q| Some(BasicExpression::Quantified(q))),
     )
 }
 
-/// `Anchor ::= '^' | '$' | '\b' | '\B'`
+/// `Anchor ::= '^' | '$' | '\b' | '\B' | '\A' | '\z' | '\Z'`
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Anchor {
     Start,
     End,
     WordBoundary,
     NotWordBoundary,
+    /// Start of a line, which `^` compiles into in multi-line mode.
+    LineStart,
+    /// End of a line, which `$` compiles into in multi-line mode.
+    LineEnd,
+    /// Start of the expression, even in multi-line mode.
+    TextStart,
+    /// End of the expression, even in multi-line mode.
+    TextEnd,
+    /// End of the expression, or right before a newline ending it.
+    TextEndBeforeNewline,
+}
+
+impl Anchor {
+    /// The number of variants of [`Anchor`], which bounds how many of them are satisfied at once.
+    pub const COUNT: usize = match Anchor::Start {
+        // Matching every variant stops compiling once one is added, until it is counted here too
+        Anchor::Start
+        | Anchor::End
+        | Anchor::WordBoundary
+        | Anchor::NotWordBoundary
+        | Anchor::LineStart
+        | Anchor::LineEnd
+        | Anchor::TextStart
+        | Anchor::TextEnd
+        | Anchor::TextEndBeforeNewline => 9,
+    };
 }
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`Anchor`].
@@ -66,12 +99,23 @@ fn anchor() -> MonadicParser<Anchor> {
         escaped().map(|c| match c {
             'b' => Some(Anchor::WordBoundary),
             'B' => Some(Anchor::NotWordBoundary),
+            'A' => Some(Anchor::TextStart),
+            'z' => Some(Anchor::TextEnd),
+            'Z' => Some(Anchor::TextEndBeforeNewline),
             _ => None,
         }),
         character('$').map(|_| Some(Anchor::End)),
     ]
 }
 
+/// `InlineFlags ::= "(?" GroupFlags ')'`
+pub type InlineFlags = GroupFlags;
+
+/// Returns a [`MonadicParser`] associated to the grammar rule [`InlineFlags`].
+fn inline_flags() -> MonadicParser<InlineFlags> {
+    string("(?") >> group_flags() << character(')')
+}
+
 /// `Quantified ::= Quantifiable Quantifier?`
 pub type Quantified = (Quantifiable, Option<Quantifier>);
 
@@ -80,47 +124,97 @@ fn quantified() -> MonadicParser<Quantified> {
     quantifiable() & quantifier().optional()
 }
 
-/// `Quantifiable ::= Group | Match | Backreference`
-#[derive(Debug)]
+/// `Quantifiable ::= Recursion | Group | Match | Backreference | NamedBackreference`
+#[derive(Clone, Debug)]
 pub enum Quantifiable {
+    Recursion(Recursion),
     Group(Group),
     Match(Match),
     Backreference(Backreference),
+    NamedBackreference(NamedBackreference),
 }
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`Quantifiable`].
 fn quantifiable() -> MonadicParser<Quantifiable> {
     union!(
+        recursion().map(|r| Some(Quantifiable::Recursion(r))),
         group().map(|g| Some(Quantifiable::Group(g))),
         r#match().map(|m| Some(Quantifiable::Match(m))),
-        backreference().map(|br| Some(Quantifiable::Backreference(br)))
+        backreference().map(|br| Some(Quantifiable::Backreference(br))),
+        named_backreference().map(|name| Some(Quantifiable::NamedBackreference(name)))
     )
 }
 
-/// `Group ::= '(' ":?"? Expression ')'`
-pub type Group = (bool, Expression);
-// pub struct Group {
-//     non_capturing: bool,
-//     expr: Box<Expression>,
-// }
+/// `Recursion ::= "(?R)" | "(?" Number ')'`
+///
+/// Calls the capturing group of the given index, where `(?R)` and `(?0)` call the whole regular expression.
+pub type Recursion = u32;
+
+/// Returns a [`MonadicParser`] associated to the grammar rule [`Recursion`].
+fn recursion() -> MonadicParser<Recursion> {
+    let index = (string("(?") >> digits() << character(')'))
+        .reject_if(|index| !fits_u32(index), |index, position| ErrorKind::NumberTooLarge(index, position + 2))
+        .map(|index| index.parse().ok());
+
+    union![string("(?R)").map(|_| Some(0)), index]
+}
+
+/// `Group ::= '(' ( "?:" | "?<" Name '>' | '?' GroupFlags ':' )? Expression ')'`
+#[derive(Clone, Debug)]
+pub struct Group {
+    pub non_capturing: bool,
+    /// Name of the capturing group, if declared as `(?<name>...)`.
+    pub name: Option<String>,
+    /// Flags enabled and disabled within the group, if declared as `(?flags:...)`.
+    pub flags: Option<GroupFlags>,
+    /// Index of the capturing group, assigned when processing the syntax representation.
+    pub index: Option<usize>,
+    pub expr: Expression,
+}
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`Group`].
 fn group() -> MonadicParser<Group> {
-    character('(') >> string(":?").exists() & MonadicParser::lazy(expression) << character(')')
-    // (character('(') >> string(":?").exists() & expression() << character(')')).map(
-    //     |(non_capturing, expr)| {
-    //         Some(Group {
-    //             non_capturing: non_capturing,
-    //             expr: Box::new(expr),
-    //         })
-    //     },
-    // )
-}
-
-/// `Match ::= '.' | CharacterClass | CharacterGroup | Char`
-#[derive(Debug)]
+    let prefix = union![
+        string("?:").map(|_| Some((true, None, None))),
+        (string("?<") >> name() << character('>')).map(|name| Some((false, Some(name), None))),
+        (character('?') >> group_flags() << character(':')).map(|flags| Some((true, None, Some(flags)))),
+    ];
+    let closing = union![character(')').map(|_| Some(true)), end().map(|_| Some(false))];
+
+    // A group still open at the end of the expression can never be closed, so the missing `)` is reported there
+    (character('(') >> prefix.optional() & MonadicParser::lazy(expression).optional() & closing)
+        .reject_if(|(_, closed)| !closed, |_, position| ErrorKind::UnclosedGroup(position))
+        .map(|((prefix, expr), _)| {
+            let (non_capturing, name, flags) = prefix.unwrap_or((false, None, None));
+            Some(Group { non_capturing, name, flags, index: None, expr: expr? })
+        })
+}
+
+/// `GroupFlags ::= GroupFlag* ( '-' GroupFlag* )?`, where `GroupFlag ::= 'i' | 'm' | 's' | 'u'`
+///
+/// The flags before `-` are enabled, and the flags after `-` are disabled.
+pub type GroupFlags = (Vec<char>, Vec<char>);
+
+/// Returns a [`MonadicParser`] associated to the grammar rule [`GroupFlags`].
+fn group_flags() -> MonadicParser<GroupFlags> {
+    let group_flag = || any().filter(|c| matches!(c, 'i' | 'm' | 's' | 'u'));
+
+    (group_flag().repeat() & (character('-') >> group_flag().repeat()).optional()).map(|(enabled, disabled)| {
+        let disabled = disabled.unwrap_or_default();
+        if enabled.is_empty() && disabled.is_empty() {
+            None
+        } else {
+            Some((enabled, disabled))
+        }
+    })
+}
+
+/// `Match ::= '.' | '\N' | CharacterClass | CharacterGroup | Char`
+#[derive(Clone, Debug)]
 pub enum Match {
     Any,
+    /// Any character except `\n`, even in dot-all mode, if declared as `\N`.
+    AnyExceptNewline,
     CharacterClass(CharacterClass),
     CharacterGroup(CharacterGroup),
     Char(char),
@@ -130,31 +224,48 @@ pub enum Match {
 fn r#match() -> MonadicParser<Match> {
     union![
         character('.').map(|_| Some(Match::Any)),
+        escaped()
+            .filter(|&c| c == 'N')
+            .map(|_| Some(Match::AnyExceptNewline)),
         character_class().map(|cc| Some(Match::CharacterClass(cc))),
         character_group().map(|cg| Some(Match::CharacterGroup(cg))),
         char().map(|c| Some(Match::Char(c))),
     ]
 }
 
-/// `CharacterGroup ::= '['  CharacterGroupItem+ ']'`
-pub type CharacterGroup = Vec<CharacterGroupItem>;
-// /// `CharacterGroup ::= '[' ^? CharacterGroupItem+ ']'`
-// pub type CharacterGroup = (bool, Vec<CharacterGroupItem>);
-// pub struct CharacterGroup {
-//     inverted: bool,
-//     items: Vec<CharacterGroupItem>,
-// }
+/// `CharacterGroup ::= '[' '^'? ( ']' CharacterGroupItem* | CharacterGroupItem+ ) ']' | "[]"`
+///
+/// A `]` right after the opening `[` or `[^` is a literal member rather than the end of the group, unless no other `]`
+/// ends the group, in which case `[]` is an empty group, which only compiles if allowed by [`Flags`](super::Flags).
+#[derive(Clone, Debug)]
+pub struct CharacterGroup {
+    pub inverted: bool,
+    pub items: Vec<CharacterGroupItem>,
+}
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`CharacterGroup`].
 fn character_group() -> MonadicParser<CharacterGroup> {
-    character('[') >> character_group_item().one_or_more() << character(']')
-    // (character('[') >> character('^').exists() & character_group_item().oneOrMore() << character(']'))
-    //     .map(|(inverted, items)| Some(CharacterGroup { inverted, items }))
+    let leading = character(']').map(|c| Some(CharacterGroupItem::Char(c)));
+
+    let group = (character('[') >> character('^').exists()
+        & (leading.optional() & character_group_item().repeat()) << character(']'))
+    .map(|(inverted, (leading, rest))| {
+        let items: Vec<_> = leading.into_iter().chain(rest).collect();
+        if items.is_empty() {
+            None
+        } else {
+            Some(CharacterGroup { inverted, items })
+        }
+    });
+    let empty = string("[]").map(|_| Some(CharacterGroup { inverted: false, items: vec![] }));
+
+    union![group, empty]
 }
 
-/// `CharacterGroupItem ::= CharacterClass | CharacterRange | Char`
-#[derive(Debug)]
+/// `CharacterGroupItem ::= PosixClass | CharacterClass | CharacterRange | Char`
+#[derive(Clone, Debug)]
 pub enum CharacterGroupItem {
+    PosixClass(PosixClass),
     CharacterClass(CharacterClass),
     CharacterRange(CharacterRange),
     Char(char),
@@ -162,19 +273,97 @@ pub enum CharacterGroupItem {
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`CharacterGroupItem`].
 fn character_group_item() -> MonadicParser<CharacterGroupItem> {
+    let class = || union![posix_class().recognize(), character_class().recognize()];
+    // A `-` between a character class and anything but the closing `]` can only be read as a range, which is rejected
+    let class_start = (class() << character('-') << character(']').negate()).reject(ErrorKind::ClassRangeEndpoint);
+    let class_end = (character_group_char().recognize() << character('-') & class())
+        .reject(|(start, class), position| ErrorKind::ClassRangeEndpoint(class, position + start.len() + 1));
+
     union![
+        class_start,
+        class_end,
+        posix_class().map(|pc| Some(CharacterGroupItem::PosixClass(pc))),
         character_class().map(|cc| Some(CharacterGroupItem::CharacterClass(cc))),
         character_range().map(|range| Some(CharacterGroupItem::CharacterRange(range))),
         character_group_char().map(|c| Some(CharacterGroupItem::Char(c)))
     ]
 }
 
+/// `PosixClass ::= "[:" '^'? PosixClassName ":]"`
+///
+/// Only allowed within a [`CharacterGroup`], e.g. `[[:alpha:]]`, where `[:^alpha:]` denotes its complement.
+#[derive(Clone, Debug)]
+pub struct PosixClass {
+    pub inverted: bool,
+    pub name: PosixClassName,
+}
+
+/// `PosixClassName ::= "alnum" | "alpha" | "ascii" | "blank" | "cntrl" | "digit" | "graph" | "lower" | "print" | "punct"
+/// | "space" | "upper" | "word" | "xdigit"`
+#[derive(Clone, Copy, Debug)]
+pub enum PosixClassName {
+    Alnum,
+    Alpha,
+    Ascii,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Word,
+    XDigit,
+}
+
+/// Returns a [`MonadicParser`] associated to the grammar rule [`PosixClass`].
+fn posix_class() -> MonadicParser<PosixClass> {
+    (string("[:") >> character('^').exists() & name() << string(":]"))
+        .reject_if(
+            |(_, name)| posix_class_name(name).is_none(),
+            |(_, name), position| ErrorKind::UnknownPosixClass(name, position),
+        )
+        .map(|(inverted, name)| Some(PosixClass { inverted, name: posix_class_name(&name)? }))
+}
+
+/// Returns the [`PosixClassName`] named `name`, or `None` if there is no such name.
+fn posix_class_name(name: &str) -> Option<PosixClassName> {
+    let name = match name {
+        "alnum" => PosixClassName::Alnum,
+        "alpha" => PosixClassName::Alpha,
+        "ascii" => PosixClassName::Ascii,
+        "blank" => PosixClassName::Blank,
+        "cntrl" => PosixClassName::Cntrl,
+        "digit" => PosixClassName::Digit,
+        "graph" => PosixClassName::Graph,
+        "lower" => PosixClassName::Lower,
+        "print" => PosixClassName::Print,
+        "punct" => PosixClassName::Punct,
+        "space" => PosixClassName::Space,
+        "upper" => PosixClassName::Upper,
+        "word" => PosixClassName::Word,
+        "xdigit" => PosixClassName::XDigit,
+        _ => return None,
+    };
+
+    Some(name)
+}
+
 /// `CharacterRange ::= Char '-' Char`
+///
+/// A character class is not a single character, so it cannot be an endpoint, e.g. `[\d-z]`, `[a-\d]` and `[[:alpha:]-z]`
+/// are rejected rather than read with a literal `-`, which is written `\-` instead, e.g. `[\d\-z]`.
 pub type CharacterRange = (char, char);
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`CharacterGroupItem`].
 fn character_range() -> MonadicParser<CharacterRange> {
-    character_group_char() << character('-') & character_group_char()
+    // A range out of order would match nothing, so it is rejected rather than read as anything else
+    (character_group_char() << character('-') & character_group_char()).reject_if(
+        |(lower, upper)| lower > upper,
+        |(lower, upper), position| ErrorKind::InvertedCharacterRange(lower, upper, position),
+    )
 }
 
 /// Returns a [`MonadicParser`] associated to the grammar rule for `Char` in [`CharacterGroupItem`]
@@ -182,14 +371,17 @@ fn character_group_char() -> MonadicParser<char> {
     let char_group_special = |c: &char| matches!(c, '^' | '\\' | '-' | ']');
 
     union![
-        any().exclude(char_group_special),
+        // A leading `^` inverts the CharacterGroup, and is consumed before any item, so `^` is literal anywhere else
+        any().exclude(|c| matches!(c, '\\' | '-' | ']')),
         escaped().filter(char_group_special),
         control_char(),
+        // Within a CharacterGroup, `\b` denotes the backspace character rather than a word boundary
+        escaped().filter(|&c| c == 'b').map(|_| Some('\x08')),
     ]
 }
 
 /// `CharacterClass ::= '\w' | '\W' | '\d' | '\D | '\s' | '\S'`
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum CharacterClass {
     Alphanumeric,
     NotAlphanumeric,
@@ -213,6 +405,8 @@ fn character_class() -> MonadicParser<CharacterClass> {
 }
 
 /// Returns a [`MonadicParser`] associated to the grammar rule `Char`.
+///
+/// A `{` which does not start a valid [`RangeQuantifier`], and any `}`, are parsed as literal characters.
 #[rustfmt::skip]
 fn char() -> MonadicParser<char> {
     let special_char = |c: &char| {
@@ -223,6 +417,8 @@ fn char() -> MonadicParser<char> {
         any().exclude(special_char),
         escaped().filter(special_char),
         control_char(),
+        range_quantifier().negate() >> character('{'),
+        character('}'),
     ]
 }
 
@@ -239,31 +435,61 @@ fn control_char() -> MonadicParser<char> {
     })
 }
 
-/// `Backreference ::= '\' 1..9`
+/// `Backreference ::= '\' 1..9 0..9*`
 pub type Backreference = u32;
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`Backreference`].
 fn backreference() -> MonadicParser<Backreference> {
-    escaped().map(|c| c.to_digit(10)).exclude(|&n| n == 0)
+    (character('\\') >> (digit().exclude(|&n| n == 0) & digit().repeat()).recognize())
+        .reject_if(|index| !fits_u32(index), |index, position| ErrorKind::NumberTooLarge(index, position + 1))
+        .map(|index| index.parse().ok())
 }
 
-/// `Quantifier ::= '*' | '+' | '?' | RangeQuantifier`
-#[derive(Debug)]
+/// `NamedBackreference ::= "\k<" Name '>' | "\k{" Name '}'`
+///
+/// Refers to the capturing group of the given name, and is resolved into a [`Backreference`] to its index.
+pub type NamedBackreference = String;
+
+/// Returns a [`MonadicParser`] associated to the grammar rule [`NamedBackreference`].
+fn named_backreference() -> MonadicParser<NamedBackreference> {
+    union![
+        string("\\k<") >> name() << character('>'),
+        string("\\k{") >> name() << character('}'),
+    ]
+}
+
+/// `Quantifier ::= ( '*' | '+' | '?' | RangeQuantifier ) '?'?`
+#[derive(Clone, Debug)]
 pub enum Quantifier {
     ZeroOrMore,
     OneOrMore,
     ZeroOrOne,
     Range(RangeQuantifier),
+    /// The quantifier repeating as few times as possible rather than as many, if followed by `?`, e.g. `a+?`.
+    Lazy(Box<Quantifier>),
+}
+
+impl Quantifier {
+    /// Returns the quantifier regardless of whether it is lazy, i.e. how many repetitions it allows.
+    pub fn greedy(&self) -> &Quantifier {
+        match self {
+            Quantifier::Lazy(quantifier) => quantifier,
+            quantifier => quantifier,
+        }
+    }
 }
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`Quantifier`].
 fn quantifier() -> MonadicParser<Quantifier> {
-    union![
+    let greedy = union![
         character('*').map(|_| Some(Quantifier::ZeroOrMore)),
         character('+').map(|_| Some(Quantifier::OneOrMore)),
         character('?').map(|_| Some(Quantifier::ZeroOrOne)),
         range_quantifier().map(|range| Some(Quantifier::Range(range))),
-    ]
+    ];
+
+    (greedy & character('?').exists())
+        .map(|(quantifier, lazy)| Some(if lazy { Quantifier::Lazy(Box::new(quantifier)) } else { quantifier }))
 }
 
 /// `RangeQuantifier ::= '{' RangeQuantifierLowerBound ( ',' RangeQuantifierUpperBound? )? '}'`
@@ -271,6 +497,28 @@ pub type RangeQuantifier = (u32, Option<u32>);
 
 /// Returns a [`MonadicParser`] associated to the grammar rule [`RangeQuantifier`].
 fn range_quantifier() -> MonadicParser<RangeQuantifier> {
-    (character('{') >> number() & (character(',') >> number().optional()).optional() << character('}'))
-        .map(|(start, maybe_end)| Some((start, maybe_end.or(Some(Some(start)))?)))
+    // Bounds are only rejected once the quantifier is closed, as a `{` which does not start one is a literal character
+    (character('{') >> digits() & (character(',') >> digits().optional()).optional() << character('}'))
+        .reject_if(
+            |(lower, upper)| !fits_u32(lower) || upper.iter().flatten().any(|upper| !fits_u32(upper)),
+            |(lower, upper), position| match upper.flatten() {
+                Some(upper) if fits_u32(&lower) => ErrorKind::NumberTooLarge(upper, position + lower.len() + 2),
+                _ => ErrorKind::NumberTooLarge(lower, position + 1),
+            },
+        )
+        .map(|(lower, upper)| {
+            let lower = lower.parse().ok()?;
+            let upper = match upper {
+                None => Some(lower),
+                Some(None) => None,
+                Some(Some(upper)) => Some(upper.parse().ok()?),
+            };
+
+            Some((lower, upper))
+        })
+}
+
+/// Returns `true` if the number written with `digits` fits in a [`u32`].
+fn fits_u32(digits: &str) -> bool {
+    digits.parse::<u32>().is_ok()
 }
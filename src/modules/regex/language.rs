@@ -1,4 +1,4 @@
-use super::{Automata, Error};
+use super::{Automata, Error, ErrorKind, Flags};
 
 use super::ast::AbstractSyntaxTree;
 use super::grammar::Grammar;
@@ -14,15 +14,44 @@ impl<T: AbstractSyntaxTree + 'static> Language<T> {
         Language { grammar }
     }
 
-    /// Parses `expr` using [`Language`]'s grammar.
-    pub fn parse(&self, expr: &str) -> Result<Automata, Error> {
-        self.syntax(expr)
-            .ok_or_else(|| Error::from("Internal error: `expr` parsed into an `None` Expression"))?
-            .compile()
+    /// Parses `expr` using [`Language`]'s grammar, and compiles it according to `flags`.
+    pub fn parse(&self, expr: &str, flags: &Flags) -> Result<Automata, Error> {
+        self.syntax(expr)?.compile(flags)
     }
 
     /// Returns the syntax representation of `expr` using [`Language`]'s grammar.
-    pub fn syntax(&self, expr: &str) -> Option<T> {
-        self.grammar.parse(expr).map(|(t, _)| t)
+    ///
+    /// Returns an [`Error`] pointing at the backslash if `expr` ends with one escaping nothing, or describing the failure
+    /// the grammar committed to, e.g. a group which is never closed, or naming the first character which could not be
+    /// parsed if `expr` is not parsed entirely. Positions are byte offsets into `expr`.
+    pub fn syntax(&self, expr: &str) -> Result<T, Error> {
+        let trailing_backslashes = expr.chars().rev().take_while(|&c| c == '\\').count();
+        if trailing_backslashes % 2 == 1 {
+            let position = expr.len() - 1;
+            return Err(Error::new(ErrorKind::TrailingBackslash(position)));
+        }
+
+        // A failure committed to is reported even if `expr` is parsed otherwise, e.g. `a{99999999999}` as literal characters
+        let (parsed, rejection) = self.grammar.parse_committed(expr);
+        if let Some(kind) = rejection {
+            return Err(Error::new(kind));
+        }
+
+        let (syntax, rst) = match parsed {
+            Some((t, rst)) => (Some(t), rst),
+            None => (None, expr),
+        };
+
+        match (syntax, rst.chars().next()) {
+            (Some(t), None) => Ok(t),
+            (_, Some(c)) => Err(Error::new(ErrorKind::UnexpectedChar(c, expr.len() - rst.len()))),
+            (_, None) => Err(Error::new(ErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    /// Returns the syntax representation of the longest prefix of `expr` parsed by [`Language`]'s grammar, along with the
+    /// remainder of `expr`, or `None` if no prefix of `expr` is parsed.
+    pub fn syntax_prefix<'a>(&self, expr: &'a str) -> Option<(T, &'a str)> {
+        self.grammar.parse(expr)
     }
 }
@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+use super::RegExp;
+
+/// A match of a regular expression, as a span of byte offsets into the searched expression.
+///
+/// Matches are ordered by position, i.e. by where they start, then by where they end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Match {
+    start: usize,
+    end: usize,
+}
+
+impl Match {
+    /// Constructs a [`Match`] spanning the byte offsets `start..end`.
+    pub fn new(start: usize, end: usize) -> Self {
+        Match { start, end }
+    }
+
+    /// Returns the byte offset where the match starts.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the byte offset where the match ends.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the range of byte offsets spanned by the match.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the length of the match in bytes.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the match is empty.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the substring of `expr` spanned by the match, where `expr` is the searched expression.
+    pub fn as_str<'h>(&self, expr: &'h str) -> &'h str {
+        &expr[self.range()]
+    }
+}
+
+/// An iterator over all non-overlapping [`Match`] of a [`RegExp`] in an expression, from left to right.
+///
+/// Each [`Match`] is only searched for when the iterator is advanced.
+pub struct Matches<'r, 'h> {
+    regexp: &'r RegExp,
+    expr: &'h str,
+    /// Byte offset from which to search for the next [`Match`], or `None` once the search is exhausted.
+    position: Option<usize>,
+    /// Byte offset where the previous [`Match`] ended.
+    last_end: Option<usize>,
+}
+
+impl<'r, 'h> Matches<'r, 'h> {
+    /// Constructs a [`Matches`] iterating over the matches of `regexp` in `expr`.
+    pub fn new(regexp: &'r RegExp, expr: &'h str) -> Self {
+        Matches { regexp, expr, position: regexp.accepts(expr).then_some(0), last_end: None }
+    }
+}
+
+impl Iterator for Matches<'_, '_> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            let position = self.position.take()?;
+            let (start, end) = self.regexp.find_at(self.expr, position)?;
+
+            // An empty match right where the previous match ended is skipped, so that matches never overlap
+            if start == end && self.last_end == Some(end) {
+                self.position = self.expr[end..].chars().next().map(|c| end + c.len_utf8());
+                continue;
+            }
+
+            self.position = Some(end);
+            self.last_end = Some(end);
+            return Some(Match::new(start, end));
+        }
+    }
+}
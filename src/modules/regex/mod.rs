@@ -1,52 +1,913 @@
 mod alphabet;
+mod analysis;
 mod ast;
+mod builder;
+mod captures;
+mod context;
+mod flags;
 mod grammar;
 mod language;
+mod matches;
 #[allow(clippy::module_name_repetitions)]
 mod parser;
+mod replacement;
+#[cfg(feature = "stats")]
+pub mod stats;
 
+use std::fmt::{self, Write};
+use std::num::NonZeroUsize;
+use std::ops::{Add, BitOr};
+use std::{slice, thread};
+
+pub use self::analysis::{MatchKind, Warning};
+pub use self::builder::RegExpBuilder;
+pub use self::captures::Captures;
+pub use self::flags::{Engine, Flags, MatchSemantics, WordChars, DEFAULT_MAX_STATES};
 pub use self::grammar::Anchor;
+pub use self::matches::{Match, Matches};
+pub use super::automata::{MatchTrace, SearchCache};
+pub use super::dfa::Dfa;
+pub use super::error::{reset_error_formatter, set_error_formatter, DefaultErrorFormatter, Error, ErrorFormatter, ErrorKind};
+pub use super::state::PredicateKind;
 
 use super::automata::Automata;
-use super::error::Error;
 
-use self::grammar::Regex;
+use self::ast::AbstractSyntaxTree;
+use self::context::RegexContext;
+use self::grammar::{BasicExpression, Group, Quantifiable, Quantifier, Regex};
 use self::language::Language;
+use self::replacement::{Reference, ReplacementItem};
+
+/// A position in an expression as a `(line, column)` pair, as reported by [`RegExp::find_iter_linecol`].
+pub type LineCol = (usize, usize);
 
 /// A wrapper around an [`Automata`] defining a parsed regular expression.
-pub struct RegExp(Automata);
+pub struct RegExp {
+    regex: Regex,
+    flags: Flags,
+    context: RegexContext,
+    automata: Automata,
+    /// The string matched by the regular expression, if it matches a single string literally.
+    literal: Option<String>,
+    max_input_len: Option<usize>,
+}
 
 impl RegExp {
+    /// Compiles the syntax representation `regex` of a regular expression into a [`RegExp`].
+    fn new(mut regex: Regex, flags: Flags) -> Result<Self, Error> {
+        #[cfg(feature = "stats")]
+        stats::record_compilation();
+
+        let context = RegexContext::process(&mut regex)?;
+
+        // The limit applies to the whole regular expression, and is checked before any state is created
+        let estimated = analysis::estimated_states(&regex);
+        if estimated > flags.max_states {
+            return Err(Error::new(ErrorKind::ExpansionTooLarge { estimated, limit: flags.max_states }));
+        }
+
+        // Lazy quantifiers only matter in which match is found first, so they are matched with leftmost-first semantics
+        let semantics = if analysis::has_lazy_quantifier(&regex) {
+            MatchSemantics::PerlLeftmostFirst
+        } else {
+            flags.semantics
+        };
+        let requires_backtracking = analysis::requires_backtracking(&regex) || semantics == MatchSemantics::PerlLeftmostFirst;
+        let backtracking = match flags.engine {
+            Engine::Nfa if requires_backtracking => return Err(Error::new(ErrorKind::BacktrackingRequired)),
+            Engine::Nfa => false,
+            Engine::Backtracking => true,
+            Engine::Auto => requires_backtracking,
+        };
+
+        let automata = regex
+            .compile(&flags)?
+            .simplify()
+            .anchored(!flags.multi_line && analysis::is_anchored_start(&regex))
+            .semantics(semantics)
+            .backtracking(backtracking)
+            .word_chars(flags.word_char_predicate())
+            .prefix(analysis::required_prefix(&regex).filter(|_| !flags.case_insensitive))
+            .batch_predicates()
+            .anchored_end(!flags.multi_line && analysis::is_anchored_end(&regex));
+
+        // Case insensitive characters match more than a single string, and empty matches are left to the automata
+        let literal = analysis::literal(&regex).filter(|literal| !literal.is_empty() && !flags.case_insensitive);
+
+        Ok(RegExp { regex, flags, context, automata, literal, max_input_len: None })
+    }
+
+    /// Compiles each of `patterns` as a regular expression into a [`RegExp`] across threads, and applies `f` to it on the
+    /// thread which compiled it, returning the results in the order of `patterns`.
+    ///
+    /// A [`RegExp`] cannot be sent across threads, so each thread compiles with its own grammar, built once per thread,
+    /// and only the results of `f` are sent back.
+    pub fn compile_many_parallel<R: Send, F: Fn(RegExp) -> R + Sync>(patterns: &[&str], f: F) -> Vec<Result<R, Error>> {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk_size = patterns.len().div_ceil(threads).max(1);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = patterns
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let f = &f;
+                    scope.spawn(move || {
+                        REGEX.with(|regex| {
+                            chunk
+                                .iter()
+                                .map(|pattern| regex.compile(pattern).map(f))
+                                .collect()
+                        })
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| -> Vec<_> { handle.join().expect("compiling threads should not panic") })
+                .collect()
+        })
+    }
+
+    /// Returns the number of capturing groups of the regular expression.
+    pub fn group_count(&self) -> usize {
+        self.context.group_count()
+    }
+
+    /// Returns the name of each capturing group, or `None` if it has none, indexed as in [`RegExp::captures`].
+    ///
+    /// The capturing group of index 0, the entire match, never has a name.
+    pub fn group_names(&self) -> Vec<Option<String>> {
+        let mut group_names = vec![None; self.group_count() + 1];
+        for (name, &index) in self.context.names().iter() {
+            group_names[index] = Some(name.clone());
+        }
+
+        group_names
+    }
+
+    /// Returns `true` if the regular expression can only match at the start of an expression, i.e. starts with `^` outside
+    /// of multi-line mode.
+    pub fn is_anchored_start(&self) -> bool {
+        !self.flags.multi_line && analysis::is_anchored_start(&self.regex)
+    }
+
+    /// Returns `true` if the regular expression can only match at the end of an expression, i.e. ends with `$` outside of
+    /// multi-line mode.
+    pub fn is_anchored_end(&self) -> bool {
+        !self.flags.multi_line && analysis::is_anchored_end(&self.regex)
+    }
+
+    /// Returns `true` if the regular expression matches every expression, if only with an empty match at its start, e.g.
+    /// for `.*` or `a*`, in which case [`RegExp::is_match`] need not scan it.
+    ///
+    /// This is determined from the structure of the regular expression alone, and conservatively, e.g. `a*$` matches every
+    /// expression, but at its end, and is not detected.
+    ///
+    /// Alternations cannot have empty branches, so e.g. `(a|)` does not compile, and is written `(a?)` or `(a|b?)` instead.
+    pub fn always_matches(&self) -> bool {
+        analysis::always_matches(&self.regex)
+    }
+
+    /// Returns `true` if the regular expression repeats a group without an upper bound which itself contains a quantifier
+    /// without an upper bound, e.g. `(a*)*` or `(a+)+`.
+    ///
+    /// The NFA engine matches such regular expressions in linear time, but the backtracking engine, which capturing
+    /// groups may require, can take exponential time on expressions they fail to match, so they may be screened out
+    /// beforehand. Compiling them with `compile_checked`, on the language returned by [`init`], warns about them as well
+    /// with [`Warning::NestedUnboundedQuantifier`].
+    pub fn has_nested_unbounded_quantifier(&self) -> bool {
+        analysis::has_nested_unbounded_quantifier(&self.regex)
+    }
+
+    /// Returns where the matches of the regular expression lie within an expression, combining
+    /// [`RegExp::is_anchored_start`] and [`RegExp::is_anchored_end`].
+    pub fn match_kind(&self) -> MatchKind {
+        match (self.is_anchored_start(), self.is_anchored_end()) {
+            (true, true) => MatchKind::Anchored,
+            (true, false) => MatchKind::Prefix,
+            (false, true) => MatchKind::Suffix,
+            (false, false) => MatchKind::Unanchored,
+        }
+    }
+
+    /// Sets the maximum length, in bytes, of expressions the regular expression is matched against.
+    ///
+    /// Longer expressions are rejected without being scanned, and are treated as not matching.
+    pub fn set_max_input_len(&mut self, n: usize) {
+        self.max_input_len = Some(n);
+    }
+
+    /// Returns `true` if `expr` is not longer than the maximum length set by [`RegExp::set_max_input_len`].
+    fn accepts(&self, expr: &str) -> bool {
+        self.max_input_len.is_none_or(|n| expr.len() <= n)
+    }
+
+    /// Returns the number of state transitions performed while matching the regular expression so far.
+    pub fn transition_count(&self) -> usize {
+        self.automata.transitions()
+    }
+
+    /// Returns the number of attempts at matching from a given position of an expression made so far.
+    pub fn attempt_count(&self) -> usize {
+        self.automata.attempts()
+    }
+
+    /// Resets the number of state transitions and attempts counted so far.
+    pub fn reset_counters(&self) {
+        self.automata.reset_counters();
+    }
+
+    /// Returns `true` if some substring of `expr` matches the regular expression.
+    ///
+    /// An empty match is a match, e.g. `a*` matches in `"b"` while `a+` does not, so this is the same as
+    /// [`RegExp::greedy_search`] returning `Some`, even of an empty substring.
+    ///
+    /// Matching stops as soon as any match is found, e.g. once a branch of an alternation accepts, without exploring
+    /// whether the other branches would have found a longer match.
+    pub fn is_match(&self, expr: impl AsRef<str>) -> bool {
+        let expr = expr.as_ref();
+        if !self.accepts(expr) {
+            return false;
+        }
+
+        match &self.literal {
+            Some(literal) => expr.contains(literal.as_str()),
+            None => self.automata.is_match(expr),
+        }
+    }
+
+    /// Returns `true` if some substring of `bytes`, decoded as UTF-8, matches the regular expression.
+    ///
+    /// Decoding is lossy: each invalid UTF-8 sequence of `bytes` is replaced by `U+FFFD REPLACEMENT CHARACTER`, which
+    /// is then matched like any other character, e.g. by `.` or `\W`.
+    pub fn is_match_bytes(&self, bytes: &[u8]) -> bool {
+        self.is_match(String::from_utf8_lossy(bytes))
+    }
+
+    /// Returns the [`Match`] of the leftmost substring of `expr` which matches the regular expression, or `None` if no such
+    /// substring of `expr` exists.
+    ///
+    /// Anchors are only satisfied at their actual position, e.g. a match of `xyz+$` must end at the end of `expr`, or of a
+    /// line in multi-line mode.
+    pub fn find(&self, expr: impl AsRef<str>) -> Option<Match> {
+        let expr = expr.as_ref();
+        self.find_iter(expr).next()
+    }
+
+    /// Returns the [`Match`] of the last of all non-overlapping substrings of `expr` which matches the regular expression,
+    /// as located by [`RegExp::find_iter`], or `None` if no such substring of `expr` exists, e.g. to find the last `.` of
+    /// a file name.
+    ///
+    /// This is the last match found scanning from left to right, which may differ from the rightmost one, e.g. `aa` in
+    /// `"aaa"` matches `"aa"` at 0 rather than at 1.
+    pub fn rfind(&self, expr: impl AsRef<str>) -> Option<Match> {
+        let expr = expr.as_ref();
+
+        // Every match ends at the end of the expression, so no other match can follow the leftmost one
+        if self.is_anchored_end() {
+            return self.find(expr);
+        }
+
+        self.find_iter(expr).last()
+    }
+
+    /// Returns the leftmost match of the regular expression in `expr`, as in [`RegExp::find`], reusing the buffers of
+    /// `cache` rather than allocating new ones.
+    ///
+    /// A single [`SearchCache`] can be used for any number of searches, with any [`RegExp`], which saves allocations when
+    /// matching many expressions in a loop.
+    pub fn find_cached(&self, expr: impl AsRef<str>, cache: &mut SearchCache) -> Option<Match> {
+        let expr = expr.as_ref();
+        if !self.accepts(expr) {
+            return None;
+        }
+
+        self.find_at_cached(expr, 0, cache)
+            .map(|(start, end)| Match::new(start, end))
+    }
+
     /// Returns `true` if expr matches the regular expression entirely.
-    pub fn full_match(&self, expr: &str) -> bool {
-        self.0.full_match(expr)
+    pub fn full_match(&self, expr: impl AsRef<str>) -> bool {
+        let expr = expr.as_ref();
+        if let Some(literal) = &self.literal {
+            return self.accepts(expr) && expr == literal;
+        }
+
+        self.accepts(expr) && self.automata.full_match(expr)
     }
 
     /// Returns the longest substring of `expr` which matches the regular expression, or `None` if no such substring of `expr` exists.
-    pub fn greedy_search(&self, expr: &str) -> Option<String> {
-        self.0.greedy_search(expr)
+    ///
+    /// An empty match is a match, so e.g. `a*` yields `Some("")` on `"b"`, while `a+` yields `None`.
+    pub fn greedy_search(&self, expr: impl AsRef<str>) -> Option<String> {
+        let expr = expr.as_ref();
+        if !self.accepts(expr) {
+            return None;
+        }
+
+        self.automata.greedy_search(expr)
+    }
+
+    /// Returns a list of all non-overlapping substrings of `expr` which matches the regular expression, in the order they
+    /// appear in `expr`, from left to right.
+    pub fn global_search(&self, expr: impl AsRef<str>) -> Vec<String> {
+        let expr = expr.as_ref();
+        if !self.accepts(expr) {
+            return vec![];
+        }
+
+        if let Some(literal) = &self.literal {
+            return expr.matches(literal.as_str()).map(String::from).collect();
+        }
+
+        self.automata.global_search(expr)
+    }
+
+    /// Returns a [`Matches`] iterating over the [`Match`] of all non-overlapping substrings of `expr` which matches the
+    /// regular expression, from left to right.
+    ///
+    /// An empty match is reported at every position where no other match overlaps it, except right where the previous
+    /// match ended, and the search then resumes from the next character.
+    pub fn find_iter<'r, 'h>(&'r self, expr: &'h str) -> Matches<'r, 'h> {
+        Matches::new(self, expr)
+    }
+
+    /// Returns the [`Match`] of all non-overlapping substrings of `expr` which matches the regular expression, as located by
+    /// [`RegExp::find_iter`], along with the positions where each starts and ends as `(line, column)` pairs, e.g. for
+    /// diagnostics in an editor.
+    ///
+    /// Lines are counted from 1 and delimited by `\n`, while columns are counted in characters from 0. A match ending
+    /// right after a `\n` ends at column 0 of the next line.
+    pub fn find_iter_linecol(&self, expr: impl AsRef<str>) -> Vec<(Match, LineCol, LineCol)> {
+        let expr = expr.as_ref();
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(expr.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let line_col = |offset: usize| {
+            // The line is the last one starting at or before `offset`
+            let line = line_starts.partition_point(|&start| start <= offset);
+            (line, expr[line_starts[line - 1]..offset].chars().count())
+        };
+
+        self.find_iter(expr)
+            .map(|m| (m, line_col(m.start()), line_col(m.end())))
+            .collect()
+    }
+
+    /// Returns the [`Match`] of the longest substring of `expr` starting at each position which matches the regular
+    /// expression, from left to right, so that matches may overlap each other.
+    pub fn find_overlapping(&self, expr: impl AsRef<str>) -> Vec<Match> {
+        let expr = expr.as_ref();
+        if !self.accepts(expr) {
+            return vec![];
+        }
+
+        expr.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(expr.len()))
+            .filter_map(|i| self.automata.match_at(expr, i))
+            .map(|(start, end)| Match::new(start, end))
+            .collect()
+    }
+
+    /// Returns the span of the leftmost match in `expr` starting at or after the byte offset `from`, as located by
+    /// [`Automata::find_at`], or by substring search if the regular expression is a literal.
+    fn find_at(&self, expr: &str, from: usize) -> Option<(usize, usize)> {
+        self.find_at_cached(expr, from, &mut SearchCache::default())
+    }
+
+    /// Returns the span of the leftmost match in `expr` starting at or after the byte offset `from`, as in
+    /// [`RegExp::find_at`], reusing the buffers of `cache`.
+    fn find_at_cached(&self, expr: &str, from: usize, cache: &mut SearchCache) -> Option<(usize, usize)> {
+        match &self.literal {
+            Some(literal) => expr[from..]
+                .find(literal.as_str())
+                .map(|i| (from + i, from + i + literal.len())),
+            None => self.automata.find_at_cached(expr, from, cache),
+        }
+    }
+
+    /// Returns the string matched by the regular expression if it contains no metacharacters, in which case matching
+    /// reduces to substring search, e.g. `Some("a.b")` for `a\.b`.
+    pub fn as_literal(&self) -> Option<String> {
+        self.literal.clone()
+    }
+
+    /// Returns the substrings of `expr` captured by the leftmost match of the regular expression, or `None` if there is no
+    /// match.
+    ///
+    /// The first element is the entire match, followed by the substring captured by each capturing group, in order. A
+    /// capturing group which does not participate in the match captures `None`, and a capturing group which participates
+    /// repeatedly captures the substring of its last repetition. A repetition never follows one which matched the empty
+    /// string, so a repeated group which may match the empty string captures its last non-empty repetition, e.g. `aa` for
+    /// `(a*)*b` in `aab`, and only captures the empty string if it never matched anything else, e.g. for `(a*)+` in `b`.
+    pub fn captures(&self, expr: impl AsRef<str>) -> Option<Vec<Option<String>>> {
+        let expr = expr.as_ref();
+        let spans = self.capture_spans(expr)?;
+        Some(
+            spans
+                .into_iter()
+                .map(|span| span.map(|m| String::from(m.as_str(expr))))
+                .collect(),
+        )
+    }
+
+    /// Returns the [`Match`] of each capturing group in the leftmost match of the regular expression, as in
+    /// [`RegExp::captures`], or `None` if there is no match.
+    ///
+    /// This avoids allocating a [`String`] for each captured substring.
+    pub fn capture_spans(&self, expr: impl AsRef<str>) -> Option<Vec<Option<Match>>> {
+        let expr = expr.as_ref();
+        if !self.accepts(expr) {
+            return None;
+        }
+
+        let spans = self.automata.captures(expr, self.group_count())?;
+        Some(
+            spans
+                .into_iter()
+                .map(|span| span.map(|(l, r)| Match::new(l, r)))
+                .collect(),
+        )
+    }
+
+    /// Returns the substrings of `expr` delimited by the non-overlapping matches of the regular expression, as in
+    /// [`str::split`].
+    ///
+    /// If the regular expression has capturing groups, the substrings they capture in each delimiting match are inserted
+    /// between the pieces it delimits, in order, e.g. splitting `"a1b2"` on `(\d)` gives `["a", "1", "b", "2", ""]`. A
+    /// capturing group which does not participate in a delimiting match inserts an empty substring.
+    pub fn split(&self, expr: impl AsRef<str>) -> Vec<String> {
+        let expr = expr.as_ref();
+        self.split_spans(expr)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Returns the substrings of `expr` delimited by the non-overlapping matches of the regular expression, as in
+    /// [`RegExp::split`], but borrowed from `expr` rather than allocated.
+    pub fn split_spans<'h>(&self, expr: &'h str) -> Vec<&'h str> {
+        let mut pieces = vec![];
+        let mut last = 0;
+        for m in self.find_iter(expr) {
+            pieces.push(&expr[last..m.start()]);
+            last = m.end();
+
+            if self.group_count() > 0 {
+                // A match located by `find_iter` is always replayed, see `Automata::captures_at`
+                let spans = self
+                    .automata
+                    .captures_at(expr, (m.start(), m.end()), self.group_count())
+                    .expect("a match should be replayed to the same match");
+                pieces.extend(
+                    spans
+                        .into_iter()
+                        .skip(1)
+                        .map(|span| span.map_or("", |(l, r)| &expr[l..r])),
+                );
+            }
+        }
+        pieces.push(&expr[last..]);
+
+        pieces
+    }
+
+    /// Returns the [`Captures`] of the longest match of the regular expression starting exactly at the byte offset
+    /// `offset` of `expr`, or `None` if there is no such match or `offset` is not on a character boundary of `expr`.
+    ///
+    /// Capturing groups are reported as in [`RegExp::captures`].
+    pub fn captures_at<'h>(&self, expr: &'h str, offset: usize) -> Option<Captures<'h>> {
+        if !self.accepts(expr) || !expr.is_char_boundary(offset) {
+            return None;
+        }
+
+        let span = self.automata.match_at(expr, offset)?;
+        self.captures_of(expr, span)
+    }
+
+    /// Returns the byte offset where the shortest match of the regular expression starting exactly at the byte offset
+    /// `offset` of `expr` ends, or `None` if there is no such match or `offset` is not on a character boundary of `expr`.
+    ///
+    /// Quantifiers match as few repetitions as needed, e.g. `a+` matches only `"a"` of `"aaa"`.
+    pub fn shortest_match_at(&self, expr: impl AsRef<str>, offset: usize) -> Option<usize> {
+        let expr = expr.as_ref();
+        if !self.accepts(expr) || !expr.is_char_boundary(offset) {
+            return None;
+        }
+
+        self.automata.shortest_match_at(expr, offset)
+    }
+
+    /// Returns an iterator over the [`Captures`] of all non-overlapping matches of the regular expression in `expr`, from
+    /// left to right, as located by [`RegExp::find_iter`].
+    ///
+    /// Each match, and the substrings captured by it, are only searched for when the iterator is advanced.
+    pub fn captures_iter_lazy<'a>(&'a self, expr: &'a str) -> impl Iterator<Item = Captures<'a>> {
+        self.find_iter(expr).map(move |m| {
+            self.captures_of(expr, (m.start(), m.end()))
+                .expect("a match should be replayed to the same match")
+        })
+    }
+
+    /// Returns the [`Captures`] of the match spanning `span` in `expr`.
+    fn captures_of<'h>(&self, expr: &'h str, span: (usize, usize)) -> Option<Captures<'h>> {
+        let spans = self.automata.captures_at(expr, span, self.group_count())?;
+        Some(Captures::new(
+            expr,
+            spans
+                .into_iter()
+                .map(|span| span.map(|(l, r)| Match::new(l, r)))
+                .collect(),
+            self.context.names(),
+        ))
+    }
+
+    /// Returns a copy of `haystack` where all non-overlapping matches of the regular expression are replaced by `rep`.
+    ///
+    /// Matches are always the ones reported by [`RegExp::find_iter`], never those of [`RegExp::find_overlapping`], as
+    /// overlapping regions cannot all be replaced.
+    ///
+    /// Within `rep`, `$n` and `$name` are replaced by the substring captured by the capturing group of index `n` and
+    /// named `name` respectively, or by nothing if there is no such group or it did not participate in the match. A
+    /// reference extends as far as possible, so braces are used to delimit it, e.g. `${1}0`, and `$$` denotes a literal
+    /// `$`.
+    pub fn replace_all(&self, haystack: impl AsRef<str>, rep: impl AsRef<str>) -> String {
+        let haystack = haystack.as_ref();
+        let rep = rep.as_ref();
+        let mut out = String::with_capacity(haystack.len());
+        self.replace_all_to(haystack, rep, &mut out)
+            .expect("writing into a String should never fail");
+
+        out
+    }
+
+    /// Returns a copy of `haystack` where all non-overlapping matches of the regular expression are replaced by `rep`, as
+    /// in [`RegExp::replace_all`], or an [`Error`] if `rep` refers to a capturing group which does not exist, rather than
+    /// replacing the reference by nothing.
+    pub fn replace_all_strict(&self, haystack: impl AsRef<str>, rep: impl AsRef<str>) -> Result<String, Error> {
+        let haystack = haystack.as_ref();
+        let rep = rep.as_ref();
+        let undefined = replacement::parse_written(rep)
+            .into_iter()
+            .find_map(|(item, written)| match item {
+                ReplacementItem::Reference(Reference::Index(index)) if index > self.group_count() => Some(written),
+                ReplacementItem::Reference(Reference::Name(name)) if self.context.group_index(&name).is_none() => Some(written),
+                _ => None,
+            });
+
+        match undefined {
+            Some(reference) => Err(Error::new(ErrorKind::UndefinedReplacementReference(String::from(reference)))),
+            None => Ok(self.replace_all(haystack, rep)),
+        }
+    }
+
+    /// Returns a copy of `haystack` where all non-overlapping matches of the regular expression are replaced by what `rep`
+    /// returns given their [`Captures`], so that replacements may be computed rather than expanded from a template as in
+    /// [`RegExp::replace_all`].
+    pub fn replace_all_with<F: FnMut(&Captures) -> String>(&self, haystack: impl AsRef<str>, mut rep: F) -> String {
+        let haystack = haystack.as_ref();
+        let mut out = String::with_capacity(haystack.len());
+
+        let mut last = 0;
+        for captures in self.captures_iter_lazy(haystack) {
+            let m = captures
+                .get_match(0)
+                .expect("the entire match should always be captured");
+            out.push_str(&haystack[last..m.start()]);
+            out.push_str(&rep(&captures));
+            last = m.end();
+        }
+        out.push_str(&haystack[last..]);
+
+        out
+    }
+
+    /// Writes `haystack`, with all non-overlapping matches of the regular expression replaced by `rep`, into `out`.
+    ///
+    /// This avoids holding the entire result in memory, as opposed to [`RegExp::replace_all`].
+    pub fn replace_all_to<W: Write>(&self, haystack: impl AsRef<str>, rep: impl AsRef<str>, out: &mut W) -> fmt::Result {
+        let haystack = haystack.as_ref();
+        let rep = rep.as_ref();
+        self.replacen_to(haystack, usize::MAX, rep, out)
+    }
+
+    /// Returns a copy of `haystack` where the first `limit` non-overlapping matches of the regular expression are replaced
+    /// by `rep`, as in [`RegExp::replace_all`], and the rest is left intact.
+    pub fn replacen(&self, haystack: impl AsRef<str>, limit: usize, rep: impl AsRef<str>) -> String {
+        let haystack = haystack.as_ref();
+        let rep = rep.as_ref();
+        let mut out = String::with_capacity(haystack.len());
+        self.replacen_to(haystack, limit, rep, &mut out)
+            .expect("writing into a String should never fail");
+
+        out
+    }
+
+    /// Writes `haystack`, with the first `limit` non-overlapping matches of the regular expression replaced by `rep`, into
+    /// `out`.
+    fn replacen_to<W: Write>(&self, haystack: &str, limit: usize, rep: &str, out: &mut W) -> fmt::Result {
+        let items = replacement::parse(rep);
+
+        // Replaced regions must not overlap, so matches are strictly those of `find_iter`
+        let mut last = 0;
+        for m in self.find_iter(haystack).take(limit) {
+            out.write_str(&haystack[last..m.start()])?;
+            self.expand_to(haystack, m, &items, out)?;
+            last = m.end();
+        }
+
+        out.write_str(&haystack[last..])
+    }
+
+    /// Returns each non-overlapping match of the regular expression in `haystack`, along with the text it would be
+    /// replaced by in [`RegExp::replace_all`], i.e. `rep` with its references to capturing groups expanded.
+    ///
+    /// This previews the replacements without building the resulting string.
+    pub fn replace_preview(&self, haystack: impl AsRef<str>, rep: impl AsRef<str>) -> Vec<(Match, String)> {
+        let haystack = haystack.as_ref();
+        let rep = rep.as_ref();
+        let items = replacement::parse(rep);
+
+        self.find_iter(haystack)
+            .map(|m| {
+                let mut replacement = String::new();
+                self.expand_to(haystack, m, &items, &mut replacement)
+                    .expect("writing into a String should never fail");
+
+                (m, replacement)
+            })
+            .collect()
+    }
+
+    /// Writes the replacement string `items` into `out`, with references resolved against the capturing groups of the
+    /// match `m` of `haystack`.
+    fn expand_to<W: Write>(&self, haystack: &str, m: Match, items: &[ReplacementItem], out: &mut W) -> fmt::Result {
+        // Capturing groups are only resolved if referenced, as it requires replaying the match
+        let has_references = items
+            .iter()
+            .any(|item| matches!(item, ReplacementItem::Reference(_)));
+        let groups = if has_references {
+            self.automata
+                .captures_at(haystack, (m.start(), m.end()), self.group_count())
+        } else {
+            None
+        };
+
+        for item in items {
+            match item {
+                ReplacementItem::Literal(c) => out.write_char(*c)?,
+                ReplacementItem::Reference(reference) => {
+                    let index = match reference {
+                        Reference::Index(index) => Some(*index),
+                        Reference::Name(name) => self.context.group_index(name),
+                    };
+
+                    if let Some((l, r)) = index.and_then(|i| groups.as_ref()?.get(i).copied().flatten()) {
+                        out.write_str(&haystack[l..r])?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a [`RegExp`] equivalent to `\A(?:...)\z` wrapped around the regular expression, so that it only matches
+    /// expressions entirely, even in multi-line mode.
+    ///
+    /// The wrapped regular expression is compiled anew, so an [`Error`] is returned if it does not compile, e.g. if it
+    /// takes more states than allowed.
+    pub fn to_anchored(&self) -> Result<RegExp, Error> {
+        let group = Group { non_capturing: true, name: None, flags: None, index: None, expr: self.regex.clone() };
+        let anchored = vec![vec![
+            BasicExpression::Anchor(Anchor::TextStart),
+            BasicExpression::Quantified((Quantifiable::Group(group), None)),
+            BasicExpression::Anchor(Anchor::TextEnd),
+        ]];
+
+        let mut regexp = RegExp::new(anchored, self.flags.clone())?;
+        regexp.max_input_len = self.max_input_len;
+        Ok(regexp)
+    }
+
+    /// Returns a [`RegExp`] matching either the regular expression or `other`, as `(?:...)|(?:...)` around both, without
+    /// manipulating their patterns as strings.
+    ///
+    /// See [`RegExp::concat`] for how capturing groups and flags are combined.
+    pub fn alternate(&self, other: &RegExp) -> Result<RegExp, Error> {
+        self.compose(other, |this, other| vec![vec![this], vec![other]])
+    }
+
+    /// Returns a [`RegExp`] matching the regular expression followed by `other`, as `(?:...)(?:...)` around both, without
+    /// manipulating their patterns as strings.
+    ///
+    /// The capturing groups of `other` are numbered after those of the regular expression, along with its backreferences
+    /// and recursions, and an [`Error`] is returned if both name a capturing group alike. Each keeps its own `i`, `m`, `s`
+    /// and `u` flags, while the other [`Flags`], e.g. [`Flags::semantics`], are those of the regular expression.
+    pub fn concat(&self, other: &RegExp) -> Result<RegExp, Error> {
+        self.compose(other, |this, other| vec![vec![this, other]])
+    }
+
+    /// Returns a [`RegExp`] matching the regular expression repeated any number of times, including none, as `(?:...)*`.
+    ///
+    /// As with [`RegExp::to_anchored`], an [`Error`] is returned if the quantified regular expression does not compile.
+    pub fn repeat(&self) -> Result<RegExp, Error> {
+        self.quantify(Quantifier::ZeroOrMore)
+    }
+
+    /// Returns a [`RegExp`] matching the regular expression or the empty string, as `(?:...)?`.
+    ///
+    /// As with [`RegExp::to_anchored`], an [`Error`] is returned if the quantified regular expression does not compile.
+    pub fn optional(&self) -> Result<RegExp, Error> {
+        self.quantify(Quantifier::ZeroOrOne)
+    }
+
+    /// Returns a [`RegExp`] matching the regular expression repeated at least once, as `(?:...)+`.
+    ///
+    /// As with [`RegExp::to_anchored`], an [`Error`] is returned if the quantified regular expression does not compile.
+    pub fn one_or_more(&self) -> Result<RegExp, Error> {
+        self.quantify(Quantifier::OneOrMore)
+    }
+
+    /// Returns the regular expression as a non-capturing group, quantified by `quantifier`, scoping the `i`, `m`, `s` and
+    /// `u` flags it was compiled with.
+    fn as_group(&self, quantifier: Option<Quantifier>) -> BasicExpression {
+        let (enabled, disabled): (Vec<_>, Vec<_>) = [
+            ('i', self.flags.case_insensitive),
+            ('m', self.flags.multi_line),
+            ('s', self.flags.dot_all),
+            ('u', self.flags.unicode),
+        ]
+        .into_iter()
+        .partition(|(_, enabled)| *enabled);
+        let flags = (enabled.into_iter().map(|(f, _)| f).collect(), disabled.into_iter().map(|(f, _)| f).collect());
+
+        let group = Group { non_capturing: true, name: None, flags: Some(flags), index: None, expr: self.regex.clone() };
+        BasicExpression::Quantified((Quantifiable::Group(group), quantifier))
     }
 
-    /// Returns a list of all substrings of `expr` which matches the regular expression.
-    pub fn global_search(&self, expr: &str) -> Vec<String> {
-        self.0.global_search(expr)
+    /// Returns the [`RegExp`] arranging the regular expression and `other`, as groups, into a syntax representation with
+    /// `arrange`.
+    fn compose<F: FnOnce(BasicExpression, BasicExpression) -> Regex>(&self, other: &RegExp, arrange: F) -> Result<RegExp, Error> {
+        let mut other_group = other.as_group(None);
+        if let BasicExpression::Quantified((Quantifiable::Group(group), _)) = &mut other_group {
+            context::shift_references(&mut group.expr, self.group_count());
+        }
+
+        let mut regexp = RegExp::new(arrange(self.as_group(None), other_group), self.flags.clone())?;
+        regexp.max_input_len = self.max_input_len;
+        Ok(regexp)
+    }
+
+    /// Returns the [`RegExp`] matching the regular expression quantified by `quantifier`.
+    fn quantify(&self, quantifier: Quantifier) -> Result<RegExp, Error> {
+        let mut regexp = RegExp::new(vec![vec![self.as_group(Some(quantifier))]], self.flags.clone())?;
+        regexp.max_input_len = self.max_input_len;
+        Ok(regexp)
+    }
+
+    /// Returns the indices of the branches of the outermost alternation whose expressions are all matched by earlier
+    /// branches already, e.g. the second branch of `(a|a)`.
+    ///
+    /// Branches containing anchors cannot be turned into a [`Dfa`], so they are neither reported nor used to cover
+    /// later branches.
+    pub fn redundant_branches(&self) -> Vec<usize> {
+        let branches = analysis::alternation(&self.regex);
+        let dfa = |branches: &[Vec<BasicExpression>]| branches.to_vec().compile(&self.flags).ok()?.to_dfa();
+
+        (1..branches.len())
+            .filter(|&index| {
+                let earlier: Vec<_> = branches[..index]
+                    .iter()
+                    .filter(|&branch| dfa(slice::from_ref(branch)).is_some())
+                    .cloned()
+                    .collect();
+
+                !earlier.is_empty()
+                    && dfa(&branches[index..=index])
+                        .zip(dfa(&earlier))
+                        .is_some_and(|(branch, earlier)| branch.is_subset_of(&earlier))
+            })
+            .collect()
+    }
+
+    /// Returns the minimal [`Dfa`] accepting exactly the expressions the regular expression matches entirely, or `None` if
+    /// the regular expression contains anchors.
+    pub fn minimize(&self) -> Option<Dfa> {
+        self.automata.minimize()
+    }
+
+    /// Returns every path through the states of the regular expression along which its leftmost match in `expr` is
+    /// matched, each as the sequence of ids of the states traversed, or no path if there is no match.
+    ///
+    /// This is meant for debugging regular expressions. Each path runs from the start state to the end state, and the id
+    /// of a state is its index in breadth-first order from the start state. Paths are enumerated exhaustively, separately
+    /// from matching, so there may be exponentially many of them, and recursions and backreferences are never traversed.
+    pub fn match_paths(&self, expr: impl AsRef<str>) -> Vec<Vec<usize>> {
+        let expr = expr.as_ref();
+        self.find(expr)
+            .map_or(vec![], |m| self.automata.match_paths(expr, (m.start(), m.end())))
+    }
+
+    /// Returns a [`MatchTrace`] recording how far matching the regular expression against `expr` progressed, and which
+    /// characters were expected where it got stuck.
+    pub fn explain(&self, expr: impl AsRef<str>) -> MatchTrace {
+        let expr = expr.as_ref();
+        self.automata.explain(expr)
+    }
+}
+
+impl Drop for RegExp {
+    fn drop(&mut self) {
+        // The states of the automata are only referenced by each other, so they are unlinked before being dropped
+        self.automata.unlink();
+    }
+}
+
+/// Composes two [`RegExp`] into their alternation, as in [`RegExp::alternate`].
+impl BitOr for RegExp {
+    type Output = Result<RegExp, Error>;
+
+    fn bitor(self, other: RegExp) -> Self::Output {
+        self.alternate(&other)
+    }
+}
+
+/// Composes two [`RegExp`] into their concatenation, as in [`RegExp::concat`].
+impl Add for RegExp {
+    type Output = Result<RegExp, Error>;
+
+    fn add(self, other: RegExp) -> Self::Output {
+        self.concat(&other)
     }
 }
 
 /// Initialise an instance of [`Language<Regex>`], a [`Language`] defining the Regex language.
 pub fn init() -> Language<Regex> {
+    #[cfg(feature = "stats")]
+    stats::record_grammar_build();
+
     Language::new(grammar::regex())
 }
 
+thread_local! {
+    /// The [`Language<Regex>`] shared by compilations on the current thread, so that its grammar is only built once.
+    static REGEX: Language<Regex> = init();
+}
+
 impl Language<Regex> {
     /// Compiles `expr` as a regular expression into a [`RegExp`].
     pub fn compile(&self, expr: &str) -> Result<RegExp, Error> {
-        Ok(RegExp(self.parse(expr)?))
+        self.compile_with_flags(expr, Flags::default())
+    }
+
+    /// Compiles `expr` as a regular expression into a [`RegExp`], along with [`Warning`]s about potential issues with it.
+    pub fn compile_checked(&self, expr: &str) -> Result<(RegExp, Vec<Warning>), Error> {
+        let regexp = self.compile(expr)?;
+        let warnings = analysis::warnings(&regexp.regex);
+
+        Ok((regexp, warnings))
+    }
+
+    /// Compiles each of `patterns` as a regular expression into a [`RegExp`], reusing the grammar of the [`Language`].
+    pub fn compile_many(&self, patterns: &[&str]) -> Vec<Result<RegExp, Error>> {
+        patterns
+            .iter()
+            .map(|pattern| self.compile(pattern))
+            .collect()
+    }
+
+    /// Compiles the longest prefix of `expr` forming a regular expression into a [`RegExp`], and returns it along with the
+    /// remainder of `expr`, e.g. to embed regular expressions within a larger grammar.
+    ///
+    /// Parsing stops at the first character which cannot continue the regular expression, e.g. an unmatched `)`, so that
+    /// `a+b) rest` compiles `a+b` and leaves `) rest`. Whitespaces are literals, so they never end the prefix. Returns
+    /// `None` if no prefix of `expr` forms a regular expression, or if it fails to compile.
+    pub fn parse_prefix<'e>(&self, expr: &'e str) -> Option<(RegExp, &'e str)> {
+        let (regex, rst) = self.syntax_prefix(expr)?;
+
+        Some((RegExp::new(regex, Flags::default()).ok()?, rst))
+    }
+
+    /// Compiles `expr` as a regular expression into a [`RegExp`] according to `flags`, e.g. with
+    /// [`Flags::case_insensitive`] so that `Hello` matches `"HELLO"`.
+    ///
+    /// This is the same as building `expr` with a [`RegExpBuilder`] setting each of `flags`, but reuses the grammar of the
+    /// [`Language`].
+    pub fn compile_with_flags(&self, expr: &str, flags: Flags) -> Result<RegExp, Error> {
+        RegExp::new(self.syntax(expr)?, flags)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+    use std::time::{Duration, Instant};
+
+    use crate::bench_support;
+
     use super::*;
 
     #[test]
@@ -308,6 +1169,29 @@ mod tests {
 
             assert!(regexp.full_match(&chars));
         }
+        {
+            let compiled_regexp = regex.compile("[a-f0-9_]+");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("deadbeef_42"));
+            assert!(regexp.full_match("_"));
+            assert!(!regexp.full_match("g"));
+            assert!(!regexp.full_match("A"));
+            assert!(!regexp.full_match("-"));
+
+            assert_eq!(regexp.global_search("0x_ff-G9"), vec!["0", "_ff", "9"]);
+        }
+        {
+            let compiled_regexp = regex.compile("[abc]");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("a"));
+            assert!(regexp.full_match("c"));
+            assert!(!regexp.full_match("ab"));
+            assert!(!regexp.full_match("d"));
+        }
     }
 
     #[test]
@@ -361,6 +1245,14 @@ mod tests {
         assert_eq!(regexp.global_search("abba"), vec!["ab", "ba"]);
         assert_eq!(regexp.global_search("ababa"), vec!["abab", ""]);
         assert_eq!(regexp.global_search("abbaab"), vec!["ab", "ba", "ab"]);
+
+        // An earlier attempt still unmatched at the end of the expression does not hide a later match
+        let compiled_regexp = regex.compile("a+ba|ab");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.find("aab").map(|m| (m.start(), m.end())), Some((1, 3)));
+        assert_eq!(regexp.find_iter("aab").count(), 1);
     }
 
     #[test]
@@ -461,6 +1353,30 @@ mod tests {
         assert_eq!(regexp.global_search("ab"), Vec::<String>::new());
     }
 
+    #[test]
+    fn regex_anchors_empty_input() {
+        let regex = init();
+
+        // The empty input still has a position, both at its start and at its end, for anchors to match at
+        for (pattern, matched) in [
+            ("^$", true),
+            ("$", true),
+            ("^", true),
+            ("a$", false),
+            ("^a", false),
+            ("a*$", true),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert_eq!(regexp.full_match(""), matched, "{pattern}");
+            assert_eq!(regexp.is_match(""), matched, "{pattern}");
+            assert_eq!(regexp.find("").map(|m| m.range()), matched.then_some(0..0), "{pattern}");
+            assert_eq!(regexp.global_search(""), if matched { vec![""] } else { vec![] }, "{pattern}");
+        }
+    }
+
     #[test]
     fn regex_bad_anchor() {
         let regex = init();
@@ -564,4 +1480,3354 @@ mod tests {
         assert_eq!(regexp.global_search("aaaaaaaa"), vec!["aaaaa"]);
         assert_eq!(regexp.global_search("aaabaaaacaa"), Vec::<String>::new());
     }
+
+    #[test]
+    fn regex_explain() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("abc");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(
+            regexp.explain("abx"),
+            MatchTrace { matched: false, start: 0, offset: 2, expected: vec![PredicateKind::Char('c')] }
+        );
+        assert_eq!(
+            regexp.explain("xab"),
+            MatchTrace { matched: false, start: 1, offset: 3, expected: vec![PredicateKind::Char('c')] }
+        );
+        assert_eq!(regexp.explain("zabc"), MatchTrace { matched: true, start: 1, offset: 4, expected: vec![] });
+
+        let compiled_regexp = regex.compile("a(b|c)d");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(
+            regexp.explain("ae"),
+            MatchTrace {
+                matched: false,
+                start: 0,
+                offset: 1,
+                expected: vec![PredicateKind::Char('b'), PredicateKind::Char('c')]
+            }
+        );
+        assert_eq!(
+            regexp.explain("ébé acx"),
+            MatchTrace { matched: false, start: 6, offset: 8, expected: vec![PredicateKind::Char('d')] }
+        );
+
+        // Character classes and wildcards are reported by the characters they accept
+        let compiled_regexp = regex.compile(r"a(\d|[b-c]|.x)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let trace = regexp.explain("a\n");
+        assert!(!trace.matched);
+        assert_eq!(trace.offset, 1);
+        assert_eq!(
+            trace.expected,
+            vec![
+                PredicateKind::Digit,
+                PredicateKind::Union(vec![PredicateKind::Range('b', 'c')]),
+                PredicateKind::Not(Box::new(PredicateKind::Char('\n')))
+            ]
+        );
+    }
+
+    #[test]
+    fn regex_inverted_character_groups() {
+        let regex = init();
+
+        {
+            let compiled_regexp = regex.compile(r"[^\d\s]+");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("abc"));
+            assert!(!regexp.full_match("a1"));
+            assert!(!regexp.full_match("a "));
+
+            assert_eq!(regexp.global_search("ab1c d"), vec!["ab", "c", "d"]);
+        }
+        {
+            let compiled_regexp = regex.compile(r"[\D\S]");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("a"));
+            assert!(regexp.full_match("1"));
+            assert!(regexp.full_match(" "));
+            assert!(regexp.full_match("\n"));
+        }
+        {
+            let compiled_regexp = regex.compile("[^abc]+");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("xyz"));
+            assert!(!regexp.full_match("xaz"));
+            assert_eq!(regexp.global_search("abxcyz"), vec!["x", "yz"]);
+        }
+        {
+            let compiled_regexp = regex.compile("[^a-z0-9]+");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("AB_ -"));
+            assert!(!regexp.full_match("A1"));
+            assert_eq!(regexp.global_search("ab-C9D"), vec!["-C", "D"]);
+        }
+        {
+            let compiled_regexp = regex.compile(r"[^\s]+");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("a1!"));
+            assert_eq!(regexp.global_search("a b\tc\n"), vec!["a", "b", "c"]);
+        }
+        {
+            // Only a leading `^` inverts the character group
+            let compiled_regexp = regex.compile("[a^]+");
+            assert!(compiled_regexp.is_ok());
+            assert!(compiled_regexp.unwrap().full_match("a^a"));
+
+            let compiled_regexp = regex.compile("[^^]+");
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+            assert!(regexp.full_match("ab"));
+            assert!(!regexp.full_match("a^"));
+
+            let compiled_regexp = regex.compile(r"[\^a]+");
+            assert!(compiled_regexp.is_ok());
+            assert!(compiled_regexp.unwrap().full_match("^a"));
+        }
+
+        // An inverted character group needs at least one item
+        assert!(regex.compile("[^]").is_err());
+        assert!(RegExpBuilder::new("[^]")
+            .allow_empty_class(true)
+            .build()
+            .is_err());
+        assert!(regex
+            .compile("[^]a]")
+            .is_ok_and(|regexp| regexp.full_match("b") && !regexp.full_match("]")));
+    }
+
+    #[test]
+    fn regex_posix_classes() {
+        let regex = init();
+
+        for (pattern, matching, not_matching) in [
+            ("[[:alpha:]]+", "abcXYZ", "a1"),
+            ("[[:digit:][:space:]]+", "1 2\t3", "1a"),
+            ("[[:punct:]]+", "!/:@[`{~", "a"),
+            ("[[:word:]]+", "a_1", "a-1"),
+            ("[[:^digit:]]+", "ab c!", "a1"),
+            ("[[:^digit:]a]+", "abc", "1"),
+            ("[[:^digit:]1]+", "ab1c", "2"),
+            ("[^[:^digit:]]+", "123", "1a"),
+            ("[^[:^digit:][:^alpha:]]", "", "a"),
+            ("[^[:digit:][:space:]]+", "ab!", "a b"),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok(), "{pattern}");
+            let regexp = compiled_regexp.unwrap();
+
+            assert_eq!(regexp.full_match(matching), !matching.is_empty(), "{pattern} on {matching:?}");
+            assert!(!regexp.full_match(not_matching), "{pattern} on {not_matching:?}");
+        }
+
+        // POSIX classes are ASCII, even with Unicode, and accept the other case of their letters when case insensitive
+        let compiled_regexp = RegExpBuilder::new("[[:alpha:]]+").unicode(true).build();
+        assert!(compiled_regexp.is_ok());
+        assert!(!compiled_regexp.unwrap().full_match("é"));
+
+        let compiled_regexp = regex.compile("(?i)[[:upper:]]+");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("aBc"));
+
+        let compiled_regexp = regex.compile("(?i)[[:^upper:]]");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert!(regexp.full_match("1"));
+        assert!(!regexp.full_match("a"));
+
+        // Outside of a POSIX class, `[` and `:` are literal members of a character group
+        let compiled_regexp = regex.compile("[[:a]+");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match(":[a"));
+
+        // A POSIX class must be named by one of the known names
+        let unknown = |name: &str, position| Some(Error::new(ErrorKind::UnknownPosixClass(String::from(name), position)));
+        assert_eq!(regex.compile("[[:alhpa:]]").err(), unknown("alhpa", 1));
+        assert_eq!(regex.compile("x[a[:^Digit:]]").err(), unknown("Digit", 3));
+        assert_eq!(regex.compile("[[:alhpa:]-z]").err(), unknown("alhpa", 1));
+        assert_eq!(
+            regex.compile("[[:alhpa:]]").err().map(|e| e.msg()),
+            Some(String::from("Unknown POSIX class `[:alhpa:]` at position 1"))
+        );
+    }
+
+    #[test]
+    fn regex_replace_all() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.replace_all("a1b22c333", "#"), "a#b#c#");
+        assert_eq!(regexp.replace_all("abc", "#"), "abc");
+        assert_eq!(regexp.replace_all("", "#"), "");
+
+        for haystack in ["a1b22c333", "123", "no digits", "é1è2"] {
+            let mut out = String::new();
+            assert!(regexp.replace_all_to(haystack, "<num>", &mut out).is_ok());
+            assert_eq!(out, regexp.replace_all(haystack, "<num>"));
+        }
+
+        let compiled_regexp = regex.compile("a*");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.replace_all("bab", "-"), "-b-b-");
+    }
+
+    #[test]
+    fn regex_backspace() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"[\b]");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("\x08"));
+        assert!(!regexp.full_match("b"));
+        assert_eq!(regexp.global_search("a\x08b"), vec!["\x08"]);
+
+        let compiled_regexp = regex.compile(r"\bfoo\b");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("foo"));
+        assert_eq!(regexp.global_search("a foo b"), vec!["foo"]);
+        assert_eq!(regexp.global_search("afoo\x08"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn regex_to_anchored() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_match("a123b"));
+        assert_eq!(regexp.find("a123b").map(|m| m.range()), Some(1..4));
+
+        let anchored = regexp.to_anchored();
+        assert!(anchored.is_ok());
+        let anchored = anchored.unwrap();
+
+        assert!(anchored.is_match("123"));
+        assert!(anchored.full_match("123"));
+        assert_eq!(anchored.find("123").map(|m| m.range()), Some(0..3));
+        assert!(!anchored.is_match("a123b"));
+        assert!(!anchored.is_match("123b"));
+        assert_eq!(anchored.find("a123b").map(|m| m.range()), None);
+
+        let compiled_regexp = regex.compile("a|bc");
+        assert!(compiled_regexp.is_ok());
+        let anchored = compiled_regexp.unwrap().to_anchored();
+        assert!(anchored.is_ok());
+        let anchored = anchored.unwrap();
+
+        assert!(anchored.is_match("a"));
+        assert!(anchored.is_match("bc"));
+        assert!(!anchored.is_match("abc"));
+
+        // Lines are not expressions, so multi-line mode does not let a line match on its own
+        let compiled_regexp = RegExpBuilder::new(r"\d+").multi_line(true).build();
+        assert!(compiled_regexp.is_ok());
+        let anchored = compiled_regexp.unwrap().to_anchored();
+        assert!(anchored.is_ok());
+        let anchored = anchored.unwrap();
+
+        assert!(anchored.is_match("123"));
+        assert!(!anchored.is_match("x\n123"));
+        assert!(!anchored.is_match("123\nx"));
+        assert!(!anchored.is_match("123\n"));
+        assert_eq!(anchored.find("x\n123"), None);
+    }
+
+    #[test]
+    fn regex_max_input_len() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("a+");
+        assert!(compiled_regexp.is_ok());
+        let mut regexp = compiled_regexp.unwrap();
+        regexp.set_max_input_len(4);
+
+        assert!(regexp.full_match("aaaa"));
+        assert_eq!(regexp.greedy_search("baaa"), Some(String::from("aaa")));
+        let transitions = regexp.transition_count();
+        assert!(transitions > 0);
+
+        assert!(!regexp.full_match("aaaaa"));
+        assert!(!regexp.is_match("aaaaa"));
+        assert_eq!(regexp.find("aaaaa").map(|m| m.range()), None);
+        assert_eq!(regexp.greedy_search("aaaaa"), None);
+        assert_eq!(regexp.global_search("aaaaa"), Vec::<String>::new());
+        assert_eq!(regexp.replace_all("aaaaa", "b"), "aaaaa");
+
+        // Oversized expressions are rejected before running the automata
+        assert_eq!(regexp.transition_count(), transitions);
+    }
+
+    #[test]
+    fn regex_anchored_find_iter() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("^a");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_anchored_start());
+        let matches: Vec<Match> = regexp.find_iter("aaa").collect();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].range(), 0..1);
+        assert_eq!(regexp.find_iter("baa").next().map(|m| m.range()), None);
+
+        // Only the attempt starting at offset 0 is ever made
+        let long = "a".repeat(100);
+        let transitions = regexp.transition_count();
+        assert_eq!(regexp.find_iter(&long).count(), 1);
+        assert!(regexp.transition_count() - transitions <= 2);
+
+        for (pattern, anchored) in [("^a|^b", true), ("(^a)b", true), ("^a|b", false), ("a^", false), ("a", false)] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            assert_eq!(compiled_regexp.unwrap().is_anchored_start(), anchored);
+        }
+    }
+
+    #[test]
+    fn regex_anchor_alternation() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(?:^|\s)@\w+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(!regexp.is_anchored_start());
+        assert_eq!(regexp.global_search("@a foo @b"), vec!["@a", " @b"]);
+        assert_eq!(regexp.global_search("x@a foo@b"), Vec::<String>::new());
+        assert_eq!(
+            regexp
+                .find_iter("@a foo @b")
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..2, 6..9]
+        );
+
+        let compiled_regexp = regex.compile(r"(?:^|,)\d");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("1"));
+        assert!(regexp.full_match(",1"));
+        assert_eq!(regexp.global_search("1,2,a3"), vec!["1", ",2"]);
+    }
+
+    #[test]
+    fn regex_backreference() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(ab)\1");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("abab"));
+        assert!(!regexp.full_match("abxy"));
+        assert!(!regexp.full_match("ab"));
+        assert_eq!(regexp.global_search("xabababab"), vec!["abab", "abab"]);
+
+        // The backreference matches what the group captured, not what the group matches
+        let compiled_regexp = regex.compile(r"(a|b)+\1");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("abb"));
+        assert!(regexp.full_match("baa"));
+        assert!(!regexp.full_match("aab"));
+
+        // A backreference to a group which did not participate in the match fails
+        let compiled_regexp = regex.compile(r"(?:(a)|b)\1");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("aa"));
+        assert!(!regexp.full_match("b"));
+    }
+
+    #[test]
+    fn regex_multidigit_backreference() {
+        let regex = init();
+
+        let groups = "(a)".repeat(12);
+        let syntax = regex.syntax(&format!(r"{groups}\11"));
+        assert!(syntax.is_ok());
+        let mut expr = syntax.unwrap();
+
+        let context = RegexContext::process(&mut expr);
+        assert!(context.is_ok());
+        assert_eq!(context.unwrap().group_count(), 12);
+        assert!(matches!(expr[0].last(), Some(BasicExpression::Quantified((Quantifiable::Backreference(11), None)))));
+
+        let compiled_regexp = regex.compile(&format!(r"{groups}\11"));
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("a".repeat(13)));
+
+        // Without enough capturing groups, `\11` is the octal escape of a tab
+        let compiled_regexp = regex.compile(r"(a)(b)\11");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 2);
+        assert!(regexp.full_match("ab\t"));
+        assert!(!regexp.full_match("abb"));
+
+        // Only up to three octal digits are read, and the quantifier only applies to the last character
+        let compiled_regexp = regex.compile(r"\1014+|\19");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("A4444"));
+        assert!(regexp.full_match("\x019"));
+        assert!(!regexp.full_match("A4A4"));
+
+        assert!(regex.compile(r"(a)\2").is_err());
+    }
+
+    #[test]
+    fn regex_captures_repetition_time() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("((a+)(b|c)?)+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        // Paths reaching a state with less preferred captures are not explored again, so the work grows linearly
+        let transitions = |expr: &str| {
+            let before = regexp.transition_count();
+            let captures = regexp.captures(expr).map(|captures| {
+                captures
+                    .iter()
+                    .map(|c| c.as_ref().map(String::len))
+                    .collect()
+            });
+            assert_eq!(captures, Some(vec![Some(expr.len()), Some(expr.len()), Some(expr.len()), None]));
+            regexp.transition_count() - before
+        };
+        let start = Instant::now();
+        let (short, long) = (transitions(&"a".repeat(200)), transitions(&"a".repeat(400)));
+        assert!(long <= 3 * short, "{short} transitions on 200 characters, {long} on 400");
+        assert!(start.elapsed() < Duration::from_secs(2), "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn regex_captures_repetition() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(\d)+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 1);
+        assert_eq!(regexp.captures("123"), Some(vec![Some(String::from("123")), Some(String::from("3"))]));
+        assert_eq!(regexp.captures("ab45c"), Some(vec![Some(String::from("45")), Some(String::from("5"))]));
+        assert_eq!(regexp.captures("abc"), None);
+
+        let compiled_regexp = regex.compile(r"(\w)(\d)*x");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(
+            regexp.captures("a12x"),
+            Some(vec![Some(String::from("a12x")), Some(String::from("a")), Some(String::from("2"))])
+        );
+        assert_eq!(regexp.captures("ax"), Some(vec![Some(String::from("ax")), Some(String::from("a")), None]));
+
+        // A repeated group which may match the empty string captures its last non-empty repetition, if any
+        for semantics in [MatchSemantics::PosixLeftmostLongest, MatchSemantics::PerlLeftmostFirst] {
+            for (pattern, expr, expected) in [
+                ("(a*)*b", "aab", ["aab", "aa"]),
+                ("(a?)+", "aa", ["aa", "a"]),
+                ("(a|b?)*c", "abc", ["abc", "b"]),
+                ("(a*)+", "b", ["", ""]),
+            ] {
+                let compiled_regexp = RegExpBuilder::new(pattern).semantics(semantics).build();
+                assert!(compiled_regexp.is_ok(), "{pattern}");
+                assert_eq!(
+                    compiled_regexp.unwrap().captures(expr),
+                    Some(expected.map(|c| Some(String::from(c))).to_vec()),
+                    "{pattern} on {expr:?} with {semantics:?}"
+                );
+            }
+
+            let compiled_regexp = RegExpBuilder::new("($a|a*(...)|b*?)*a{1,2}")
+                .semantics(semantics)
+                .build();
+            assert!(compiled_regexp.is_ok());
+            assert_eq!(
+                compiled_regexp.unwrap().captures("bba"),
+                Some(vec![Some(String::from("bba")), Some(String::from("bb")), None])
+            );
+        }
+    }
+
+    #[test]
+    fn regex_builder() {
+        let compiled_regexp = RegExpBuilder::new("hello.world").build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("hello world"));
+        assert!(!regexp.full_match("HELLO world"));
+        assert!(!regexp.full_match("hello\nworld"));
+
+        let compiled_regexp = RegExpBuilder::new("hello.world")
+            .case_insensitive(true)
+            .dot_all(true)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("hello world"));
+        assert!(regexp.full_match("HELLO wORLD"));
+        assert!(regexp.full_match("hello\nworld"));
+
+        let compiled_regexp = RegExpBuilder::new("^[a-c]+$")
+            .case_insensitive(true)
+            .multi_line(true)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(!regexp.is_anchored_start());
+        assert_eq!(regexp.global_search("abc\nxyz\nCAB"), vec!["abc", "CAB"]);
+        assert!(!regexp.full_match("abc\nCAB"));
+
+        let compiled_regexp = RegExpBuilder::new(r"[^a]\w+")
+            .case_insensitive(true)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("bcd"));
+        assert!(!regexp.full_match("Abc"));
+
+        let compiled_regexp = RegExpBuilder::new(r"\w+").build();
+        assert!(compiled_regexp.is_ok());
+        assert!(!compiled_regexp.unwrap().full_match("café"));
+
+        let compiled_regexp = RegExpBuilder::new(r"\w+").unicode(true).build();
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("café"));
+
+        let compiled_regexp = RegExpBuilder::new("ÉTÉ")
+            .case_insensitive(true)
+            .unicode(true)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("été"));
+
+        assert!(RegExpBuilder::new("ÉTÉ")
+            .case_insensitive(true)
+            .build()
+            .is_err());
+        assert!(RegExpBuilder::new("a(b").build().is_err());
+
+        // Inline case insensitivity requires Unicode all the same, and only applies where enabled
+        for pattern in ["(?i)ÉTÉ", "(?i:[é])", "(?i)[à-é]"] {
+            let compiled_regexp = RegExpBuilder::new(pattern).build();
+            assert_eq!(compiled_regexp.err().unwrap().kind(), &ErrorKind::UnicodeRequired);
+        }
+        assert!(RegExpBuilder::new("(?iu)ÉTÉ")
+            .build()
+            .unwrap()
+            .full_match("été"));
+        assert!(RegExpBuilder::new("é(?i)a")
+            .build()
+            .unwrap()
+            .full_match("éA"));
+    }
+
+    #[test]
+    fn regex_replace_all_references() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(?<year>\d{4})/(?<month>\d{2})");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 2);
+        assert_eq!(regexp.replace_all("on 2024/03 and 1999/12", "${year}-${month}"), "on 2024-03 and 1999-12");
+        assert_eq!(regexp.replace_all("2024/03", "$month.$year"), "03.2024");
+        assert_eq!(regexp.replace_all("2024/03", "$2.$1"), "03.2024");
+        assert_eq!(regexp.replace_all("2024/03", "$0!"), "2024/03!");
+        assert_eq!(regexp.replace_all("2024/03", "$$1 costs $"), "$1 costs $");
+        assert_eq!(regexp.replace_all("2024/03", "[$day$3${nope}]"), "[]");
+        assert_eq!(regexp.replace_all("2024/03", "${year"), "${year");
+
+        let compiled_regexp = regex.compile("(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.replace_all("abcdefghij", "$10"), "j");
+        assert_eq!(regexp.replace_all("abcdefghij", "${1}0"), "a0");
+        assert_eq!(regexp.replace_all("abcdefghij", "${10}"), "j");
+
+        assert!(regex.compile("(?<a>x)(?<a>y)").is_err());
+        assert!(regex.compile("(?<1a>x)").is_err());
+    }
+
+    #[test]
+    fn regex_minimize() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("(a|b)(a|b)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let minimal = regexp.minimize();
+        assert!(minimal.is_some());
+        let minimal = minimal.unwrap();
+
+        assert_eq!(minimal.state_count(), 3);
+        assert!(minimal.full_match("ab"));
+        assert!(minimal.full_match("bb"));
+        assert!(!minimal.full_match("a"));
+        assert!(!minimal.full_match("abb"));
+
+        let compiled_regexp = regex.compile("[ab][ab]");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().minimize(), Some(minimal));
+
+        let compiled_regexp = regex.compile("^ab");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().minimize().is_none());
+    }
+
+    #[test]
+    fn regex_find_end_anchor() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("xyz+$");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "xxxyzwxyz";
+        assert_eq!(regexp.find(expr).map(|m| m.range()), Some(6..9));
+        assert_eq!(regexp.greedy_search(expr), Some(String::from("xyz")));
+
+        let expr = "xyzzz xyz xyzz";
+        assert_eq!(regexp.find(expr).map(|m| m.range()), Some(10..14));
+        assert_eq!(regexp.greedy_search(expr), Some(String::from("xyzz")));
+        assert_eq!(regexp.global_search(expr), vec!["xyzz"]);
+
+        assert_eq!(regexp.find("xyz xyzw").map(|m| m.range()), None);
+        assert_eq!(regexp.find("xyz\n").map(|m| m.range()), None);
+
+        let compiled_regexp = RegExpBuilder::new("xyz+$").multi_line(true).build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "xyzw\nxyz\nxyzzz";
+        assert_eq!(regexp.find(expr).map(|m| m.range()), Some(5..8));
+        assert_eq!(regexp.greedy_search(expr), Some(String::from("xyzzz")));
+        assert_eq!(regexp.global_search(expr), vec!["xyz", "xyzzz"]);
+    }
+
+    #[test]
+    fn regex_matches() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "a1 b22 c333 d4444";
+        assert_eq!(regexp.find_iter(expr).count(), 4);
+        assert_eq!(regexp.find_iter(expr).filter(|m| m.len() % 2 == 0).count(), 2);
+        assert_eq!(
+            regexp
+                .find_iter(expr)
+                .map(|m| m.as_str(expr))
+                .collect::<Vec<_>>(),
+            vec!["1", "22", "333", "4444"]
+        );
+
+        let mut matches = regexp.find_iter(expr).skip(1);
+        assert_eq!(matches.next().map(|m| m.range()), Some(4..6));
+
+        // Matches are found lazily, so taking the first match does not scan the rest of the expression
+        let long = format!("1{}", "a".repeat(100));
+        let transitions = regexp.transition_count();
+        assert_eq!(regexp.find_iter(&long).next().map(|m| m.range()), Some(0..1));
+        assert!(regexp.transition_count() - transitions <= 4);
+
+        for (pattern, expr) in [
+            ("a*", "bab"),
+            ("a?b?", "aabxb"),
+            (r"\b", "ab cd"),
+            ("x*", "é"),
+            ("(?:^|,)\\d", "1,2,x,3"),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            let matches: Vec<String> = regexp
+                .find_iter(expr)
+                .map(|m| String::from(m.as_str(expr)))
+                .collect();
+            assert_eq!(matches, regexp.global_search(expr));
+        }
+    }
+
+    #[test]
+    fn regex_is_match_bytes() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"^\w+$");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_match_bytes(b"hello"));
+        assert!(regexp.is_match_bytes("hello".as_bytes()));
+        assert!(!regexp.is_match_bytes(b"hello world"));
+        assert!(!regexp.is_match_bytes(b"hel\xfflo"));
+
+        let compiled_regexp = regex.compile(r"\w+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_match_bytes(b"\xff\xfeab"));
+        assert!(!regexp.is_match_bytes(b"\xff\xfe"));
+
+        let compiled_regexp = regex.compile("a\u{fffd}b");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().is_match_bytes(b"a\xc3b"));
+    }
+
+    #[test]
+    fn regex_replacen() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\d");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.replacen("a1a2a3", 2, "X"), "aXaXa3");
+        assert_eq!(regexp.replacen("a1a2a3", 0, "X"), "a1a2a3");
+        assert_eq!(regexp.replacen("a1a2a3", 3, "X"), "aXaXaX");
+        assert_eq!(regexp.replacen("a1a2a3", 10, "X"), regexp.replace_all("a1a2a3", "X"));
+        assert_eq!(regexp.replacen("abc", 1, "X"), "abc");
+
+        let compiled_regexp = regex.compile(r"(\d)(\d)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.replacen("12 34 56", 1, "$2$1"), "21 34 56");
+    }
+
+    #[test]
+    fn regex_word_chars() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\b\w+\b");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.global_search("foo_bar baz"), vec!["foo", "bar", "baz"]);
+        assert_eq!(regexp.global_search(""), Vec::<String>::new());
+
+        let compiled_regexp = RegExpBuilder::new(r"\b\w+\b")
+            .word_chars(|c| c.is_alphanumeric() || c == '_')
+            .build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.global_search("foo_bar baz"), vec!["foo_bar", "baz"]);
+        assert_eq!(regexp.global_search("_x é"), vec!["_x", "é"]);
+
+        let compiled_regexp = regex.compile(r"\Ba\B");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.find("a bab").map(|m| m.range()), Some(3..4));
+        assert!(!regexp.is_match("a ab ba"));
+
+        let compiled_regexp = RegExpBuilder::new(r"\B_\B")
+            .word_chars(|c| c == '_' || c.is_ascii_alphabetic())
+            .build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_match("a_b"));
+        assert!(!regexp.is_match("1_2"));
+    }
+
+    #[test]
+    fn regex_find_iter_empty_matches() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("a*");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let ranges = |expr| {
+            regexp
+                .find_iter(expr)
+                .map(|m| m.range())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranges("bab"), vec![0..0, 1..2, 3..3]);
+        assert_eq!(ranges(""), vec![0..0]);
+        assert_eq!(ranges("aa"), vec![0..2]);
+        assert_eq!(ranges("bb"), vec![0..0, 1..1, 2..2]);
+        assert_eq!(ranges("éa"), vec![0..0, 2..3]);
+
+        for expr in ["bab", "", "aa", "bb", "éa", "baab"] {
+            assert_eq!(
+                regexp
+                    .find_iter(expr)
+                    .map(|m| String::from(m.as_str(expr)))
+                    .collect::<Vec<_>>(),
+                regexp.global_search(expr)
+            );
+        }
+    }
+
+    #[test]
+    fn regex_captures_at() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(\d+)(\w*)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "abc 123xyz!";
+        let captures = regexp.captures_at(expr, 4);
+        assert!(captures.is_some());
+        let captures = captures.unwrap();
+
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(0), Some("123xyz"));
+        assert_eq!(captures.get(1), Some("123"));
+        assert_eq!(captures.get(2), Some("xyz"));
+        assert_eq!(captures.get(3), None);
+        assert_eq!(captures.get_match(1).map(|m| m.range()), Some(4..7));
+
+        let captures = regexp.captures_at(expr, 5).unwrap();
+        assert_eq!(captures.get(1), Some("23"));
+        assert_eq!(captures.get(2), Some("xyz"));
+
+        let captures = regexp.captures_at(expr, 6).unwrap();
+        assert_eq!(captures.get(1), Some("3"));
+        assert_eq!(captures.get(2), Some("xyz"));
+
+        // The match must start exactly at the offset
+        assert!(regexp.captures_at(expr, 0).is_none());
+        assert!(regexp.captures_at(expr, 3).is_none());
+        assert!(regexp.captures_at(expr, 7).is_none());
+        assert!(regexp.captures_at(expr, expr.len()).is_none());
+        assert!(regexp.captures_at(expr, expr.len() + 1).is_none());
+
+        // Offsets must be on a character boundary
+        assert!(regexp.captures_at("é1", 1).is_none());
+        assert_eq!(regexp.captures_at("é1", 2).unwrap().get(1), Some("1"));
+
+        let compiled_regexp = regex.compile(r"^(\d)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.captures_at("12", 0).unwrap().get(1), Some("1"));
+        assert!(regexp.captures_at("12", 1).is_none());
+    }
+
+    #[test]
+    fn regex_match_ordering() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "1 22 333 4444";
+        let matches: Vec<Match> = regexp.find_iter(expr).collect();
+
+        let mut shuffled = vec![matches[2], matches[0], matches[3], matches[1]];
+        shuffled.sort();
+        assert_eq!(shuffled, matches);
+
+        let set: BTreeSet<Match> = matches
+            .iter()
+            .chain(matches.iter().rev())
+            .copied()
+            .collect();
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), matches);
+
+        assert!(Match::new(0, 1) < Match::new(0, 2));
+        assert!(Match::new(0, 2) < Match::new(1, 1));
+        assert_eq!(regexp.find(expr), Some(Match::new(0, 1)));
+    }
+
+    #[test]
+    fn regex_global_search_order() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("ba*");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "zzbabaaaabbam";
+        assert_eq!(regexp.global_search(expr), vec!["ba", "baaaa", "b", "ba"]);
+        assert_eq!(
+            regexp
+                .find_iter(expr)
+                .map(|m| m.start())
+                .collect::<Vec<_>>(),
+            vec![2, 4, 9, 10]
+        );
+        // The longest match is not the first one, yet does not move ahead of it
+        assert_eq!(regexp.greedy_search(expr), Some(String::from("baaaa")));
+
+        for (pattern, expr) in [
+            ("ba*", "zzbabaaaabbam"),
+            (r"\d+|[a-z]+", "ab12cd345e6"),
+            ("a*", "baab"),
+            ("x(y|z)*", "xzyxxyyz"),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            let matches: Vec<Match> = regexp.find_iter(expr).collect();
+            assert!(matches
+                .windows(2)
+                .all(|w| w[0].end() <= w[1].start() && w[0] < w[1]));
+            assert_eq!(matches.iter().map(|m| m.as_str(expr)).collect::<Vec<_>>(), regexp.global_search(expr));
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn regex_trace() {
+        use std::sync::Mutex;
+
+        static RECORDS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+        struct Recorder;
+
+        impl log::Log for Recorder {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                RECORDS.lock().unwrap().push(record.args().to_string());
+            }
+
+            fn flush(&self) {}
+        }
+
+        static RECORDER: Recorder = Recorder;
+        assert!(log::set_logger(&RECORDER).is_ok());
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let compiled_regexp = init().compile("a|b");
+        assert!(compiled_regexp.is_ok());
+
+        let records = RECORDS.lock().unwrap();
+        assert!(records.iter().any(|r| r == r#"parsed "a|b", leaving """#));
+        assert!(records.iter().any(|r| r == r#"failed to parse "|b""#));
+        assert!(records
+            .iter()
+            .any(|r| r.starts_with("compiling [[Quantified((Match(Char('a'))")));
+        assert!(records.iter().any(|r| r == "compiling 'b'"));
+    }
+
+    #[test]
+    fn regex_captures_alternation() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("(a)|(b)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.captures("a"), Some(vec![Some(String::from("a")), Some(String::from("a")), None]));
+        assert_eq!(regexp.captures("b"), Some(vec![Some(String::from("b")), None, Some(String::from("b"))]));
+        assert_eq!(regexp.captures("c"), None);
+
+        // The capture table covers every group, however many groups each branch has
+        let compiled_regexp = regex.compile("(a)|(b)(c)(d)|e");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.captures("a").map(|c| c.len()), Some(5));
+        assert_eq!(
+            regexp.captures("xbcd"),
+            Some(vec![
+                Some(String::from("bcd")),
+                None,
+                Some(String::from("b")),
+                Some(String::from("c")),
+                Some(String::from("d"))
+            ])
+        );
+        assert_eq!(regexp.captures("e"), Some(vec![Some(String::from("e")), None, None, None, None]));
+    }
+
+    #[test]
+    fn regex_compile_checked() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile_checked("a.*b");
+        assert!(compiled_regexp.is_ok());
+        let (regexp, warnings) = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("axyzb"));
+        assert_eq!(warnings, vec![Warning::UnboundedAny(String::from(".*"))]);
+        assert_eq!(warnings[0].to_string(), "`.*` can be expensive to match against long expressions");
+
+        let compiled_regexp = regex.compile_checked("a[bc]*d");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().1.is_empty());
+
+        let compiled_regexp = regex.compile_checked(r"(x.+)|.{2,}|.?|.{1,3}|\.*");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(
+            compiled_regexp.unwrap().1,
+            vec![
+                Warning::UnboundedAny(String::from(".+")),
+                Warning::UnboundedAny(String::from(".{2,}"))
+            ]
+        );
+
+        assert!(regex.compile_checked("a(").is_err());
+    }
+
+    #[test]
+    fn regex_scoped_flags() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("(?i:abc)DEF");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 0);
+        assert!(regexp.full_match("ABCDEF"));
+        assert!(regexp.full_match("abcDEF"));
+        assert!(regexp.full_match("aBcDEF"));
+        assert!(!regexp.full_match("abcdef"));
+        assert!(!regexp.full_match("ABCDEf"));
+
+        let compiled_regexp = RegExpBuilder::new("(?-i:a)b")
+            .case_insensitive(true)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("aB"));
+        assert!(!regexp.full_match("Ab"));
+
+        let compiled_regexp = regex.compile("(?s:.).");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("\na"));
+        assert!(!regexp.full_match("a\n"));
+
+        let compiled_regexp = regex.compile(r"(?m:^\d)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(!regexp.is_anchored_start());
+        assert_eq!(regexp.global_search("1a\n2b\nc3"), vec!["1", "2"]);
+
+        let compiled_regexp = regex.compile(r"(?iu:é)(?i-u:\w)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("ÉA"));
+        assert!(!regexp.full_match("Éé"));
+
+        assert!(regex.compile("(?x:a)").is_err());
+        assert!(regex.compile("(?-:a)").is_err());
+    }
+
+    #[test]
+    fn regex_find_captures_agree() {
+        let regex = init();
+
+        let patterns = [
+            "abc",
+            "a|bc",
+            "(a|b)*c",
+            r"\d+",
+            "x?y*",
+            r"(\w+)@(\w+)",
+            "((a)|b)+",
+            r"\bfoo\b",
+            "^a|b$",
+            "(?:ab)+",
+        ];
+        let exprs = [
+            "", "abc", "xbc", "aabbc", "12 345", "yyy", "me@host", "abab", "a foo b", "bab", "é1abab",
+        ];
+
+        for pattern in patterns {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            for expr in exprs {
+                let found = regexp.find(expr).map(|m| String::from(m.as_str(expr)));
+                let captured = regexp
+                    .captures(expr)
+                    .and_then(|captures| captures[0].clone());
+                assert_eq!(found, captured, "`{pattern}` on {expr:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn regex_split() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\d");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.split_spans("a1b2c"), vec!["a", "b", "c"]);
+        assert_eq!(regexp.split("a1b2c"), vec!["a", "b", "c"]);
+        assert_eq!(regexp.split_spans("1a22"), vec!["", "a", "", ""]);
+        assert_eq!(regexp.split_spans("abc"), vec!["abc"]);
+        assert_eq!(regexp.split_spans(""), vec![""]);
+
+        for expr in ["a1b2c", "1a22", "abc", "", "é1è"] {
+            assert_eq!(regexp.split_spans(expr), expr.split(|c: char| c.is_ascii_digit()).collect::<Vec<_>>());
+            assert_eq!(regexp.split(expr), regexp.split_spans(expr));
+        }
+
+        let compiled_regexp = regex.compile(r",\s*");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().split_spans("a, b,c,  d"), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn regex_split_empty_segments() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(",");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        // Matches at either end, or next to each other, delimit empty segments, exactly as in `str::split`
+        assert_eq!(regexp.split(",a,b"), vec!["", "a", "b"]);
+        assert_eq!(regexp.split("a,b,"), vec!["a", "b", ""]);
+        assert_eq!(regexp.split("a,,b"), vec!["a", "", "b"]);
+        assert_eq!(regexp.split(","), vec!["", ""]);
+
+        for expr in [",a,b", "a,b,", "a,,b", ",", ",,", ",,a,,", ""] {
+            assert_eq!(regexp.split_spans(expr), expr.split(',').collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn regex_split_with_captures() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(\d)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.split("a1b2"), vec!["a", "1", "b", "2", ""]);
+        assert_eq!(regexp.split_spans("12"), vec!["", "1", "", "2", ""]);
+        assert_eq!(regexp.split_spans("abc"), vec!["abc"]);
+
+        let compiled_regexp = regex.compile(r"(,)|(;)\s*");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.split("a,b;  c"), vec!["a", ",", "", "b", "", ";", "c"]);
+
+        let compiled_regexp = regex.compile(r"_+|(x)(y)?");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().split_spans("a__bxc"), vec!["a", "", "", "b", "x", "", "c"]);
+    }
+
+    #[test]
+    fn regex_redundant_branches() {
+        let regex = init();
+
+        let redundant_branches = |expr: &str| {
+            let compiled_regexp = regex.compile(expr);
+            assert!(compiled_regexp.is_ok());
+            compiled_regexp.unwrap().redundant_branches()
+        };
+
+        assert_eq!(redundant_branches("(a|a)"), vec![1]);
+        assert_eq!(redundant_branches("(a|b)"), vec![]);
+        assert_eq!(redundant_branches("abc|abc"), vec![1]);
+        assert_eq!(redundant_branches("a|ab"), vec![]);
+        assert_eq!(redundant_branches("ab|a|bb|[ab]b"), vec![3]);
+        assert_eq!(redundant_branches("a*|b|a"), vec![2]);
+        assert_eq!(redundant_branches("a|b|[ab]"), vec![2]);
+        assert_eq!(redundant_branches("(a|b)c"), vec![]);
+        assert_eq!(redundant_branches("^a|a"), vec![]);
+    }
+
+    #[test]
+    fn regex_leading_bracket_in_group() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("[]a]");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("]"));
+        assert!(regexp.full_match("a"));
+        assert!(!regexp.full_match("b"));
+        assert!(!regexp.full_match("]a"));
+
+        let compiled_regexp = regex.compile("[^]]");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("a"));
+        assert!(regexp.full_match("["));
+        assert!(!regexp.full_match("]"));
+
+        let compiled_regexp = regex.compile("[]]+");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search("a]]b]"), vec!["]]", "]"]);
+
+        assert!(regex.compile("[]").is_err());
+        assert!(regex.compile("[^]").is_err());
+        assert!(regex.compile("[a]]").is_err());
+    }
+
+    #[test]
+    fn regex_empty_class() {
+        let regex = init();
+
+        for pattern in ["[]", "[]x", "a|[]"] {
+            let compiled_regexp = regex.compile(pattern);
+            assert_eq!(compiled_regexp.err().unwrap().kind(), &ErrorKind::EmptyCharacterGroup, "{pattern}");
+        }
+
+        let compiled_regexp = RegExpBuilder::new("[]x").allow_empty_class(true).build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        for expr in ["", "x", "]x", "[]x", "a x"] {
+            assert!(!regexp.is_match(expr), "{expr:?}");
+            assert!(regexp.find(expr).is_none(), "{expr:?}");
+            assert!(regexp.captures(expr).is_none(), "{expr:?}");
+        }
+
+        // Only the branch with an empty character group never matches
+        let compiled_regexp = RegExpBuilder::new("a|([])*b")
+            .allow_empty_class(true)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search("abc"), vec!["a", "b"]);
+
+        // A leading `]` closed by another `]` is still a literal member
+        let compiled_regexp = RegExpBuilder::new("[]x]").allow_empty_class(true).build();
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("]"));
+    }
+
+    #[test]
+    fn regex_replace_all_non_overlapping() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("aa");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.find_overlapping("aaaa"), vec![Match::new(0, 2), Match::new(1, 3), Match::new(2, 4)]);
+        assert_eq!(regexp.replace_all("aaaa", "X"), "XX");
+        assert_eq!(regexp.replace_all("aaaaa", "X"), "XXa");
+        assert_eq!(regexp.replacen("aaaa", 1, "X"), "Xaa");
+
+        let compiled_regexp = regex.compile(r"\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.find_overlapping("a12"), vec![Match::new(1, 3), Match::new(2, 3)]);
+        assert_eq!(regexp.replace_all("a12", "X"), "aX");
+    }
+
+    #[test]
+    fn regex_unparsed_suffix() {
+        let regex = init();
+
+        assert_eq!(regex.compile("abc)").err(), Some(Error::new(ErrorKind::UnexpectedChar(')', 3))));
+        assert_eq!(regex.compile("a]b").err(), Some(Error::new(ErrorKind::UnexpectedChar(']', 1))));
+        assert_eq!(regex.compile("(ab|c))d").err(), Some(Error::new(ErrorKind::UnexpectedChar(')', 6))));
+        assert_eq!(regex.compile("*a").err(), Some(Error::new(ErrorKind::UnexpectedChar('*', 0))));
+        assert_eq!(regex.compile("").err(), Some(Error::new(ErrorKind::UnexpectedEnd)));
+        assert!(regex.compile("(ab|c)d").is_ok());
+
+        // A group still open at the end is reported there, rather than where parsing stopped
+        assert_eq!(regex.compile("(ab").err(), Some(Error::new(ErrorKind::UnclosedGroup(0))));
+        assert_eq!(regex.compile("a(?:b|c").err(), Some(Error::new(ErrorKind::UnclosedGroup(1))));
+        assert_eq!(regex.compile("(a)(").err(), Some(Error::new(ErrorKind::UnclosedGroup(3))));
+        assert_eq!(regex.compile("é(a(b").err(), Some(Error::new(ErrorKind::UnclosedGroup(4))));
+        assert_eq!(
+            regex.compile("x((a)").err().map(|e| e.msg()),
+            Some(String::from("Missing `)` at end of expression, to close the group opened at position 1"))
+        );
+        assert_eq!(regex.compile("(a))(").err(), Some(Error::new(ErrorKind::UnexpectedChar(')', 3))));
+    }
+
+    #[test]
+    fn regex_capture_spans() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(\w+)@(\w+)(\.com)?");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(
+            regexp.capture_spans("to: me@host.org"),
+            Some(vec![Some(Match::new(4, 11)), Some(Match::new(4, 6)), Some(Match::new(7, 11)), None])
+        );
+        assert_eq!(regexp.capture_spans("nobody"), None);
+
+        for expr in ["to: me@host.org", "me@host.com", "é@è", "nobody"] {
+            let sliced = regexp.capture_spans(expr).map(|spans| {
+                spans
+                    .into_iter()
+                    .map(|span| span.map(|m| String::from(m.as_str(expr))))
+                    .collect::<Vec<_>>()
+            });
+            assert_eq!(sliced, regexp.captures(expr));
+        }
+    }
+
+    #[test]
+    fn regex_literal_braces() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("a{b}");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("a{b}"));
+        assert!(!regexp.full_match("ab"));
+
+        let compiled_regexp = regex.compile("a{2}");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("aa"));
+        assert!(!regexp.full_match("a{2}"));
+
+        let compiled_regexp = regex.compile(r"a{,2}|\{2}");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("a{,2}"));
+        assert!(regexp.full_match("{2}"));
+
+        let compiled_regexp = regex.compile("x{");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("x{"));
+
+        assert_eq!(regex.compile("{2}").err(), Some(Error::new(ErrorKind::UnexpectedChar('{', 0))));
+    }
+
+    #[test]
+    fn regex_compile_many() {
+        let regex = init();
+
+        let patterns = ["a+b", "a)", r"\d{2}", "(a|b", "[]x]"];
+        let compiled_regexps = regex.compile_many(&patterns);
+        assert_eq!(compiled_regexps.len(), patterns.len());
+        assert_eq!(
+            compiled_regexps
+                .iter()
+                .map(Result::is_ok)
+                .collect::<Vec<_>>(),
+            vec![true, false, true, false, true]
+        );
+        assert_eq!(compiled_regexps[1].as_ref().err(), Some(&Error::new(ErrorKind::UnexpectedChar(')', 1))));
+        assert!(compiled_regexps[0]
+            .as_ref()
+            .is_ok_and(|regexp| regexp.full_match("aab")));
+        assert!(compiled_regexps[2]
+            .as_ref()
+            .is_ok_and(|regexp| regexp.full_match("42")));
+
+        let results = RegExp::compile_many_parallel(&patterns, |regexp| regexp.is_match("x42aab"));
+        assert_eq!(
+            results,
+            vec![
+                Ok(true),
+                Err(Error::new(ErrorKind::UnexpectedChar(')', 1))),
+                Ok(true),
+                Err(Error::new(ErrorKind::UnclosedGroup(0))),
+                Ok(true)
+            ]
+        );
+        assert!(RegExp::compile_many_parallel(&[], |regexp| regexp.group_count()).is_empty());
+    }
+
+    #[test]
+    fn regex_inline_flags() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("(?i)ABC(?-i)def");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("abcdef"));
+        assert!(regexp.full_match("aBcdef"));
+        assert!(!regexp.full_match("abcDEF"));
+        assert!(!regexp.full_match("abcDef"));
+
+        let compiled_regexp = regex.compile("(?im-s)^a.$");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.global_search("x\nAb\na\n"), vec!["Ab"]);
+
+        let compiled_regexp = regex.compile("((?i)a)a|b");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("Aa"));
+        assert!(!regexp.full_match("AA"));
+        assert!(!regexp.full_match("B"));
+
+        let compiled_regexp = regex.compile("a(?i)b|c");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("aB"));
+        assert!(regexp.full_match("C"));
+        assert!(!regexp.full_match("AB"));
+
+        let compiled_regexp = RegExpBuilder::new("a(?-s).").dot_all(true).build();
+        assert!(compiled_regexp.is_ok());
+        assert!(!compiled_regexp.unwrap().full_match("a\n"));
+
+        let compiled_regexp = regex.compile("b|(?m)^a");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search("x\na"), vec!["a"]);
+
+        assert!(regex.compile("(?)a").is_err());
+        assert!(regex.compile("(?x)a").is_err());
+    }
+
+    #[test]
+    fn regex_predicate_kinds() {
+        let regex = init();
+
+        let predicate_kinds = |regexp: Result<RegExp, Error>| {
+            assert!(regexp.is_ok());
+            regexp.unwrap().automata.predicate_kinds()
+        };
+        let not = |kind| PredicateKind::Not(Box::new(kind));
+
+        assert_eq!(
+            predicate_kinds(regex.compile(r"a\d\w\s.")),
+            vec![
+                PredicateKind::Char('a'),
+                PredicateKind::Digit,
+                PredicateKind::Word,
+                PredicateKind::Whitespace,
+                not(PredicateKind::Char('\n'))
+            ]
+        );
+        assert_eq!(
+            predicate_kinds(regex.compile(r"[^a-f\D]")),
+            vec![not(PredicateKind::Union(vec![
+                PredicateKind::Range('a', 'f'),
+                not(PredicateKind::Digit)
+            ]))]
+        );
+        assert_eq!(
+            predicate_kinds(RegExpBuilder::new("[a-c]").case_insensitive(true).build()),
+            vec![PredicateKind::Union(vec![PredicateKind::Union(vec![
+                PredicateKind::Range('a', 'c'),
+                PredicateKind::Range('A', 'C')
+            ])])]
+        );
+        assert_eq!(predicate_kinds(RegExpBuilder::new(r"\d").unicode(true).build()), vec![PredicateKind::Custom]);
+        assert_eq!(predicate_kinds(RegExpBuilder::new(".").dot_all(true).build()), vec![PredicateKind::Any]);
+
+        assert_eq!(
+            not(PredicateKind::Union(vec![PredicateKind::Range('a', 'f'), PredicateKind::Digit])).ranges(),
+            Some(vec![('\0', '/'), (':', '`'), ('g', char::MAX)])
+        );
+        assert_eq!(PredicateKind::Custom.ranges(), None);
+
+        // Symbolic and probed partitions of the alphabet yield the same automaton
+        let symbolic = regex.compile("[a-f0-9]+x").unwrap().minimize();
+        let probed = RegExpBuilder::new("[a-f0-9]+x")
+            .word_chars(|c| c.is_ascii_alphanumeric())
+            .build()
+            .unwrap()
+            .minimize();
+        assert!(symbolic.is_some());
+        assert_eq!(symbolic, probed);
+    }
+
+    #[test]
+    fn regex_trailing_backslash() {
+        let regex = init();
+
+        let trailing_backslash_error = || Some(Error::new(ErrorKind::TrailingBackslash(3)));
+        assert_eq!(regex.compile("abc\\").err(), trailing_backslash_error());
+        assert_eq!(regex.compile("(a|\\").err(), trailing_backslash_error());
+        assert_eq!(regex.compile("[a\\\\\\").err(), Some(Error::new(ErrorKind::TrailingBackslash(4))));
+        assert_eq!(
+            regex.compile("abc\\").err().unwrap().to_string(),
+            "Trailing backslash: nothing to escape at position 3"
+        );
+
+        let compiled_regexp = regex.compile("abc\\\\");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("abc\\"));
+    }
+
+    #[test]
+    fn regex_empty_match_vs_no_match() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("a*");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.greedy_search("b"), Some(String::new()));
+        assert_eq!(regexp.find("b"), Some(Match::new(0, 0)));
+        assert!(regexp.is_match("b"));
+        assert!(regexp.is_match(""));
+
+        let compiled_regexp = regex.compile("a+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.greedy_search("b"), None);
+        assert_eq!(regexp.find("b"), None);
+        assert!(!regexp.is_match("b"));
+        assert!(regexp.is_match("ba"));
+
+        for (pattern, expr) in [("a*", "b"), ("a+", "b"), ("x?$", "y"), ("^x", "yx")] {
+            let regexp = regex.compile(pattern).unwrap();
+            assert_eq!(regexp.is_match(expr), regexp.greedy_search(expr).is_some());
+        }
+    }
+
+    #[test]
+    fn regex_recursion() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\((?R)?\)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("()"));
+        assert!(regexp.full_match("((()))"));
+        assert!(!regexp.full_match("(()"));
+        assert!(!regexp.full_match("())"));
+        assert!(!regexp.full_match("()()"));
+        assert_eq!(regexp.global_search("x((())) ()("), vec!["((()))", "()"]);
+        assert_eq!(regexp.find("(()"), Some(Match::new(1, 3)));
+
+        let compiled_regexp = regex.compile(r"<(\((?1)*\))>");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("<(()(()))>"));
+        assert!(!regexp.full_match("<(()(())>"));
+        assert_eq!(regexp.captures("<(())()>"), None);
+        assert_eq!(
+            regexp.captures("a<(()())>"),
+            Some(vec![Some(String::from("<(()())>")), Some(String::from("(()())"))])
+        );
+
+        let compiled_regexp = regex.compile("(a|bc)(?1)(?1)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("abca"));
+        assert!(regexp.full_match("bcbcbc"));
+        assert!(!regexp.full_match("ab"));
+        assert_eq!(regexp.captures("bcaa"), Some(vec![Some(String::from("bcaa")), Some(String::from("bc"))]));
+
+        // A recursion which would never consume anything fails rather than looping forever
+        let compiled_regexp = regex.compile("(?R)?a");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("a"));
+        assert!(regexp.full_match("aa"));
+
+        assert_eq!(regex.compile("(a)(?2)").err(), Some(Error::new(ErrorKind::UndefinedRecursion(2))));
+    }
+
+    #[test]
+    fn regex_counters() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("(a|b)*c");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(bench_support::counters(&regexp), bench_support::Counters::default());
+
+        let (matched, counters) = bench_support::measure(&regexp, |regexp| regexp.full_match("ababc"));
+        assert!(matched);
+        assert!(counters.transitions > 0);
+        assert_eq!(counters.attempts, 6);
+
+        let (matches, counters) = bench_support::measure(&regexp, |regexp| regexp.find_iter("xxc").count());
+        assert_eq!(matches, 1);
+        assert!(counters.transitions > 0);
+        assert!(counters.attempts >= 3);
+        assert_eq!(bench_support::counters(&regexp), counters);
+
+        regexp.reset_counters();
+        assert_eq!(bench_support::counters(&regexp), bench_support::Counters::default());
+    }
+
+    #[test]
+    fn regex_nested_captures() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("((a)(b))");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 3);
+        assert_eq!(
+            regexp.captures("ab"),
+            Some(vec![
+                Some(String::from("ab")),
+                Some(String::from("ab")),
+                Some(String::from("a")),
+                Some(String::from("b"))
+            ])
+        );
+
+        let compiled_regexp = regex.compile("(x(y(z))|(w))v");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let captures = regexp.captures_at("xyzv", 0);
+        assert!(captures.is_some());
+        let captures = captures.unwrap();
+
+        assert_eq!((1..=4).map(|i| captures.get(i)).collect::<Vec<_>>(), vec![Some("xyz"), Some("yz"), Some("z"), None]);
+    }
+
+    #[test]
+    fn regex_literal() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("abc");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        let nfa_regexp = RegExpBuilder::new("abc")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(regexp.as_literal(), Some(String::from("abc")));
+        assert_eq!(nfa_regexp.as_literal(), None);
+
+        for expr in ["abc", "xabcabcabx", "ab", "", "éabcè", "aabcc"] {
+            assert_eq!(regexp.find(expr), nfa_regexp.find(expr));
+            assert_eq!(regexp.is_match(expr), nfa_regexp.is_match(expr));
+            assert_eq!(regexp.full_match(expr), nfa_regexp.full_match(expr));
+            assert_eq!(regexp.global_search(expr), nfa_regexp.global_search(expr));
+            assert_eq!(regexp.find_iter(expr).collect::<Vec<_>>(), nfa_regexp.find_iter(expr).collect::<Vec<_>>());
+        }
+        assert_eq!(regexp.find("xabc"), Some(Match::new(1, 4)));
+
+        // The literal path never runs the automata
+        assert_eq!(regexp.transition_count(), 0);
+        assert_eq!(regexp.attempt_count(), 0);
+        assert!(nfa_regexp.transition_count() > 0);
+
+        let literal = |expr: &str| regex.compile(expr).unwrap().as_literal();
+        assert_eq!(literal(r"a\.b\*"), Some(String::from("a.b*")));
+        assert_eq!(literal("ab*"), None);
+        assert_eq!(literal("a|b"), None);
+        assert_eq!(literal("(ab)"), None);
+        assert_eq!(literal("^ab"), None);
+        assert_eq!(literal("a.b"), None);
+    }
+
+    #[test]
+    fn regex_required_prefix() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"foo\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        let scanning_regexp = regex.compile(r"(foo)\d+").unwrap();
+
+        let text = format!("{}foo12 fo3 foofoo4{}foo", "x".repeat(100), "y".repeat(100));
+        for expr in [text.as_str(), "", "foo", "foo1", "ffoo1foo", "éfoo1è", "foofoo12foo3"] {
+            assert_eq!(regexp.find_iter(expr).collect::<Vec<_>>(), scanning_regexp.find_iter(expr).collect::<Vec<_>>());
+            assert_eq!(regexp.global_search(expr), scanning_regexp.global_search(expr));
+            assert_eq!(regexp.greedy_search(expr), scanning_regexp.greedy_search(expr));
+            assert_eq!(regexp.full_match(expr), scanning_regexp.full_match(expr));
+        }
+        assert_eq!(regexp.global_search(&text), vec!["foo12", "foo4"]);
+
+        // Matching is only attempted where the prefix occurs
+        regexp.reset_counters();
+        scanning_regexp.reset_counters();
+        assert_eq!(regexp.find_iter(&text).count(), 2);
+        assert_eq!(scanning_regexp.find_iter(&text).count(), 2);
+        assert_eq!(regexp.attempt_count(), 4);
+        assert!(scanning_regexp.attempt_count() > 200);
+
+        // The prefix is not required of case insensitive matches
+        let regexp = RegExpBuilder::new("ab")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        assert_eq!(regexp.global_search("AB aB"), vec!["AB", "aB"]);
+    }
+
+    #[test]
+    fn regex_negated_class_captures() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("([^,]+),([^,]+)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(
+            regexp.captures("hello,world"),
+            Some(vec![
+                Some(String::from("hello,world")),
+                Some(String::from("hello")),
+                Some(String::from("world"))
+            ])
+        );
+        assert_eq!(
+            regexp.captures("a,b,c"),
+            Some(vec![Some(String::from("a,b")), Some(String::from("a")), Some(String::from("b"))])
+        );
+        assert_eq!(regexp.captures(",,"), None);
+
+        let compiled_regexp = regex.compile("([^,]*),([^,]*)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.captures(",,"), Some(vec![Some(String::from(",")), Some(String::new()), Some(String::new())]));
+        assert_eq!(
+            regexp.captures("x,"),
+            Some(vec![Some(String::from("x,")), Some(String::from("x")), Some(String::new())])
+        );
+    }
+
+    #[test]
+    fn regex_error_formatter() {
+        struct FrenchFormatter;
+
+        impl ErrorFormatter for FrenchFormatter {
+            fn format(&self, kind: &ErrorKind) -> String {
+                match kind {
+                    ErrorKind::UnexpectedChar(c, position) => format!("`{c}` inattendu à la position {position}"),
+                    _ => DefaultErrorFormatter.format(kind),
+                }
+            }
+        }
+
+        let regex = init();
+
+        let error = regex.compile("a)").err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::UnexpectedChar(')', 1));
+        assert_eq!(error.to_string(), "Unexpected `)` at position 1");
+
+        set_error_formatter(FrenchFormatter);
+        assert_eq!(error.to_string(), "`)` inattendu à la position 1");
+        assert_eq!(format!("{error:?}"), "RegexError { Error Message: \"`)` inattendu à la position 1\" }");
+        assert_eq!(regex.compile("").err().unwrap().to_string(), "Unexpected end of expression");
+
+        // The formatter is only set for the current thread
+        assert_eq!(thread::spawn(move || error.to_string()).join().unwrap(), "Unexpected `)` at position 1");
+
+        reset_error_formatter();
+        assert_eq!(regex.compile("a)").err().unwrap().to_string(), "Unexpected `)` at position 1");
+    }
+
+    #[test]
+    fn regex_captures_iter_lazy() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(?<key>\w+)\s*=\s*(?<value>\w*)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let config = "name = rustregex\nversion=1\n# comment\nempty =\n";
+        let pairs: Vec<_> = regexp
+            .captures_iter_lazy(config)
+            .map(|captures| (captures.name("key"), captures.name("value")))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (Some("name"), Some("rustregex")),
+                (Some("version"), Some("1")),
+                (Some("empty"), Some(""))
+            ]
+        );
+
+        let mut captures_iter = regexp.captures_iter_lazy(config);
+        let captures = captures_iter.next().unwrap();
+        assert_eq!(captures.get(0), Some("name = rustregex"));
+        assert_eq!(captures.get(1), captures.name("key"));
+        assert_eq!(captures.name("missing"), None);
+        assert_eq!(captures_iter.count(), 2);
+
+        assert_eq!(regexp.captures_iter_lazy("no pairs").count(), 0);
+
+        // Capturing groups which do not participate have no substring
+        let compiled_regexp = regex.compile("(?<a>a)|(?<b>b)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        let names: Vec<_> = regexp
+            .captures_iter_lazy("ba")
+            .map(|captures| (captures.name("a"), captures.name("b")))
+            .collect();
+        assert_eq!(names, vec![(None, Some("b")), (Some("a"), None)]);
+
+        // Every match located by find_iter is replayed, including empty matches and those requiring backtracking
+        for (pattern, expr) in [
+            ("a*", "baab"),
+            (r"(a|ab)(c|bcd)\1?", "abcdabcd a"),
+            ("(b??)*", "bab"),
+            (r"(\w)\1", "aabbc"),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok(), "{pattern}");
+            let regexp = compiled_regexp.unwrap();
+            let spans: Vec<_> = regexp
+                .captures_iter_lazy(expr)
+                .map(|captures| captures.get(0))
+                .collect();
+            let matches: Vec<_> = regexp
+                .find_iter(expr)
+                .map(|m| Some(m.as_str(expr)))
+                .collect();
+            assert_eq!(spans, matches, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn regex_shortest_match_at() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("a+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.shortest_match_at("aaa", 0), Some(1));
+        assert_eq!(regexp.shortest_match_at("aaa", 2), Some(3));
+        assert_eq!(regexp.shortest_match_at("aaa", 3), None);
+        assert_eq!(regexp.shortest_match_at("baa", 0), None);
+        assert_eq!(regexp.shortest_match_at("baa", 1), Some(2));
+
+        let compiled_regexp = regex.compile(".+b");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.shortest_match_at("xabab", 0), Some(3));
+        assert_eq!(regexp.shortest_match_at("xabab", 2), Some(5));
+        assert_eq!(regexp.shortest_match_at("éb", 0), Some(3));
+        assert_eq!(regexp.shortest_match_at("éxb", 0), Some(4));
+        assert_eq!(regexp.shortest_match_at("éxb", 1), None);
+
+        // Empty matches are the shortest of all
+        let compiled_regexp = regex.compile("a*");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().shortest_match_at("aaa", 1), Some(1));
+
+        // The backtracking engine agrees
+        let compiled_regexp = regex.compile("(a(?1)?b)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert_eq!(regexp.shortest_match_at("aabbab", 0), Some(4));
+        assert_eq!(regexp.shortest_match_at("aabbab", 1), Some(3));
+        assert_eq!(regexp.shortest_match_at("aabbab", 2), None);
+    }
+
+    #[test]
+    fn regex_text_anchors() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"abc\Z");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_match("abc"));
+        assert!(regexp.is_match("abc\n"));
+        assert!(regexp.is_match("xabc\n"));
+        assert!(!regexp.is_match("abc\n\n"));
+        assert!(!regexp.is_match("abc\nabd"));
+        assert_eq!(regexp.find("abc\n"), Some(Match::new(0, 3)));
+
+        let compiled_regexp = regex.compile(r"abc\z");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_match("abc"));
+        assert!(!regexp.is_match("abc\n"));
+
+        let compiled_regexp = regex.compile(r"\Aabc");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_anchored_start());
+        assert!(regexp.is_match("abcd"));
+        assert!(!regexp.is_match("xabc"));
+
+        // Unlike `^` and `$`, they are not affected by multi-line mode
+        let regexp = RegExpBuilder::new(r"\Aa$")
+            .multi_line(true)
+            .build()
+            .unwrap();
+        assert_eq!(regexp.global_search("a\na\n"), vec!["a"]);
+        let regexp = RegExpBuilder::new(r"^a\z")
+            .multi_line(true)
+            .build()
+            .unwrap();
+        assert_eq!(regexp.global_search("a\na"), vec!["a"]);
+        assert_eq!(regexp.find("a\na"), Some(Match::new(2, 3)));
+        let regexp = RegExpBuilder::new(r"^a\Z")
+            .multi_line(true)
+            .build()
+            .unwrap();
+        assert_eq!(regexp.find("a\na\n"), Some(Match::new(2, 3)));
+
+        // The backtracking engine agrees
+        let compiled_regexp = regex.compile(r"(a(?1)?b)\Z");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert_eq!(regexp.find("aabb\n"), Some(Match::new(0, 4)));
+        assert_eq!(regexp.find("aabb\nab"), Some(Match::new(5, 7)));
+    }
+
+    #[test]
+    fn regex_non_capturing_group_indices() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("(?:x)(a)(?:y)(b)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 2);
+        assert_eq!(regexp.group_names(), vec![None, None, None]);
+        assert_eq!(
+            regexp.captures("xayb"),
+            Some(vec![Some(String::from("xayb")), Some(String::from("a")), Some(String::from("b"))])
+        );
+
+        let compiled_regexp = regex.compile("(?:a)(b)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 1);
+        assert_eq!(regexp.captures("ab"), Some(vec![Some(String::from("ab")), Some(String::from("b"))]));
+
+        let compiled_regexp = regex.compile("(?:ab)+c");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 0);
+        assert_eq!(regexp.global_search("abababc abc ac"), vec!["abababc", "abc"]);
+
+        let compiled_regexp = regex.compile("(?:(?<first>a)(?:b(c))*)(?<last>d)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.group_count(), 3);
+        assert_eq!(regexp.group_names(), vec![None, Some(String::from("first")), None, Some(String::from("last"))]);
+
+        let captures = regexp.captures_at("abcbcd", 0).unwrap();
+        assert_eq!(captures.len(), regexp.group_count() + 1);
+        assert_eq!(
+            (0..captures.len())
+                .map(|i| captures.get(i))
+                .collect::<Vec<_>>(),
+            vec![Some("abcbcd"), Some("a"), Some("c"), Some("d")]
+        );
+        assert_eq!(captures.name("first"), captures.get(1));
+        assert_eq!(captures.name("last"), captures.get(3));
+        assert_eq!(captures.get(4), None);
+    }
+
+    #[test]
+    fn regex_malformed_numbers() {
+        let regex = init();
+
+        // Numbers of repetitions, recursions and backreferences must fit in a u32
+        let too_large = |number: &str, position| Some(Error::new(ErrorKind::NumberTooLarge(String::from(number), position)));
+        assert_eq!(regex.compile("a{99999999999}").err(), too_large("99999999999", 2));
+        assert_eq!(regex.compile("a{1,4294967296}").err(), too_large("4294967296", 4));
+        assert_eq!(regex.compile("a{99999999999,}").err(), too_large("99999999999", 2));
+        assert_eq!(regex.compile("(?99999999999)").err(), too_large("99999999999", 2));
+        assert_eq!(regex.compile(r"(a)\99999999999").err(), too_large("99999999999", 4));
+        assert_eq!(
+            regex.compile("a{99999999999}").err().map(|e| e.msg()),
+            Some(String::from("Number `99999999999` at position 2 is too large, as it does not fit in 32 bits"))
+        );
+
+        // A `{` which does not start a repetition is literal, whatever follows it
+        let compiled_regexp = regex.compile("a{99999999999");
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("a{99999999999"));
+        assert!(matches!(
+            RegExpBuilder::new("a{4294967295}")
+                .max_states(100)
+                .build()
+                .err()
+                .unwrap()
+                .kind(),
+            ErrorKind::ExpansionTooLarge { .. }
+        ));
+
+        let compiled_regexp = regex.compile("ba{0}c");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert!(regexp.full_match("bc"));
+        assert!(!regexp.full_match("bac"));
+
+        let compiled_regexp = regex.compile("(a{0,0})*");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search("aa"), vec!["", "", ""]);
+    }
+
+    #[test]
+    fn regex_anchor_in_group_captures() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(^\w+)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.captures("abc def"), Some(vec![Some(String::from("abc")), Some(String::from("abc"))]));
+        assert_eq!(regexp.captures(" abc def"), None);
+        assert!(regexp.captures_at("abc def", 4).is_none());
+        assert_eq!(regexp.find_iter("abc def").count(), 1);
+
+        let compiled_regexp = regex.compile(r"x(^\d+)|(\d+$)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        // The group spans exclude the zero-width anchors
+        assert_eq!(regexp.capture_spans("12 34"), Some(vec![Some(Match::new(3, 5)), None, Some(Match::new(3, 5))]));
+        assert_eq!(regexp.capture_spans("x12 a"), None);
+
+        let compiled_regexp = regex.compile(r"(\b\w+\b)");
+        assert!(compiled_regexp.is_ok());
+        let captures = compiled_regexp.unwrap().captures_at("ab cd", 3).unwrap();
+        assert_eq!(captures.get_match(1), Some(Match::new(3, 5)));
+    }
+
+    #[test]
+    fn regex_replace_preview() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(?<key>\w+)=(\d+)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let haystack = "a=1, bc=23, d=";
+        assert_eq!(
+            regexp.replace_preview(haystack, "$2:${key}$$"),
+            vec![
+                (Match::new(0, 3), String::from("1:a$")),
+                (Match::new(5, 10), String::from("23:bc$"))
+            ]
+        );
+        assert_eq!(regexp.replace_preview("none", "$1"), vec![]);
+
+        // Splicing the previewed replacements gives the result of replace_all
+        for (haystack, rep) in [
+            (haystack, "$2:${key}$$"),
+            ("x=1y=2", "[$0]"),
+            ("", "$1"),
+            ("a=1", "$3$missing"),
+        ] {
+            let mut spliced = String::new();
+            let mut last = 0;
+            for (m, replacement) in regexp.replace_preview(haystack, rep) {
+                spliced.push_str(&haystack[last..m.start()]);
+                spliced.push_str(&replacement);
+                last = m.end();
+            }
+            spliced.push_str(&haystack[last..]);
+
+            assert_eq!(spliced, regexp.replace_all(haystack, rep));
+        }
+    }
+
+    #[test]
+    fn regex_find_cached() {
+        let regex = init();
+
+        let regexps: Vec<RegExp> = ["(a|b)*c", r"\d+$", "x?", r"\bfoo\d", "abc", "^a+"]
+            .iter()
+            .map(|expr| regex.compile(expr).unwrap())
+            .collect();
+
+        // A single cache is shared by every regular expression and expression
+        let mut cache = SearchCache::default();
+        for regexp in &regexps {
+            for expr in ["", "abac", "x12 34", "foo1 foo", "aabc", "é", "bbbbc"] {
+                assert_eq!(regexp.find_cached(expr, &mut cache), regexp.find(expr));
+            }
+        }
+
+        let mut regexp = regex.compile("a+").unwrap();
+        assert_eq!(regexp.find_cached("baab", &mut cache), Some(Match::new(1, 3)));
+        regexp.set_max_input_len(3);
+        assert_eq!(regexp.find_cached("baab", &mut cache), None);
+    }
+
+    #[test]
+    fn regex_anchored_end() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"\d+$");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.is_anchored_end());
+        assert_eq!(regexp.find("abc 123").map(|m| m.range()), Some(4..7));
+        assert_eq!(regexp.global_search("1 23 456"), vec![String::from("456")]);
+        assert_eq!(regexp.find("123 abc").map(|m| m.range()), None);
+
+        for (pattern, anchored_end) in [
+            ("a|b$", false),
+            ("a$b", false),
+            ("(?m)a$", false),
+            ("(a$)?", false),
+            ("(a|b)$", true),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            assert_eq!(compiled_regexp.unwrap().is_anchored_end(), anchored_end);
+        }
+
+        // Matching backwards from the end finds the same matches as attempting matching from every position
+        let exprs = [
+            "", "a", "123", "abc 123", "12a", "foo", "xbaar", "x xx", "aé", "éé", "ab\nAB", "aaa", "a\n",
+        ];
+        for pattern in [
+            r"\d+$",
+            "(foo|ba+r)$",
+            r"\bx+\z",
+            "^a*$",
+            "(?i)ab$",
+            "é+$",
+            "$",
+            "a*$",
+            r"(a$|b\z)",
+            r"\w\B.$",
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+            assert!(regexp.is_anchored_end());
+
+            let forward = regexp
+                .regex
+                .compile(&regexp.flags)
+                .unwrap()
+                .word_chars(regexp.flags.word_char_predicate());
+            for expr in exprs {
+                assert_eq!(regexp.automata.search(expr), forward.search(expr), "{pattern} on {expr:?}");
+                for from in 0..=expr.len() {
+                    if expr.is_char_boundary(from) {
+                        assert_eq!(regexp.automata.find_at(expr, from), forward.find_at(expr, from), "{pattern} on {expr:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regex_captures_without_groups() {
+        let regex = init();
+
+        for (pattern, expr) in [(r"a\w+", "abc"), (r"a\w+", "xx abc yy"), ("abc", "xx abc"), ("a*", "b")] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+            let matched = regexp.find(expr).map(|m| String::from(m.as_str(expr)));
+
+            assert_eq!(regexp.group_count(), 0);
+            assert_eq!(regexp.captures(expr), Some(vec![matched.clone()]));
+
+            let captures = regexp.captures_iter_lazy(expr).next();
+            assert!(captures.is_some());
+            let captures = captures.unwrap();
+            assert_eq!(captures.len(), 1);
+            assert_eq!(captures.get(0).map(String::from), matched);
+            assert_eq!(captures.get(1), None);
+        }
+
+        let compiled_regexp = regex.compile(r"a\w+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert_eq!(regexp.captures("xyz"), None);
+        assert!(regexp.captures_at("xyz", 0).is_none());
+    }
+
+    #[test]
+    fn regex_is_match_alternation_short_circuit() {
+        let regex = init();
+
+        // Every branch but the first keeps going for as long as the expression does
+        let branches: Vec<String> = (0..50).map(|i| format!("x{}{i}", "y".repeat(20))).collect();
+        let compiled_regexp = regex.compile(&format!("x|{}", branches.join("|")));
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        let expr = format!("x{}49", "y".repeat(20));
+
+        regexp.reset_counters();
+        assert!(regexp.is_match(&expr));
+        let is_match_transitions = regexp.transition_count();
+
+        regexp.reset_counters();
+        assert_eq!(regexp.find(&expr).map(|m| m.range()), Some(0..expr.len()));
+        let find_transitions = regexp.transition_count();
+
+        // Only the first character is read, by the first state of each branch
+        assert_eq!(is_match_transitions, 51);
+        assert!(find_transitions > 20 * 50);
+
+        for (pattern, exprs) in [
+            ("a|bc|cde", vec!["", "a", "xcd", "xcde", "b"]),
+            ("^(ab|b)c", vec!["abc", "bc", "xbc", "ab"]),
+            (r"\bfoo\b|bar", vec!["foo", "xfoo", "xfoo bar", "foox"]),
+            ("a*", vec!["", "b"]),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            for expr in exprs {
+                assert_eq!(regexp.is_match(expr), regexp.find(expr).is_some(), "{pattern} on {expr:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn regex_captures_expand() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(\d+)\-(?<word>\w+)(x)?");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let captures = regexp.captures_at("123-abc", 0);
+        assert!(captures.is_some());
+        let captures = captures.unwrap();
+
+        assert_eq!(captures.expand("$2-$1"), "abc-123");
+        assert_eq!(captures.expand("${word}:${1}0"), "abc:1230");
+        assert_eq!(captures.expand("$0 costs $$5"), "123-abc costs $5");
+        assert_eq!(captures.expand("[$3][$9][$missing]"), "[][][]");
+        assert_eq!(captures.expand("no references"), "no references");
+    }
+
+    #[test]
+    fn regex_class_range_endpoint() {
+        let regex = init();
+
+        let endpoint = |class: &str, position| Some(Error::new(ErrorKind::ClassRangeEndpoint(String::from(class), position)));
+        assert_eq!(regex.compile(r"[\d-z]").err(), endpoint(r"\d", 1));
+        assert_eq!(regex.compile(r"[a-\d]").err(), endpoint(r"\d", 3));
+        assert_eq!(regex.compile(r"x[^\W-z]").err(), endpoint(r"\W", 3));
+        assert_eq!(regex.compile(r"[\\-\d]").err(), endpoint(r"\d", 4));
+        assert_eq!(regex.compile("[[:alpha:]-z]").err(), endpoint("[:alpha:]", 1));
+        assert_eq!(regex.compile("(é[a-[:^digit:]])").err(), endpoint("[:^digit:]", 6));
+        assert!(regex.compile(r"[\d\-z]").is_ok());
+        assert_eq!(
+            regex.compile(r"[a-\s]").err().map(|e| e.msg()),
+            Some(String::from("Character class `\\s` cannot be a range endpoint at position 3"))
+        );
+
+        // A range must not be out of order, but may consist of a single character
+        assert_eq!(regex.compile("[z-a]").err(), Some(Error::new(ErrorKind::InvertedCharacterRange('z', 'a', 1))));
+        assert_eq!(regex.compile("x[a-c9-0]").err(), Some(Error::new(ErrorKind::InvertedCharacterRange('9', '0', 5))));
+        assert_eq!(regex.compile("é[^b-a]").err(), Some(Error::new(ErrorKind::InvertedCharacterRange('b', 'a', 4))));
+        assert_eq!(
+            regex.compile("[z-a]").err().map(|e| e.msg()),
+            Some(String::from("Character range `z-a` at position 1 is out of order, as `z` comes after `a`"))
+        );
+        assert!(regex
+            .compile("[a-a]")
+            .is_ok_and(|regexp| regexp.full_match("a")));
+        assert!(regex
+            .compile("[a-z]+")
+            .is_ok_and(|regexp| regexp.full_match("range")));
+        assert!(regex
+            .compile("[0-9]+")
+            .is_ok_and(|regexp| regexp.full_match("0123456789") && !regexp.full_match("a")));
+
+        // A `-` next to a character class is escaped to be a literal
+        let compiled_regexp = regex.compile(r"[\d\-z]+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert!(regexp.full_match("1-z"));
+        assert!(!regexp.full_match("a"));
+
+        // Other parsing errors are reported as before
+        assert_eq!(regex.compile(r"[\d\-z]-").err(), Some(Error::new(ErrorKind::UnexpectedChar('-', 7))));
+        assert_eq!(regex.compile(r"\d-z").err(), Some(Error::new(ErrorKind::UnexpectedChar('-', 2))));
+    }
+
+    #[test]
+    fn regex_match_semantics() {
+        let compile = |pattern: &str, semantics: MatchSemantics| {
+            let compiled_regexp = RegExpBuilder::new(pattern).semantics(semantics).build();
+            assert!(compiled_regexp.is_ok());
+            compiled_regexp.unwrap()
+        };
+        let captures = |captures: &[&str]| {
+            Some(
+                captures
+                    .iter()
+                    .map(|c| Some(String::from(*c)))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let perl = compile("a|ab", MatchSemantics::PerlLeftmostFirst);
+        let posix = compile("a|ab", MatchSemantics::PosixLeftmostLongest);
+        assert_eq!(perl.find("xab").map(|m| m.range()), Some(1..2));
+        assert_eq!(posix.find("xab").map(|m| m.range()), Some(1..3));
+        assert!(perl.is_match("xab"));
+
+        let perl = compile("a|a+", MatchSemantics::PerlLeftmostFirst);
+        let posix = compile("a|a+", MatchSemantics::PosixLeftmostLongest);
+        assert_eq!(perl.global_search("aaa"), vec!["a", "a", "a"]);
+        assert_eq!(posix.global_search("aaa"), vec!["aaa"]);
+
+        // Both find the whole of "abcd", as `(ab)(c)` only matches "abc"
+        let perl = compile("(a|ab)(c|bcd)", MatchSemantics::PerlLeftmostFirst);
+        let posix = compile("(a|ab)(c|bcd)", MatchSemantics::PosixLeftmostLongest);
+        assert_eq!(perl.captures("abcd"), captures(&["abcd", "a", "bcd"]));
+        assert_eq!(posix.captures("abcd"), captures(&["abcd", "a", "bcd"]));
+        assert_eq!(perl.captures("abc"), captures(&["abc", "ab", "c"]));
+        assert_eq!(posix.captures("abc"), captures(&["abc", "ab", "c"]));
+
+        // POSIX matches as much as possible with the first capturing group, even if the first alternative also matches
+        let perl = compile("(a|ab)(c|bcd)(d*)", MatchSemantics::PerlLeftmostFirst);
+        let posix = compile("(a|ab)(c|bcd)(d*)", MatchSemantics::PosixLeftmostLongest);
+        assert_eq!(perl.captures("abcd"), captures(&["abcd", "a", "bcd", ""]));
+        assert_eq!(posix.captures("abcd"), captures(&["abcd", "ab", "c", "d"]));
+
+        // A repetition which matched the empty string does not repeat again, so capturing does not change the match
+        for (pattern, expr, expected) in [
+            ("(?:b??)*", "ba", 0..0),
+            ("(b??)*", "ba", 0..0),
+            ("(?:a??)+", "aa", 0..0),
+            ("(a??)+", "aa", 0..0),
+            ("(?:a*)*b", "aab", 0..3),
+            ("(a*)*b", "aab", 0..3),
+        ] {
+            let perl = compile(pattern, MatchSemantics::PerlLeftmostFirst);
+            assert_eq!(perl.find(expr).map(|m| m.range()), Some(expected), "{pattern} on {expr:?}");
+        }
+        let perl = compile("(b??)*", MatchSemantics::PerlLeftmostFirst);
+        assert_eq!(perl.captures("ba"), captures(&["", ""]));
+        let perl = compile("(a*)*b", MatchSemantics::PerlLeftmostFirst);
+        assert_eq!(perl.captures("aab"), captures(&["aab", "aa"]));
+
+        assert_eq!(
+            init()
+                .compile("(a|ab)(c|bcd)(d*)")
+                .unwrap()
+                .captures("abcd"),
+            captures(&["abcd", "ab", "c", "d"])
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn regex_stats() {
+        // The grammar shared by compilations on this thread is built on first use
+        assert!(RegExpBuilder::new("a").build().is_ok());
+        let grammar_builds = stats::grammar_builds();
+        let compilations = stats::compilations();
+
+        assert!(RegExpBuilder::new("b+").build().is_ok());
+        assert!(RegExpBuilder::new("c|d")
+            .case_insensitive(true)
+            .build()
+            .is_ok());
+        assert_eq!(stats::grammar_builds(), grammar_builds);
+        assert_eq!(stats::compilations(), compilations + 2);
+
+        // A pattern which fails to parse is never compiled
+        assert!(RegExpBuilder::new("(").build().is_err());
+        assert_eq!(stats::compilations(), compilations + 2);
+
+        let regex = init();
+        assert_eq!(stats::grammar_builds(), grammar_builds + 1);
+        assert!(regex.compile("e").is_ok());
+        assert_eq!(stats::compilations(), compilations + 3);
+    }
+
+    #[test]
+    fn regex_named_backreference() {
+        let regex = init();
+
+        for pattern in [r#"(?<q>['"]).*\k<q>"#, r#"(?<q>['"]).*\k{q}"#] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match(r#""double""#));
+            assert!(regexp.full_match("'single'"));
+            assert!(regexp.full_match(r#"'it "is" quoted'"#));
+            assert!(!regexp.full_match(r#""mismatched'"#));
+            assert!(!regexp.full_match(r#"'mismatched""#));
+            assert_eq!(regexp.global_search(r#"say "hi" and 'bye'"#), vec![r#""hi""#, "'bye'"]);
+        }
+
+        // A named backreference refers to the same capturing group as its index
+        let compiled_regexp = regex.compile(r"(?<word>\w+) \k<word>\+\1");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert!(regexp.full_match("ab ab+ab"));
+        assert!(!regexp.full_match("ab ab+ba"));
+        assert_eq!(regexp.captures("xx ab ab+ab"), Some(vec![Some(String::from("ab ab+ab")), Some(String::from("ab"))]));
+
+        assert_eq!(
+            regex.compile(r"(?<q>a)\k<quote>").err(),
+            Some(Error::new(ErrorKind::UndefinedNamedBackreference(String::from("quote"))))
+        );
+        assert_eq!(regex.compile(r"(a)\k<1>").err(), Some(Error::new(ErrorKind::UnexpectedChar('\\', 3))));
+    }
+
+    #[test]
+    fn regex_multibyte_capture_spans() {
+        let compiled_regexp = RegExpBuilder::new(r"(\w+) (\w+)").unicode(true).build();
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "café foo";
+        let spans = regexp.capture_spans(expr);
+        assert!(spans.is_some());
+        let spans = spans.unwrap();
+        assert_eq!(
+            spans
+                .iter()
+                .map(|m| m.map(|m| m.range()))
+                .collect::<Vec<_>>(),
+            vec![Some(0..9), Some(0..5), Some(6..9)]
+        );
+        assert_eq!(spans[1].map(|m| &expr[m.range()]), Some("café"));
+        assert_eq!(spans[2].map(|m| &expr[m.range()]), Some("foo"));
+
+        // Groups starting and ending next to characters of every UTF-8 length
+        let compiled_regexp = init().compile("é(.)(ab|€+)(.)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "xé😀€€ab";
+        let captures = regexp.captures_at(expr, 1);
+        assert!(captures.is_some());
+        let captures = captures.unwrap();
+        assert_eq!(captures.get_match(0).map(|m| m.range()), Some(1..14));
+        assert_eq!(captures.get_match(1).map(|m| m.range()), Some(3..7));
+        assert_eq!(captures.get_match(2).map(|m| m.range()), Some(7..13));
+        assert_eq!(captures.get_match(3).map(|m| m.range()), Some(13..14));
+        assert_eq!(captures.get(1), Some("😀"));
+        assert_eq!(captures.get(2), Some("€€"));
+        assert_eq!(captures.get(3), Some("a"));
+    }
+
+    #[test]
+    fn regex_engine() {
+        for pattern in [r"(a)\1", r"\((?R)?\)", r"(?<q>a)\k<q>"] {
+            assert_eq!(
+                RegExpBuilder::new(pattern)
+                    .engine(Engine::Nfa)
+                    .build()
+                    .err(),
+                Some(Error::new(ErrorKind::BacktrackingRequired))
+            );
+            assert!(RegExpBuilder::new(pattern).build().is_ok());
+        }
+        assert_eq!(
+            RegExpBuilder::new("a|ab")
+                .engine(Engine::Nfa)
+                .semantics(MatchSemantics::PerlLeftmostFirst)
+                .build()
+                .err(),
+            Some(Error::new(ErrorKind::BacktrackingRequired))
+        );
+
+        // Every engine finds the same matches
+        for engine in [Engine::Nfa, Engine::Backtracking, Engine::Auto] {
+            let compiled_regexp = RegExpBuilder::new(r"(\w+)@(\w+)|\d+")
+                .engine(engine)
+                .build();
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            let expr = "mail me@home or call 123";
+            assert!(regexp.is_match(expr));
+            assert_eq!(regexp.global_search(expr), vec!["me@home", "123"]);
+            assert_eq!(
+                regexp.captures(expr),
+                Some(vec![
+                    Some(String::from("me@home")),
+                    Some(String::from("me")),
+                    Some(String::from("home"))
+                ])
+            );
+            assert_eq!(regexp.find_at(expr, 8), Some((21, 24)));
+            assert!(!regexp.full_match(expr));
+        }
+
+        let compiled_regexp = RegExpBuilder::new(r"(a)\1")
+            .engine(Engine::Backtracking)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("aa"));
+    }
+
+    #[test]
+    fn regex_repeated_named_group() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(?<d>\d)+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let captures = regexp.captures_at("789", 0);
+        assert!(captures.is_some());
+        let captures = captures.unwrap();
+        assert_eq!(captures.name("d"), Some("9"));
+        assert_eq!(captures.name("d"), captures.get(1));
+        assert_eq!(captures.get_match(1).map(|m| m.range()), Some(2..3));
+
+        // The last iteration in which the named group participates is reported
+        let compiled_regexp = regex.compile(r"(?:(?<letter>[a-z])|\d)+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let captures = regexp.captures_iter_lazy("ab12 x").collect::<Vec<_>>();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures[0].name("letter"), Some("b"));
+        assert_eq!(captures[1].name("letter"), Some("x"));
+        assert_eq!(captures[0].name("missing"), None);
+    }
+
+    #[test]
+    fn regex_replace_all_strict() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(?<key>\w+)=(\w+)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let haystack = "a=1, b=2";
+        assert_eq!(regexp.replace_all_strict(haystack, "$2=$key"), Ok(String::from("1=a, 2=b")));
+        assert_eq!(regexp.replace_all_strict(haystack, "$0;$$3"), Ok(String::from("a=1;$3, b=2;$3")));
+
+        for (rep, reference) in [
+            ("$3=$1", "$3"),
+            ("${10}", "${10}"),
+            ("$value", "$value"),
+            ("${value}x", "${value}"),
+        ] {
+            assert_eq!(
+                regexp.replace_all_strict(haystack, rep),
+                Err(Error::new(ErrorKind::UndefinedReplacementReference(String::from(reference))))
+            );
+        }
+        assert_eq!(
+            regexp
+                .replace_all_strict(haystack, "$3")
+                .err()
+                .unwrap()
+                .to_string(),
+            "Replacement reference $3 refers to a non-existent capturing group"
+        );
+
+        // Lenient replacement replaces undefined references by nothing
+        assert_eq!(regexp.replace_all(haystack, "$3=$1"), "=a, =b");
+        assert_eq!(regexp.replace_all(haystack, "[$value]"), "[], []");
+    }
+
+    #[test]
+    fn regex_match_kind() {
+        let regex = init();
+
+        for (pattern, kind) in [
+            ("^abc", MatchKind::Prefix),
+            ("abc$", MatchKind::Suffix),
+            ("^abc$", MatchKind::Anchored),
+            ("abc", MatchKind::Unanchored),
+            ("^a|^b$", MatchKind::Prefix),
+            ("(?m)^abc$", MatchKind::Unanchored),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            assert_eq!(compiled_regexp.unwrap().match_kind(), kind, "{pattern}");
+        }
+
+        let compiled_regexp = RegExpBuilder::new("^abc$").multi_line(true).build();
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().match_kind(), MatchKind::Unanchored);
+    }
+
+    #[test]
+    fn regex_self_referential_borrows() {
+        // Nested closures of expressions matching the empty string loop states onto themselves, which must never be
+        // borrowed while mutably borrowed, whichever way the automata is built, simplified, reversed or matched
+        let mut patterns = vec![
+            String::from("(a*)*"),
+            String::from("(a*)+"),
+            String::from("(a?)*"),
+            String::from("(a*)*$"),
+            String::from("((a|b)*)*c"),
+            String::from(r"(\b)*a"),
+            String::from("(^)*a"),
+            String::from("(a{0,2})*"),
+        ];
+        for depth in 1..8 {
+            patterns.push(format!("{}a{}", "(".repeat(depth), ")*".repeat(depth)));
+            patterns.push(format!("{}a*{}", "(?:".repeat(depth), ")*".repeat(depth)));
+        }
+
+        for pattern in &patterns {
+            for semantics in [MatchSemantics::PosixLeftmostLongest, MatchSemantics::PerlLeftmostFirst] {
+                let compiled_regexp = RegExpBuilder::new(pattern).semantics(semantics).build();
+                assert!(compiled_regexp.is_ok(), "{pattern}");
+                let regexp = compiled_regexp.unwrap();
+
+                for expr in ["", "a", "aaab", "abcabc"] {
+                    regexp.is_match(expr);
+                    regexp.full_match(expr);
+                    regexp.global_search(expr);
+                    regexp.find_overlapping(expr);
+                    regexp.captures(expr);
+                    regexp.captures_iter_lazy(expr).count();
+                    regexp.explain(expr);
+                }
+                regexp.minimize();
+            }
+        }
+    }
+
+    #[test]
+    fn regex_always_matches() {
+        let regex = init();
+
+        for (pattern, always) in [
+            (".*", true),
+            ("a*", true),
+            ("abc", false),
+            ("a+", false),
+            ("(a?)", true),
+            ("^(?:ab)*(c|d?)", true),
+            ("a{0,3}b?", true),
+            ("abc|x*", true),
+            ("a{1,3}", false),
+            ("a*$", false),
+            (r"\b", false),
+            (r"(a*)\1", false),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert_eq!(regexp.always_matches(), always, "{pattern}");
+            if always {
+                for expr in ["", "b", "xyz\n"] {
+                    assert!(regexp.is_match(expr), "{pattern} on {expr:?}");
+                }
+            }
+        }
+
+        // Empty branches are not supported, so an alternation with an empty branch is written with an optional instead
+        for pattern in ["(a|)", "(|a)", "a|"] {
+            assert!(regex.compile(pattern).is_err(), "{pattern}");
+        }
+        assert!(regex
+            .compile("(a|b?)")
+            .is_ok_and(|regexp| regexp.always_matches()));
+    }
+
+    #[test]
+    fn regex_replace_all_with() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(\w+)@(\w+)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let haystack = "mail alice@example or bob@test, not @nobody";
+        let swapped = regexp.replace_all_with(haystack, |captures| {
+            format!("{}@{}", captures.get(2).unwrap_or_default(), captures.get(1).unwrap_or_default())
+        });
+        assert_eq!(swapped, "mail example@alice or test@bob, not @nobody");
+        assert_eq!(swapped, regexp.replace_all(haystack, "$2@$1"));
+
+        // The spans of capturing groups are exposed as well as their substrings
+        let spans = regexp.replace_all_with("a@b cc@dd", |captures| {
+            let (user, domain) = (captures.get_match(1).unwrap(), captures.get_match(2).unwrap());
+            format!("[{}..{}|{}..{}]", user.start(), user.end(), domain.start(), domain.end())
+        });
+        assert_eq!(spans, "[0..1|2..3] [4..6|7..9]");
+
+        assert_eq!(regexp.replace_all_with("no match", |_| String::from("#")), "no match");
+    }
+
+    #[test]
+    fn regex_find_iter_linecol() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"b+|c\nd|$");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let expr = "ab\néébb c\nd\n";
+        let positions: Vec<_> = regexp
+            .find_iter_linecol(expr)
+            .into_iter()
+            .map(|(m, start, end)| (m.as_str(expr), start, end))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                ("b", (1, 1), (1, 2)),
+                // Columns count characters rather than bytes
+                ("bb", (2, 2), (2, 4)),
+                // A match spanning lines ends on a later line
+                ("c\nd", (2, 5), (3, 1)),
+                // The end of the expression lies on the line after its trailing newline
+                ("", (4, 0), (4, 0)),
+            ]
+        );
+
+        assert_eq!(regexp.find_iter_linecol("").len(), 1);
+        assert!(regex
+            .compile("x")
+            .is_ok_and(|regexp| regexp.find_iter_linecol("a\nb").is_empty()));
+    }
+
+    #[test]
+    fn regex_any_except_newline() {
+        for dot_all in [false, true] {
+            let compiled_regexp = RegExpBuilder::new(r"a\Nb").dot_all(dot_all).build();
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            assert!(regexp.full_match("axb"));
+            assert!(regexp.full_match("aéb"));
+            assert!(!regexp.full_match("a\nb"));
+
+            // Unlike `\N`, `.` matches `\n` in dot-all mode
+            let compiled_regexp = RegExpBuilder::new("a.b").dot_all(dot_all).build();
+            assert!(compiled_regexp.is_ok());
+            assert_eq!(compiled_regexp.unwrap().full_match("a\nb"), dot_all);
+        }
+
+        let compiled_regexp = init().compile(r"(?s)\N+");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search("ab\ncd"), vec!["ab", "cd"]);
+    }
+
+    #[test]
+    fn regex_alternation_property() {
+        // A linear congruential generator, so that the generated cases are the same on every run
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |n: usize| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 33) as usize % n
+        };
+
+        let regex = init();
+        for _ in 0..200 {
+            let mut branches = [String::new(), String::new()];
+            for branch in &mut branches {
+                for _ in 0..1 + next(3) {
+                    let atom = ["a", "b", "(ab)", "(ba)", "[ab]"][next(5)];
+                    let quantifier = ["", "", "*", "+", "?"][next(5)];
+                    branch.push_str(atom);
+                    branch.push_str(quantifier);
+                }
+            }
+            let pattern = format!("{}|{}", branches[0], branches[1]);
+            let branch_regexps: Vec<RegExp> = branches
+                .iter()
+                .map(|branch| regex.compile(branch).unwrap())
+                .collect();
+            let regexp = regex.compile(&pattern).unwrap();
+
+            let haystack: String = (0..next(9)).map(|_| ['a', 'b', 'c'][next(3)]).collect();
+            let longest = |start: usize| {
+                (start..=haystack.len()).rev().find(|&end| {
+                    branch_regexps
+                        .iter()
+                        .any(|r| r.full_match(&haystack[start..end]))
+                })
+            };
+
+            let matches: Vec<Match> = regexp.find_iter(&haystack).collect();
+            assert_eq!(
+                regexp.global_search(&haystack),
+                matches
+                    .iter()
+                    .map(|m| m.as_str(&haystack))
+                    .collect::<Vec<_>>(),
+                "{pattern:?} on {haystack:?}"
+            );
+            for (i, m) in matches.iter().enumerate() {
+                assert_eq!(longest(m.start()), Some(m.end()), "{pattern:?} on {haystack:?}: {m:?}");
+                if i > 0 {
+                    assert!(matches[i - 1].end() <= m.start(), "{pattern:?} on {haystack:?}: {m:?} overlaps");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regex_parse_prefix() {
+        let regex = init();
+
+        let parsed = regex.parse_prefix("a+b) rest");
+        assert!(parsed.is_some());
+        let (regexp, rst) = parsed.unwrap();
+        assert_eq!(rst, ") rest");
+        assert!(regexp.full_match("aab"));
+        assert!(!regexp.full_match("aab)"));
+
+        // Whitespaces are literals, so the entire expression is consumed
+        let parsed = regex.parse_prefix("a+b rest");
+        assert!(parsed.is_some());
+        let (regexp, rst) = parsed.unwrap();
+        assert_eq!(rst, "");
+        assert!(regexp.full_match("ab rest"));
+
+        let parsed = regex.parse_prefix("(a|b)*c]d");
+        assert!(parsed.is_some());
+        let (regexp, rst) = parsed.unwrap();
+        assert_eq!(rst, "]d");
+        assert!(regexp.full_match("abbac"));
+
+        assert!(regex.parse_prefix("").is_none());
+        assert!(regex.parse_prefix(")a").is_none());
+        assert!(regex.parse_prefix(r"(a)\2 rest").is_none());
+    }
+
+    #[test]
+    fn regex_match_paths() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("ab");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        // The start state, the state reached after `a`, then the end state reached after `b`
+        assert_eq!(regexp.match_paths("ab"), vec![vec![0, 1, 2]]);
+        assert_eq!(regexp.match_paths("xaby"), vec![vec![0, 1, 2]]);
+        assert!(regexp.match_paths("ba").is_empty());
+
+        // Each branch matching the same substring is a separate path
+        let compiled_regexp = regex.compile("(a|a)b*");
+        assert!(compiled_regexp.is_ok());
+        let paths = compiled_regexp.unwrap().match_paths("abb");
+        assert_eq!(paths.len(), 2);
+        assert_ne!(paths[0], paths[1]);
+        assert_eq!(paths[0].first(), Some(&0));
+        assert_eq!(paths[0].last(), paths[1].last());
+    }
+
+    #[test]
+    fn regex_captures_alternating_repetition() {
+        let spans = |regexp: &RegExp, expr: &str| {
+            regexp.capture_spans(expr).map(|spans| {
+                spans
+                    .iter()
+                    .map(|m| m.map(|m| (m.start(), m.end())))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        for semantics in [MatchSemantics::PosixLeftmostLongest, MatchSemantics::PerlLeftmostFirst] {
+            let compiled_regexp = RegExpBuilder::new("(?:(a)|(b))+")
+                .semantics(semantics)
+                .build();
+            assert!(compiled_regexp.is_ok());
+            let regexp = compiled_regexp.unwrap();
+
+            // Each group captures the last iteration it participates in, even if a later iteration takes the other branch
+            assert_eq!(
+                regexp.captures("ab"),
+                Some(vec![Some(String::from("ab")), Some(String::from("a")), Some(String::from("b"))])
+            );
+            assert_eq!(spans(&regexp, "abab"), Some(vec![Some((0, 4)), Some((2, 3)), Some((3, 4))]));
+            assert_eq!(spans(&regexp, "bab"), Some(vec![Some((0, 3)), Some((1, 2)), Some((2, 3))]));
+            assert_eq!(spans(&regexp, "xba"), Some(vec![Some((1, 3)), Some((2, 3)), Some((1, 2))]));
+
+            // A group of a branch never taken does not participate
+            assert_eq!(regexp.captures("aa"), Some(vec![Some(String::from("aa")), Some(String::from("a")), None]));
+            assert_eq!(spans(&regexp, "bb"), Some(vec![Some((0, 2)), None, Some((1, 2))]));
+        }
+    }
+
+    #[test]
+    fn regex_as_ref_inputs() {
+        use std::borrow::Cow;
+
+        let regex = init();
+
+        let compiled_regexp = regex.compile(r"(\d)\d*");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let owned = String::from("a12b3");
+        let borrowed: Cow<str> = Cow::Borrowed("a12b3");
+        let cow_owned: Cow<str> = Cow::Owned(String::from("a12b3"));
+
+        assert!(regexp.is_match(owned.clone()));
+        assert!(regexp.is_match(&owned));
+        assert!(regexp.is_match(&borrowed));
+        assert!(regexp.is_match(cow_owned.clone()));
+        assert!(regexp.full_match(String::from("123")));
+        assert!(!regexp.full_match(Cow::Borrowed("12a")));
+
+        assert_eq!(regexp.greedy_search(&owned), Some(String::from("12")));
+        assert_eq!(regexp.greedy_search(borrowed.clone()), Some(String::from("12")));
+        assert_eq!(regexp.global_search(&owned), vec!["12", "3"]);
+        assert_eq!(regexp.global_search(&cow_owned), vec!["12", "3"]);
+        assert_eq!(regexp.find(&owned).map(|m| m.start()), Some(1));
+        assert_eq!(
+            regexp
+                .find_cached(&borrowed, &mut SearchCache::default())
+                .map(|m| m.start()),
+            Some(1)
+        );
+        assert_eq!(regexp.find_overlapping(&owned).len(), 3);
+        assert_eq!(regexp.find_iter_linecol(&borrowed).len(), 2);
+        assert_eq!(regexp.shortest_match_at(&owned, 1), Some(2));
+
+        assert_eq!(regexp.captures(&owned), regexp.captures("a12b3"));
+        assert_eq!(regexp.capture_spans(&borrowed).map(|spans| spans.len()), Some(2));
+        assert_eq!(regexp.split(&cow_owned), vec!["a", "1", "b", "3", ""]);
+
+        assert_eq!(regexp.replace_all(&owned, String::from("<$1>")), "a<1>b<3>");
+        assert_eq!(regexp.replace_all(&borrowed, Cow::Borrowed("#")), "a#b#");
+        assert_eq!(
+            regexp
+                .replace_all_strict(owned.clone(), "$2")
+                .err()
+                .unwrap()
+                .kind(),
+            &ErrorKind::UndefinedReplacementReference(String::from("$2"))
+        );
+        assert_eq!(
+            regexp.replace_all_with(&cow_owned, |captures| captures.get(1).unwrap_or_default().repeat(2)),
+            "a11b33"
+        );
+        assert_eq!(regexp.replacen(&owned, 1, &owned), "aa12b3b3");
+        assert_eq!(regexp.replace_preview(&borrowed, "x").len(), 2);
+
+        let mut out = String::new();
+        assert!(regexp.replace_all_to(&owned, "_", &mut out).is_ok());
+        assert_eq!(out, "a_b_");
+
+        assert!(regexp.explain(&owned).matched);
+        assert_eq!(regexp.match_paths(&owned), regexp.match_paths("a12b3"));
+    }
+
+    #[test]
+    fn regex_nested_unbounded_quantifier() {
+        let regex = init();
+
+        for (pattern, nested) in [
+            ("(a*)*", true),
+            ("(a+)+", true),
+            ("(?:a|b+){2,}", true),
+            ("x(y(a*)b)+", true),
+            ("((a(b*))c)*", true),
+            ("((a+))", false),
+            ("(a*)b", false),
+            ("a*b*", false),
+            ("(a*)?", false),
+            ("(a*){1,3}", false),
+            ("(ab)*", false),
+            ("(a{1,3})+", false),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok());
+            assert_eq!(compiled_regexp.unwrap().has_nested_unbounded_quantifier(), nested, "{pattern}");
+        }
+
+        let compiled_regexp = regex.compile_checked("(a+)+b");
+        assert!(compiled_regexp.is_ok());
+        let (_, warnings) = compiled_regexp.unwrap();
+        assert_eq!(warnings, vec![Warning::NestedUnboundedQuantifier]);
+    }
+
+    #[test]
+    fn regex_captures_by_name_and_index() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("(?<a>x)(y)");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        let captures = regexp.captures_iter_lazy("_xy_").next();
+        assert!(captures.is_some());
+        let captures = captures.unwrap();
+
+        // Named capturing groups are numbered along with unnamed ones
+        assert_eq!(captures.len(), 3);
+        assert_eq!(captures.get(0), Some("xy"));
+        assert_eq!(captures.name("a"), Some("x"));
+        assert_eq!(captures.get(1), Some("x"));
+        assert_eq!(captures.get(2), Some("y"));
+        assert_eq!(captures.name_match("a"), captures.get_match(1));
+        assert_eq!(captures.name_match("a").map(|m| (m.start(), m.end())), Some((1, 2)));
+
+        assert_eq!(captures.get(3), None);
+        assert_eq!(captures.name("b"), None);
+        assert_eq!(captures.name_match("b"), None);
+    }
+
+    #[test]
+    fn regex_compose() -> Result<(), Error> {
+        let regex = init();
+
+        let concatenated = (regex.compile("a")? + regex.compile("b")?)?;
+        assert!(concatenated.full_match("ab"));
+        assert!(!concatenated.full_match("a"));
+        assert_eq!(concatenated.global_search("xabab"), vec!["ab", "ab"]);
+
+        // Each operand is grouped, so that alternations do not extend across them
+        let alternated = (regex.compile("ab|c")? | regex.compile("d|ef")?)?;
+        assert!(alternated.full_match("ab"));
+        assert!(alternated.full_match("ef"));
+        assert!(!alternated.full_match("cd"));
+        let concatenated = regex.compile("ab|c")?.concat(&regex.compile("d|ef")?)?;
+        assert!(concatenated.full_match("cd"));
+        assert!(concatenated.full_match("abef"));
+        assert!(!concatenated.full_match("ab"));
+
+        let repeated = regex.compile("ab")?.repeat()?;
+        assert!(repeated.full_match(""));
+        assert!(repeated.full_match("abab"));
+        assert!(!repeated.full_match("aba"));
+        assert!(regex.compile("ab")?.optional()?.full_match(""));
+        assert!(!regex.compile("ab")?.optional()?.full_match("abab"));
+        assert!(!regex.compile("ab")?.one_or_more()?.full_match(""));
+        assert!(regex.compile("ab")?.one_or_more()?.full_match("ababab"));
+
+        // The quantified regular expression is compiled anew, against the limits it was built with
+        let limited = RegExpBuilder::new("a{5}").max_states(20).build()?;
+        assert!(limited.full_match("aaaaa"));
+        for quantified in [limited.repeat(), limited.optional(), limited.one_or_more()] {
+            assert!(matches!(quantified.err().unwrap().kind(), ErrorKind::ExpansionTooLarge { limit: 20, .. }));
+        }
+
+        // Capturing groups, and references to them, are numbered after those of the first operand
+        let composed = regex
+            .compile(r"(a)\1")?
+            .concat(&regex.compile(r"(b)(?<c>c)\1\k<c>")?)?;
+        assert_eq!(composed.group_count(), 3);
+        assert_eq!(composed.group_names(), vec![None, None, None, Some(String::from("c"))]);
+        assert!(composed.full_match("aabcbc"));
+        assert!(!composed.full_match("aabcac"));
+        assert_eq!(
+            composed.captures("aabcbc"),
+            Some(vec![
+                Some(String::from("aabcbc")),
+                Some(String::from("a")),
+                Some(String::from("b")),
+                Some(String::from("c"))
+            ])
+        );
+
+        assert_eq!(
+            (regex.compile("(?<x>a)")? | regex.compile("(?<x>b)")?).err(),
+            Some(Error::new(ErrorKind::DuplicateGroupName(String::from("x"))))
+        );
+
+        // Each operand keeps its own flags
+        let composed = (RegExpBuilder::new("a").case_insensitive(true).build()? + regex.compile("b")?)?;
+        assert!(composed.full_match("Ab"));
+        assert!(!composed.full_match("AB"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn regex_compile_with_flags() {
+        let regex = init();
+        let flags = Flags { case_insensitive: true, ..Flags::default() };
+
+        let compiled_regexp = regex.compile_with_flags("Hello", flags.clone());
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert!(regexp.full_match("Hello"));
+        assert!(regexp.full_match("hello"));
+        assert!(regexp.full_match("HELLO"));
+        assert!(!regexp.full_match("Help"));
+        assert_eq!(regexp.global_search("say hello, HeLLo!"), vec!["hello", "HeLLo"]);
+
+        // Character ranges accept the other case of their letters as well
+        let compiled_regexp = regex.compile_with_flags("[A-Z]+", flags.clone());
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("keyWord"));
+
+        let compiled_regexp = regex.compile_with_flags("Hello", Flags::default());
+        assert!(compiled_regexp.is_ok());
+        assert!(!compiled_regexp.unwrap().full_match("hello"));
+    }
+
+    #[test]
+    fn regex_rfind() {
+        let regex = init();
+        let span = |m: Option<Match>| m.map(|m| (m.start(), m.end()));
+
+        let compiled_regexp = regex.compile(r"\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(span(regexp.rfind("a1b22c333")), Some((6, 9)));
+        assert_eq!(span(regexp.rfind("a1b22c333d")), Some((6, 9)));
+        assert_eq!(span(regexp.rfind("a1")), Some((1, 2)));
+        assert_eq!(regexp.rfind("abc"), None);
+
+        // Matches are located from left to right, so the last one need not be the rightmost
+        let compiled_regexp = regex.compile("aa");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(span(compiled_regexp.unwrap().rfind("aaa")), Some((0, 2)));
+
+        let compiled_regexp = regex.compile(r"\.\w+$");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+        assert_eq!(span(regexp.rfind("archive.tar.gz")), Some((11, 14)));
+        assert_eq!(regexp.rfind("archive.tar.gz/"), None);
+
+        let compiled_regexp = regex.compile("x*");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(span(compiled_regexp.unwrap().rfind("axxb")), Some((4, 4)));
+    }
+
+    #[test]
+    fn regex_lazy_quantifiers() {
+        let regex = init();
+
+        let compiled_regexp = regex.compile("a+?");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.greedy_search("aaa"), Some(String::from("a")));
+        assert_eq!(regexp.global_search("aaa"), vec!["a", "a", "a"]);
+        assert!(regexp.full_match("aaa"));
+
+        for (pattern, expr, expected) in [
+            ("a*?", "aaa", ""),
+            ("a??", "a", ""),
+            ("a{2,4}?", "aaaa", "aa"),
+            ("a{2,}?", "aaaa", "aa"),
+            ("a*?b", "aaab", "aaab"),
+            ("<.+?>", "<a><b>", "<a>"),
+            ("<.+>", "<a><b>", "<a><b>"),
+            ("(?:ab)+?", "ababab", "ab"),
+            ("x(a|b)*?y", "xabay", "xabay"),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok(), "{pattern}");
+            assert_eq!(compiled_regexp.unwrap().greedy_search(expr), Some(String::from(expected)), "{pattern} on {expr:?}");
+        }
+
+        // Capturing groups capture as in Perl
+        let compiled_regexp = regex.compile("(a+?)(a*)");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(
+            compiled_regexp.unwrap().captures("aaa"),
+            Some(vec![Some(String::from("aaa")), Some(String::from("a")), Some(String::from("aa"))])
+        );
+
+        // A lazy body which may match the empty string stops its repetition once it does, whether or not it captures
+        for (pattern, expr, expected, group) in [
+            ("(?:b??)*", "ba", "", None),
+            ("(b??)*", "ba", "", Some("")),
+            ("(?:a??)+", "aa", "", None),
+            ("(a??)+", "aa", "", Some("")),
+            ("(?:a*?|b)+c", "bac", "bac", None),
+            ("(a*?|b)+c", "bac", "bac", Some("a")),
+            ("(?:a{0,2}?)+b", "aab", "aab", None),
+            ("(a{0,2}?)+b", "aab", "aab", Some("a")),
+        ] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok(), "{pattern}");
+            let regexp = compiled_regexp.unwrap();
+            assert_eq!(regexp.greedy_search(expr), Some(String::from(expected)), "{pattern} on {expr:?}");
+            if let Some(group) = group {
+                assert_eq!(
+                    regexp.captures(expr),
+                    Some(vec![Some(String::from(expected)), Some(String::from(group))]),
+                    "{pattern} on {expr:?}"
+                );
+            }
+        }
+
+        // A `?` quantifying a quantifier makes it lazy, but cannot be repeated
+        assert!(regex.compile("a+??").is_err());
+        assert_eq!(
+            RegExpBuilder::new("a+?").engine(Engine::Nfa).build().err(),
+            Some(Error::new(ErrorKind::BacktrackingRequired))
+        );
+
+        let compiled_regexp = regex.compile_checked(".*?x");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().1, vec![Warning::UnboundedAny(String::from(".*?"))]);
+    }
+
+    #[test]
+    fn regex_expansion_too_large() {
+        let regex = init();
+
+        // The default limit only rejects regular expressions expanding into a very large number of states
+        for pattern in [r"\d{0,30000}", "(ab){0,20000}", "[a-z]{1,40000}"] {
+            assert!(regex.compile(pattern).is_ok(), "{pattern}");
+        }
+        for compiled_regexp in [regex.compile("(ab){0,100000}"), RegExpBuilder::new("(ab){0,100000}").build()] {
+            assert!(matches!(
+                compiled_regexp.err().unwrap().kind(),
+                ErrorKind::ExpansionTooLarge { estimated: 800_000, limit: DEFAULT_MAX_STATES }
+            ));
+        }
+        assert!(RegExpBuilder::new("(ab){0,30000}")
+            .max_states(usize::MAX)
+            .build()
+            .is_ok());
+
+        let compiled_regexp = RegExpBuilder::new("(ab){0,100000}")
+            .max_states(100_000)
+            .build();
+        match compiled_regexp.err().unwrap().kind() {
+            ErrorKind::ExpansionTooLarge { estimated, limit } => {
+                assert_eq!(*limit, 100_000);
+                assert!(*estimated >= 100000 * 2);
+            }
+            kind => panic!("Unexpected error {kind:?}"),
+        }
+
+        // Nested ranges multiply, and the limit applies to the whole regular expression rather than to each range
+        let compiled_regexp = RegExpBuilder::new("(a{0,500}){0,500}")
+            .max_states(100_000)
+            .build();
+        assert!(matches!(compiled_regexp.err().unwrap().kind(), ErrorKind::ExpansionTooLarge { .. }));
+        assert!(RegExpBuilder::new("a{0,20000}")
+            .max_states(100_000)
+            .build()
+            .is_ok());
+        assert!(RegExpBuilder::new(&"a{0,20000}".repeat(10))
+            .max_states(100_000)
+            .build()
+            .is_err());
+
+        let compiled_regexp = RegExpBuilder::new("a{5,10}").max_states(100).build();
+        assert!(compiled_regexp.is_ok());
+        assert!(compiled_regexp.unwrap().full_match("aaaaaaa"));
+
+        // Unbounded ranges only expand their lower bound
+        let compiled_regexp = RegExpBuilder::new("a{5,10}").max_states(30).build();
+        assert!(matches!(
+            compiled_regexp.err().unwrap().kind(),
+            ErrorKind::ExpansionTooLarge { estimated, limit: 30 } if *estimated > 30
+        ));
+        assert!(RegExpBuilder::new("a{5,}").max_states(30).build().is_ok());
+
+        // The estimate is an upper bound on the states actually created
+        for pattern in ["a{5,10}", "(ab|c){3}", r"(?:x\d+|[a-z]?){2,}$", "(a{0,5}b*){0,5}"] {
+            let compiled_regexp = regex.compile(pattern);
+            assert!(compiled_regexp.is_ok(), "{pattern}");
+            let regexp = compiled_regexp.unwrap();
+            assert!(regexp.automata.state_count() <= analysis::estimated_states(&regexp.regex), "{pattern}");
+        }
+
+        let compiled_regexp = RegExpBuilder::new("(ab){0,100000}").max_states(10).build();
+        assert_eq!(
+            compiled_regexp.err().unwrap().msg(),
+            "Regular expression expands into an estimated 800000 states, exceeding the limit of 10"
+        );
+    }
+
+    #[test]
+    fn regex_multi_line_global_search() {
+        let regex = init();
+
+        let expr = "12 apples\nno digits\n345 pears\nend 6";
+
+        let compiled_regexp = regex.compile(r"(?m)^\d+");
+        assert!(compiled_regexp.is_ok());
+        let regexp = compiled_regexp.unwrap();
+
+        assert_eq!(regexp.global_search(expr), vec!["12", "345"]);
+        assert_eq!(
+            regexp
+                .find_iter(expr)
+                .map(|m| m.range())
+                .collect::<Vec<_>>(),
+            vec![0..2, 20..23]
+        );
+
+        let compiled_regexp = RegExpBuilder::new(r"^\d+")
+            .multi_line(true)
+            .semantics(MatchSemantics::PerlLeftmostFirst)
+            .build();
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search(expr), vec!["12", "345"]);
+
+        // Without multi-line, `^` only matches at the start of the expression
+        let compiled_regexp = regex.compile(r"^\d+");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search(expr), vec!["12"]);
+
+        // Matches ending at `$` on one line do not prevent matches on the next
+        let compiled_regexp = regex.compile(r"(?m)\w+$");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search(expr), vec!["apples", "digits", "pears", "6"]);
+
+        // Empty lines match empty anchored patterns once each
+        let compiled_regexp = regex.compile(r"(?m)^\d*$");
+        assert!(compiled_regexp.is_ok());
+        assert_eq!(compiled_regexp.unwrap().global_search("1\n\n23\nx\n"), vec!["1", "", "23", ""]);
+    }
 }
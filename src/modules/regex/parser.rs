@@ -1,8 +1,23 @@
+use std::cell::RefCell;
 use std::ops;
+use std::rc::Rc;
+
+use crate::modules::trace::trace;
+
+use super::ErrorKind;
 
 /// A function which parses a [`prim@str`] expression into an `Option<(T, &str)>` type.
 type ParserFunction<T> = dyn Fn(&str) -> Option<(T, &str)>;
 
+/// A function building the [`ErrorKind`] of a failure committed to by [`MonadicParser::reject_if`], given its byte offset.
+type Rejection = Box<dyn FnOnce(usize) -> ErrorKind>;
+
+thread_local! {
+    /// The first failure committed to by [`MonadicParser::reject_if`] since [`MonadicParser::parse_committed`] last started,
+    /// along with the length of the remainder of the expression where it occurred.
+    static REJECTION: RefCell<Option<(usize, Rejection)>> = const { RefCell::new(None) };
+}
+
 /// A structure to define a parser as a monad of a formal grammar, i.e. a monoid in the category of endofunctors of a formal grammar.
 ///
 /// Here, we view a parser as an endofunctor of a formal (context free) grammar, viewed as a category over its alphabet set.
@@ -20,7 +35,25 @@ impl<T: 'static> MonadicParser<T> {
 
     /// Parses `expr` into an `Option<(T, &str)>` type.
     pub fn parse<'a>(&self, expr: &'a str) -> Option<(T, &'a str)> {
-        (self.fcn)(expr)
+        let result = (self.fcn)(expr);
+        match &result {
+            Some((_, rst)) => trace!("parsed {expr:?}, leaving {rst:?}"),
+            None => trace!("failed to parse {expr:?}"),
+        }
+
+        result
+    }
+
+    /// Parses `expr` into an `Option<(T, &str)>` type, along with the [`ErrorKind`] of the first failure committed to by
+    /// [`MonadicParser::reject_if`] while doing so, if any, located at its byte offset in `expr`.
+    pub fn parse_committed<'a>(&self, expr: &'a str) -> (Option<(T, &'a str)>, Option<ErrorKind>) {
+        REJECTION.with(|rejection| rejection.borrow_mut().take());
+        let result = self.parse(expr);
+        let rejection = REJECTION
+            .with(|rejection| rejection.borrow_mut().take())
+            .map(|(remaining, rejection)| rejection(expr.len() - remaining));
+
+        (result, rejection)
     }
 
     /// Takes two [`MonadicParser`] and creates a new [`MonadicParser`] which applies the parsing of both in sequence.
@@ -88,6 +121,55 @@ impl<T: 'static> MonadicParser<T> {
         self.optional().map(|x| Some(x.is_some()))
     }
 
+    /// Creates a [`MonadicParser`] which succeeds without consuming anything if `parse` fails on the given expression, and
+    /// fails otherwise.
+    pub fn negate(self) -> MonadicParser<()> {
+        MonadicParser::new(move |expr| if self.parse(expr).is_some() { None } else { Some(((), expr)) })
+    }
+
+    /// Creates a [`MonadicParser`] which yields the substring of the given expression consumed by `parse`, rather than its
+    /// result.
+    pub fn recognize(self) -> MonadicParser<String> {
+        MonadicParser::new(move |expr| {
+            let (_, rst) = self.parse(expr)?;
+            Some((String::from(&expr[..expr.len() - rst.len()]), rst))
+        })
+    }
+
+    /// Creates a [`MonadicParser`] which yields the result of `parse` unless it satisfies `predicate`, in which case it
+    /// fails and commits to the failure built by `rejection` from the result and the byte offset of the given expression,
+    /// so that it is reported by [`MonadicParser::parse_committed`] rather than where parsing stopped.
+    ///
+    /// This is meant for expressions which cannot be parsed in any other way, e.g. a group which is never closed.
+    pub fn reject_if<P, F>(self, predicate: P, rejection: F) -> Self
+    where
+        P: Fn(&T) -> bool + 'static,
+        F: Fn(T, usize) -> ErrorKind + 'static,
+    {
+        let rejection = Rc::new(rejection);
+        MonadicParser::new(move |expr| {
+            let (t, rst) = self.parse(expr)?;
+            if !predicate(&t) {
+                return Some((t, rst));
+            }
+
+            let rejection = rejection.clone();
+            REJECTION.with(|committed| {
+                committed
+                    .borrow_mut()
+                    .get_or_insert_with(|| (expr.len(), Box::new(move |position| rejection(t, position))));
+            });
+
+            None
+        })
+    }
+
+    /// Creates a [`MonadicParser`] which always fails, but commits to the failure built by `rejection` wherever `parse`
+    /// succeeds, as [`MonadicParser::reject_if`] does.
+    pub fn reject<U: 'static, F: Fn(T, usize) -> ErrorKind + 'static>(self, rejection: F) -> MonadicParser<U> {
+        self.reject_if(|_| true, rejection).map(|_| None)
+    }
+
     /// Creates a wrapper [`MonadicParser`] which calls the resulting [`MonadicParser`] from `closure` lazily, i.e. on parsing an expression.
     pub fn lazy<F: Fn() -> MonadicParser<T> + 'static>(closure: F) -> Self {
         MonadicParser::new(move |expr| closure().parse(expr))
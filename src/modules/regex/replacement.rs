@@ -0,0 +1,78 @@
+use crate::union;
+
+use super::alphabet::{any, character, digit, end, name, string};
+use super::parser::MonadicParser;
+
+// Specification of grammar rules for replacement strings
+
+/// `Replacement ::= ReplacementItem* END`
+pub type Replacement = Vec<ReplacementItem>;
+
+/// Returns a [`MonadicParser`] defining the grammar of replacement strings.
+pub fn replacement() -> MonadicParser<Replacement> {
+    replacement_item().repeat() << end()
+}
+
+/// Parses `rep` as a replacement string, which never fails as any string is a valid replacement string.
+pub fn parse(rep: &str) -> Replacement {
+    let (items, _) = replacement()
+        .parse(rep)
+        .expect("every string should parse as a replacement string");
+
+    items
+}
+
+/// Parses `rep` as a replacement string, as [`parse`] does, along with the substring of `rep` each item is written as.
+pub fn parse_written(rep: &str) -> Vec<(ReplacementItem, &str)> {
+    let replacement_item = replacement_item();
+    let mut items = vec![];
+    let mut rst = rep;
+    while let Some((item, next)) = replacement_item.parse(rst) {
+        items.push((item, &rst[..rst.len() - next.len()]));
+        rst = next;
+    }
+
+    items
+}
+
+/// `ReplacementItem ::= "$$" | "${" Reference '}' | '$' Reference | Char`
+///
+/// A `$` which does not start a valid reference is a literal `$`.
+#[derive(Debug, PartialEq)]
+pub enum ReplacementItem {
+    Literal(char),
+    Reference(Reference),
+}
+
+/// Returns a [`MonadicParser`] associated to the grammar rule [`ReplacementItem`].
+fn replacement_item() -> MonadicParser<ReplacementItem> {
+    union![
+        string("$$").map(|_| Some(ReplacementItem::Literal('$'))),
+        (string("${") >> reference() << character('}')).map(|r| Some(ReplacementItem::Reference(r))),
+        (character('$') >> reference()).map(|r| Some(ReplacementItem::Reference(r))),
+        any().map(|c| Some(ReplacementItem::Literal(c))),
+    ]
+}
+
+/// `Reference ::= Index | Name`
+///
+/// An unbraced reference extends as far as possible, so that `$10` refers to group 10 and `${1}0` is needed to refer to
+/// group 1 followed by a literal `0`.
+#[derive(Debug, PartialEq)]
+pub enum Reference {
+    Index(usize),
+    Name(String),
+}
+
+/// Returns a [`MonadicParser`] associated to the grammar rule [`Reference`].
+fn reference() -> MonadicParser<Reference> {
+    union![
+        digit().one_or_more().map(|digits| {
+            digits
+                .iter()
+                .try_fold(0usize, |acc, &d| acc.checked_mul(10)?.checked_add(d as usize))
+                .map(Reference::Index)
+        }),
+        name().map(|name| Some(Reference::Name(name))),
+    ]
+}
@@ -0,0 +1,30 @@
+//! Counters of the compilations made on the current thread, to detect regular expressions being compiled over and over
+//! rather than compiled once and reused.
+
+use std::cell::Cell;
+
+thread_local! {
+    static GRAMMAR_BUILDS: Cell<usize> = const { Cell::new(0) };
+    static COMPILATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Returns the number of times the grammar of regular expressions was built on the current thread, i.e. the number of
+/// calls to [`init`](super::init), including the one building the grammar shared by compilations on the current thread.
+pub fn grammar_builds() -> usize {
+    GRAMMAR_BUILDS.with(Cell::get)
+}
+
+/// Returns the number of regular expressions compiled into a [`RegExp`](super::RegExp) on the current thread.
+pub fn compilations() -> usize {
+    COMPILATIONS.with(Cell::get)
+}
+
+/// Counts a build of the grammar of regular expressions.
+pub(super) fn record_grammar_build() {
+    GRAMMAR_BUILDS.with(|builds| builds.set(builds.get() + 1));
+}
+
+/// Counts a compilation of a regular expression.
+pub(super) fn record_compilation() {
+    COMPILATIONS.with(|compilations| compilations.set(compilations.get() + 1));
+}
@@ -3,7 +3,7 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::{ptr, slice};
 
-use super::{Anchor, State};
+use super::{Anchor, PredicateKind, State};
 
 pub struct AnchorState {
     anchor: Anchor,
@@ -14,6 +14,10 @@ impl AnchorState {
     pub fn new(anchor: Anchor, dest: Rc<RefCell<dyn State>>) -> Self {
         AnchorState { anchor, dest }
     }
+
+    pub fn anchor(&self) -> Anchor {
+        self.anchor
+    }
 }
 
 impl State for AnchorState {
@@ -33,6 +37,22 @@ impl State for AnchorState {
         slice::from_ref(&self.dest)
     }
 
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>] {
+        slice::from_mut(&mut self.dest)
+    }
+
+    fn get_token(&self) -> Option<char> {
+        None
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_kind(&self) -> Option<PredicateKind> {
+        None
+    }
+
     fn get_state_type(&self) -> String {
         String::from("Trivial State")
     }
@@ -45,6 +65,6 @@ impl State for AnchorState {
         other
             .as_any()
             .downcast_ref::<AnchorState>()
-            .map_or(false, |a| ptr::eq(self, a))
+            .is_some_and(|a| ptr::eq(self, a))
     }
 }
@@ -0,0 +1,69 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::{ptr, slice};
+
+use super::{Anchor, PredicateKind, State};
+
+/// A state matching the substring last captured by the capturing group of index `index`, and moving on to `dest` past it.
+///
+/// Backreferences require the capture slots of the path taken, so they are only followed by the backtracking engine.
+pub struct BackrefState {
+    dest: Rc<RefCell<dyn State>>,
+    index: usize,
+}
+
+impl BackrefState {
+    pub fn new(index: usize, dest: Rc<RefCell<dyn State>>) -> Self {
+        BackrefState { dest, index }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl State for BackrefState {
+    fn epsilon(&self, _: &[Anchor]) -> &[Rc<RefCell<dyn State>>] {
+        &([] as [Rc<RefCell<dyn State>>; 0])
+    }
+
+    fn transition(&self, _: char) -> Option<Rc<RefCell<dyn State>>> {
+        None
+    }
+
+    fn get_dest(&self) -> &[Rc<RefCell<dyn State>>] {
+        slice::from_ref(&self.dest)
+    }
+
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>] {
+        slice::from_mut(&mut self.dest)
+    }
+
+    fn get_token(&self) -> Option<char> {
+        None
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_kind(&self) -> Option<PredicateKind> {
+        None
+    }
+
+    fn get_state_type(&self) -> String {
+        format!("Backreference State: {}", self.index)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn equals(&self, other: &dyn State) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<BackrefState>()
+            .is_some_and(|a| ptr::eq(self, a))
+    }
+}
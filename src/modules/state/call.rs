@@ -0,0 +1,70 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::{ptr, slice};
+
+use super::{Anchor, PredicateKind, State};
+
+/// A state calling the capturing group of index `index`, or the whole regular expression if `index` is 0, and resuming at
+/// `dest` once the call returns.
+///
+/// Calls require a call stack, so they are only followed by the backtracking engine.
+pub struct CallState {
+    dest: Rc<RefCell<dyn State>>,
+    index: usize,
+}
+
+impl CallState {
+    pub fn new(index: usize, dest: Rc<RefCell<dyn State>>) -> Self {
+        CallState { dest, index }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl State for CallState {
+    fn epsilon(&self, _: &[Anchor]) -> &[Rc<RefCell<dyn State>>] {
+        &([] as [Rc<RefCell<dyn State>>; 0])
+    }
+
+    fn transition(&self, _: char) -> Option<Rc<RefCell<dyn State>>> {
+        None
+    }
+
+    fn get_dest(&self) -> &[Rc<RefCell<dyn State>>] {
+        slice::from_ref(&self.dest)
+    }
+
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>] {
+        slice::from_mut(&mut self.dest)
+    }
+
+    fn get_token(&self) -> Option<char> {
+        None
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_kind(&self) -> Option<PredicateKind> {
+        None
+    }
+
+    fn get_state_type(&self) -> String {
+        format!("Call State: {}", self.index)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn equals(&self, other: &dyn State) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<CallState>()
+            .is_some_and(|a| ptr::eq(self, a))
+    }
+}
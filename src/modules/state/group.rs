@@ -0,0 +1,66 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::{ptr, slice};
+
+use super::{Anchor, PredicateKind, State};
+
+/// A state recording the byte offset where it is reached into the capture slot `slot`, either the start or the end of a
+/// capturing group, and moving on to `dest` without consuming anything.
+///
+/// Capture slots belong to the path taken, so they are only recorded by the backtracking engine.
+pub struct GroupState {
+    dest: Rc<RefCell<dyn State>>,
+    slot: usize,
+}
+
+impl GroupState {
+    pub fn new(slot: usize, dest: Rc<RefCell<dyn State>>) -> Self {
+        GroupState { dest, slot }
+    }
+}
+
+impl State for GroupState {
+    fn epsilon(&self, _: &[Anchor]) -> &[Rc<RefCell<dyn State>>] {
+        slice::from_ref(&self.dest)
+    }
+
+    fn transition(&self, _: char) -> Option<Rc<RefCell<dyn State>>> {
+        None
+    }
+
+    fn get_dest(&self) -> &[Rc<RefCell<dyn State>>] {
+        slice::from_ref(&self.dest)
+    }
+
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>] {
+        slice::from_mut(&mut self.dest)
+    }
+
+    fn get_token(&self) -> Option<char> {
+        None
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        Some(self.slot)
+    }
+
+    fn get_kind(&self) -> Option<PredicateKind> {
+        None
+    }
+
+    fn get_state_type(&self) -> String {
+        format!("Group State: {}", self.slot)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn equals(&self, other: &dyn State) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<GroupState>()
+            .is_some_and(|a| ptr::eq(self, a))
+    }
+}
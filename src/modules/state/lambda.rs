@@ -1,22 +1,49 @@
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::{ptr, slice};
 
-use super::{Anchor, State};
+use super::{Anchor, PredicateKind, State};
 
 pub struct LambdaState {
     dest: Rc<RefCell<dyn State>>,
-    lambda: Box<dyn Fn(char) -> bool>,
+    lambda: Rc<dyn Fn(char) -> bool>,
+    /// Description of the characters accepted by `lambda`.
+    kind: PredicateKind,
+    /// Number of the predicate among the distinct ones of its automaton, if numbered.
+    predicate: Cell<Option<usize>>,
 }
 
 impl LambdaState {
-    pub fn new<F: Fn(char) -> bool + 'static>(lambda: F, dest: Rc<RefCell<dyn State>>) -> Self {
-        LambdaState { dest, lambda: Box::new(lambda) }
+    pub fn new<F: Fn(char) -> bool + 'static>(lambda: F, kind: PredicateKind, dest: Rc<RefCell<dyn State>>) -> Self {
+        LambdaState { dest, lambda: Rc::new(lambda), kind, predicate: Cell::new(None) }
     }
 
-    pub fn new_with_box(closure: Box<dyn Fn(char) -> bool>, dest: Rc<RefCell<dyn State>>) -> Self {
-        LambdaState { dest, lambda: closure }
+    pub fn new_with_box(closure: Box<dyn Fn(char) -> bool>, kind: PredicateKind, dest: Rc<RefCell<dyn State>>) -> Self {
+        LambdaState { dest, lambda: Rc::from(closure), kind, predicate: Cell::new(None) }
+    }
+
+    pub fn new_with_rc(lambda: Rc<dyn Fn(char) -> bool>, kind: PredicateKind, dest: Rc<RefCell<dyn State>>) -> Self {
+        LambdaState { dest, lambda, kind, predicate: Cell::new(None) }
+    }
+
+    pub fn lambda(&self) -> Rc<dyn Fn(char) -> bool> {
+        self.lambda.clone()
+    }
+
+    /// Returns the description of the characters accepted, without cloning it unlike [`State::get_kind`].
+    pub fn kind(&self) -> &PredicateKind {
+        &self.kind
+    }
+
+    /// Sets the number of the predicate among the distinct ones of its automaton.
+    pub fn set_predicate(&self, index: usize) {
+        self.predicate.set(Some(index));
+    }
+
+    /// Returns whether `token` is accepted.
+    pub fn accepts(&self, token: char) -> bool {
+        (self.lambda)(token)
     }
 }
 
@@ -26,17 +53,46 @@ impl State for LambdaState {
     }
 
     fn transition(&self, token: char) -> Option<Rc<RefCell<dyn State>>> {
-        if (self.lambda)(token) {
+        if self.accepts(token) {
             Some(self.dest.clone())
         } else {
             None
         }
     }
 
+    fn transition_batched(&self, token: char, results: &mut [Option<bool>]) -> Option<Rc<RefCell<dyn State>>> {
+        let accepted = match self
+            .predicate
+            .get()
+            .and_then(|index| results.get_mut(index))
+        {
+            Some(result) => *result.get_or_insert_with(|| self.accepts(token)),
+            None => self.accepts(token),
+        };
+
+        accepted.then(|| self.dest.clone())
+    }
+
     fn get_dest(&self) -> &[Rc<RefCell<dyn State>>] {
         slice::from_ref(&self.dest)
     }
 
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>] {
+        slice::from_mut(&mut self.dest)
+    }
+
+    fn get_token(&self) -> Option<char> {
+        None
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_kind(&self) -> Option<PredicateKind> {
+        Some(self.kind.clone())
+    }
+
     fn get_state_type(&self) -> String {
         String::from("Lambda State")
     }
@@ -49,6 +105,6 @@ impl State for LambdaState {
         other
             .as_any()
             .downcast_ref::<LambdaState>()
-            .map_or(false, |a| ptr::eq(self, a))
+            .is_some_and(|a| ptr::eq(self, a))
     }
 }
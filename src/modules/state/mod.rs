@@ -1,10 +1,18 @@
 mod anchor;
+mod backref;
+mod call;
+mod group;
 mod lambda;
+mod predicate;
 mod token;
 mod trivial;
 
 pub use self::anchor::AnchorState;
+pub use self::backref::BackrefState;
+pub use self::call::CallState;
+pub use self::group::GroupState;
 pub use self::lambda::LambdaState;
+pub use self::predicate::{next_char, prev_char, PredicateKind};
 pub use self::token::TokenState;
 pub use self::trivial::TrivialState;
 
@@ -20,8 +28,22 @@ pub trait State {
 
     fn transition(&self, token: char) -> Option<Rc<RefCell<dyn State>>>;
 
+    /// Same as [`State::transition`], but reuses the result of a numbered predicate from `results` if already evaluated
+    /// on `token`, or records it there otherwise.
+    fn transition_batched(&self, token: char, _results: &mut [Option<bool>]) -> Option<Rc<RefCell<dyn State>>> {
+        self.transition(token)
+    }
+
     fn get_dest(&self) -> &[Rc<RefCell<dyn State>>];
 
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>];
+
+    fn get_token(&self) -> Option<char>;
+
+    fn get_slot(&self) -> Option<usize>;
+
+    fn get_kind(&self) -> Option<PredicateKind>;
+
     fn get_state_type(&self) -> String;
 
     fn as_any(&self) -> &dyn Any;
@@ -0,0 +1,109 @@
+/// A description of the characters accepted by the predicate of a state, used to partition the alphabet symbolically.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PredicateKind {
+    /// A single character.
+    Char(char),
+    /// Characters between both bounds, inclusive.
+    Range(char, char),
+    /// ASCII digits.
+    Digit,
+    /// ASCII alphanumeric characters.
+    Word,
+    /// ASCII whitespaces.
+    Whitespace,
+    /// Any character.
+    Any,
+    /// Characters not accepted by the inner predicate.
+    Not(Box<PredicateKind>),
+    /// Characters accepted by any of the inner predicates.
+    Union(Vec<PredicateKind>),
+    /// Characters accepted by an opaque predicate, e.g. a Unicode class, whose ranges are unknown.
+    Custom,
+}
+
+impl PredicateKind {
+    /// Returns the sorted and disjoint ranges of characters accepted, or `None` if they are unknown.
+    pub fn ranges(&self) -> Option<Vec<(char, char)>> {
+        let ranges = match self {
+            PredicateKind::Char(c) => vec![(*c, *c)],
+            PredicateKind::Range(lower, upper) if lower <= upper => vec![(*lower, *upper)],
+            PredicateKind::Range(_, _) => vec![],
+            PredicateKind::Digit => vec![('0', '9')],
+            PredicateKind::Word => vec![('0', '9'), ('A', 'Z'), ('a', 'z')],
+            PredicateKind::Whitespace => vec![('\t', '\n'), ('\x0c', '\r'), (' ', ' ')],
+            PredicateKind::Any => vec![('\0', char::MAX)],
+            PredicateKind::Not(kind) => complement(&kind.ranges()?),
+            PredicateKind::Union(kinds) => {
+                let mut ranges = vec![];
+                for kind in kinds {
+                    ranges.extend(kind.ranges()?);
+                }
+
+                normalize(ranges)
+            }
+            PredicateKind::Custom => return None,
+        };
+
+        Some(ranges)
+    }
+
+    /// Returns whether the characters accepted are unknown, i.e. whether two predicates of this kind may differ.
+    pub fn is_opaque(&self) -> bool {
+        match self {
+            PredicateKind::Not(kind) => kind.is_opaque(),
+            PredicateKind::Union(kinds) => kinds.iter().any(PredicateKind::is_opaque),
+            PredicateKind::Custom => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the character right after `c`, skipping surrogates, or `None` if `c` is [`char::MAX`].
+pub fn next_char(c: char) -> Option<char> {
+    match c {
+        '\u{d7ff}' => Some('\u{e000}'),
+        _ => char::from_u32(c as u32 + 1),
+    }
+}
+
+/// Returns the character right before `c`, skipping surrogates, or `None` if `c` is `'\0'`.
+pub fn prev_char(c: char) -> Option<char> {
+    match c {
+        '\u{e000}' => Some('\u{d7ff}'),
+        _ => char::from_u32((c as u32).checked_sub(1)?),
+    }
+}
+
+/// Sorts `ranges` and merges those which overlap or are adjacent.
+fn normalize(mut ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(char, char)> = vec![];
+    for (lower, upper) in ranges {
+        match merged.last_mut() {
+            Some((_, last_upper)) if next_char(*last_upper).is_none_or(|next| lower <= next) => {
+                *last_upper = (*last_upper).max(upper);
+            }
+            _ => merged.push((lower, upper)),
+        }
+    }
+
+    merged
+}
+
+/// Returns the ranges of characters not in the sorted and disjoint `ranges`.
+fn complement(ranges: &[(char, char)]) -> Vec<(char, char)> {
+    let mut complement = vec![];
+    let mut lower = Some('\0');
+    for &(start, end) in ranges {
+        if let Some(l) = lower.filter(|&l| l < start) {
+            complement.push((l, prev_char(start).expect("a character after another should have a predecessor")));
+        }
+        lower = next_char(end);
+    }
+    if let Some(l) = lower {
+        complement.push((l, char::MAX));
+    }
+
+    complement
+}
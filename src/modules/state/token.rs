@@ -3,7 +3,7 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::{ptr, slice};
 
-use super::{Anchor, State};
+use super::{Anchor, PredicateKind, State};
 
 pub struct TokenState {
     dest: Rc<RefCell<dyn State>>,
@@ -33,6 +33,22 @@ impl State for TokenState {
         slice::from_ref(&self.dest)
     }
 
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>] {
+        slice::from_mut(&mut self.dest)
+    }
+
+    fn get_token(&self) -> Option<char> {
+        Some(self.token)
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_kind(&self) -> Option<PredicateKind> {
+        Some(PredicateKind::Char(self.token))
+    }
+
     fn get_state_type(&self) -> String {
         format!("Token State: {}", self.token)
     }
@@ -45,6 +61,6 @@ impl State for TokenState {
         other
             .as_any()
             .downcast_ref::<TokenState>()
-            .map_or(false, |a| ptr::eq(self, a))
+            .is_some_and(|a| ptr::eq(self, a))
     }
 }
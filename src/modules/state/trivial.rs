@@ -3,7 +3,7 @@ use std::cell::RefCell;
 use std::ptr;
 use std::rc::Rc;
 
-use super::{Anchor, State};
+use super::{Anchor, PredicateKind, State};
 
 #[derive(Default)]
 pub struct TrivialState {
@@ -33,6 +33,22 @@ impl State for TrivialState {
         &self.dest[..]
     }
 
+    fn get_dest_mut(&mut self) -> &mut [Rc<RefCell<dyn State>>] {
+        &mut self.dest[..]
+    }
+
+    fn get_token(&self) -> Option<char> {
+        None
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_kind(&self) -> Option<PredicateKind> {
+        None
+    }
+
     fn get_state_type(&self) -> String {
         String::from("Trivial State")
     }
@@ -45,6 +61,6 @@ impl State for TrivialState {
         other
             .as_any()
             .downcast_ref::<TrivialState>()
-            .map_or(false, |a| ptr::eq(self, a))
+            .is_some_and(|a| ptr::eq(self, a))
     }
 }
@@ -0,0 +1,18 @@
+/// Logs a message at trace level through the `log` facade with the `trace` feature, and expands to nothing otherwise.
+#[cfg(feature = "trace")]
+macro_rules! trace {
+    ( $($arg:tt)+ ) => {
+        log::trace!($($arg)+)
+    };
+}
+
+/// Logs a message at trace level through the `log` facade with the `trace` feature, and expands to nothing otherwise.
+#[cfg(not(feature = "trace"))]
+macro_rules! trace {
+    ( $($arg:tt)+ ) => {{
+        // Arguments are still type checked, but never formatted
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+pub(crate) use trace;
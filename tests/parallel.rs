@@ -0,0 +1,18 @@
+//! Compiles and matches regular expressions across threads through the public API, as a caller outside of this crate
+//! would.
+
+use rustregex::regex::{ErrorKind, RegExp};
+
+#[test]
+fn compile_many_parallel() {
+    let patterns = ["a+b", "(", r"\d{2}", "x|y"];
+    let results = RegExp::compile_many_parallel(&patterns, |regexp| regexp.find("zz42aab").map(|m| m.range()));
+
+    assert_eq!(results.len(), patterns.len());
+    assert_eq!(results[0], Ok(Some(4..7)));
+    assert!(matches!(results[1].as_ref().err().map(|err| err.kind()), Some(ErrorKind::UnclosedGroup(0))));
+    assert_eq!(results[2], Ok(Some(2..4)));
+    assert_eq!(results[3], Ok(None));
+
+    assert!(RegExp::compile_many_parallel(&[], |regexp| regexp.group_count()).is_empty());
+}
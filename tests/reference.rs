@@ -0,0 +1,318 @@
+//! Compares the matches and captures of this crate against those of the `regex` crate, as a reference.
+//!
+//! The `regex` crate reports leftmost-first matches, so patterns are compiled with
+//! [`MatchSemantics::PerlLeftmostFirst`] to compare every match and capture, whereas only which expressions match and
+//! where the first match starts are compared under the default leftmost-longest semantics.
+//!
+//! Features the `regex` crate does not support, i.e. backreferences and recursion, are left out, and the classes of
+//! this crate are translated into their ASCII definitions, see [`reference_pattern`].
+//!
+//! Besides a curated list of patterns, patterns are generated pseudo-randomly from a fixed seed, nesting groups,
+//! alternations, anchors and greedy or lazy quantifiers, so that the same cases are compared on every run.
+
+use regex::Regex;
+use rustregex::regex::{MatchSemantics, RegExp, RegExpBuilder};
+
+/// Patterns, in the syntax of this crate, along with the expressions to match them against.
+const CASES: &[(&str, &[&str])] = &[
+    // Literals and concatenation
+    ("abc", &["abc", "xabcx", "ababc", "ab", ""]),
+    (r"a\.b", &["a.b", "axb"]),
+    ("é", &["café", "cafe"]),
+    // Alternation
+    ("a|b", &["a", "b", "cab", "c"]),
+    ("a|ab", &["ab", "aab"]),
+    ("ab|a", &["ab", "aab"]),
+    ("cat|category", &["category", "cat"]),
+    ("(a|ab)(c|bcd)", &["abcd", "abc"]),
+    // Quantifiers
+    ("a*", &["", "aaa", "baaa", "aba"]),
+    ("a+", &["", "aaa", "baaab"]),
+    ("a?b", &["b", "ab", "aab"]),
+    ("a{2}", &["a", "aaaaa"]),
+    ("a{2,}", &["a", "aaaaa"]),
+    ("a{2,3}", &["aaaaaaa", "aa a"]),
+    ("a{0,2}", &["aaa", "b"]),
+    ("(ab)*c", &["ababc", "abc", "c", "abab"]),
+    ("(a*)*b", &["aab", "b"]),
+    ("(a|b)*abb", &["aababb", "abb", "ab"]),
+    ("x*y*", &["xxyy", "yx", "z"]),
+    // Classes and character groups
+    (r"\d+", &["abc123def45", "", "٣"]),
+    (r"\w+", &["hello world", "a1 b2"]),
+    (r"\s+", &["a \t\nb", "ab"]),
+    (r"\D\W\S", &["a b", "1 b"]),
+    ("[a-c]+", &["abcd", "xyz"]),
+    ("[^a-c]+", &["abcd", "xyz"]),
+    (r"[\d\s]+", &["1 2 3x"]),
+    (r"[^\d\s]+", &["1 2 3xy"]),
+    ("[a-cx-z]", &["bycz"]),
+    ("[a^]+", &["a^b^"]),
+    ("[^^a]+", &["a^bc^"]),
+    ("[[:alpha:][:digit:]]+", &["a1 b2"]),
+    ("[[:^digit:]a]+", &["ab12a"]),
+    ("[^[:^digit:]]+", &["ab12a"]),
+    (".", &["ab", "\n", "é"]),
+    (".+", &["ab\ncd"]),
+    // Groups
+    ("(a)(b)?", &["a", "ab", "b"]),
+    ("(?:ab)+", &["ababab", "aba"]),
+    ("(a(b(c)))", &["abc", "xabcx"]),
+    ("(?<first>a)(?<second>b)", &["ab"]),
+    ("(a)|(b)", &["ab", "ba"]),
+    ("(a|b)+", &["abba"]),
+    ("((a)|b)+", &["ab", "ba"]),
+    ("(?:(a)|(b))+", &["ab", "aa", "abab", "xba"]),
+    ("(a?)+", &["aa"]),
+    (r"(\d+)\-(\w+)", &["123-abc", "1-2-3"]),
+    // Anchors
+    ("^a", &["a", "ba", "aa"]),
+    ("a$", &["a", "ab", "aa"]),
+    ("^$", &["", "a"]),
+    ("^a*$", &["", "aaa", "aba"]),
+    (r"\bab\b", &["ab ab", "aab", "ab"]),
+    (r"\Ba", &["ba a", "a"]),
+    ("(?m)^a", &["a\na", "ba\nb"]),
+    ("(?m)a$", &["a\na", "ab\na"]),
+    ("(?m)^$", &["a\n\nb", ""]),
+    // Inline flags
+    ("(?i)abc", &["ABC", "aBc", "abd"]),
+    ("a(?i:b)c", &["aBc", "ABC"]),
+    ("(?s).+", &["a\nb"]),
+    ("(?i)[a-c]+", &["xAbCd"]),
+    ("(?iu)é", &["É", "é"]),
+    ("(?m)^\\w+$", &["ab\ncd\n", "ab cd"]),
+    ("(a|ab)*c", &["abac", "ababc"]),
+    ("(a+)(a+)", &["aaaa"]),
+    ("a+?", &["aaa", "baab"]),
+    ("a*?b", &["aaab", "b"]),
+    ("a??b", &["ab", "b"]),
+    ("(a+?)(a*)", &["aaa"]),
+    ("a{2,3}?", &["aaaaa"]),
+    ("(?:a|b)*?b", &["aabab"]),
+    ("<(.+?)>", &["<a><bc>"]),
+    ("(a*)(a*)", &["aaaa", "b"]),
+    ("\\b", &["ab cd", "é a"]),
+    ("[a\\-z]+", &["a-z", "b"]),
+    ("[\\]]", &["]"]),
+    ("\\w+@\\w+\\.com", &["mail: abc@example.com."]),
+    ("(\\d{3})\\-(\\d{4})", &["555-1234 and 555-9876"]),
+    // Repeated groups which may match the empty string
+    ("(?:b??)*", &["ba", "bb", ""]),
+    ("(b??)*", &["ba", "bb", ""]),
+    ("(a??)+", &["aa", "b"]),
+    ("(a*)+b", &["aab", "b"]),
+    ("(a*?|b)+c", &["bac", "abc"]),
+    ("((?:a*?|b)+)*?a*.$", &["baaab", "bab"]),
+    ("[ab]{0,2}(((?:a*)*)*|c*)+b*", &["bcc", "abcc"]),
+    ("($a|a*(...)|b*?)*a{1,2}", &["bba", "aaa"]),
+    ("(a|b?){2,}c", &["abc", "c"]),
+    ("(a{0,2}?|b)+", &["aab", "ba"]),
+    // Alternatives sharing a prefix
+    ("a?a|a?(b)", &["ab", "b"]),
+];
+
+/// Patterns, in the syntax of this crate, whose results knowingly differ from those of the `regex` crate, along with
+/// why.
+const KNOWN_DIFFERENCES: &[(&str, &str)] = &[
+    // The `regex` crate factors out a prefix common to every alternative, e.g. into `a?(?:a|(b))` here, so that it tries
+    // the second alternative before backtracking into the prefix of the first, unlike Perl
+    ("a?a|a?(b)", "a prefix common to every alternative is factored out"),
+];
+
+/// Returns `pattern` translated into the syntax of the `regex` crate, replacing each class by its ASCII definition in
+/// this crate, e.g. `\w` excludes `_` here.
+fn reference_pattern(pattern: &str) -> String {
+    let mut reference = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            reference.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('d') => reference.push_str("[0-9]"),
+            Some('D') => reference.push_str("[^0-9]"),
+            Some('w') => reference.push_str("[0-9A-Za-z]"),
+            Some('W') => reference.push_str("[^0-9A-Za-z]"),
+            Some('s') => reference.push_str(r"[\t\n\x0C\r ]"),
+            Some('S') => reference.push_str(r"[^\t\n\x0C\r ]"),
+            Some('b') => reference.push_str(r"(?:(?-u:\b))"),
+            Some('B') => reference.push_str(r"(?:(?-u:\B))"),
+            Some(escaped) => {
+                reference.push('\\');
+                reference.push(escaped);
+            }
+            None => reference.push('\\'),
+        }
+    }
+
+    reference
+}
+
+/// Returns the spans of every capturing group, including the entire match, of each match of `regexp` in `expr`.
+fn captures(regexp: &RegExp, expr: &str) -> Vec<Vec<Option<(usize, usize)>>> {
+    regexp
+        .captures_iter_lazy(expr)
+        .map(|captures| {
+            (0..captures.len())
+                .map(|i| captures.get_match(i).map(|m| (m.start(), m.end())))
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the spans of every capturing group, including the entire match, of each match of `reference` in `expr`.
+fn reference_captures(reference: &Regex, expr: &str) -> Vec<Vec<Option<(usize, usize)>>> {
+    reference
+        .captures_iter(expr)
+        .map(|captures| {
+            captures
+                .iter()
+                .map(|m| m.map(|m| (m.start(), m.end())))
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns a description of each difference between the results of this crate and those of the `regex` crate for
+/// `pattern` on `expr`.
+fn differences(pattern: &str, expr: &str) -> Vec<String> {
+    let reference = Regex::new(&reference_pattern(pattern)).unwrap();
+    let perl = RegExpBuilder::new(pattern)
+        .semantics(MatchSemantics::PerlLeftmostFirst)
+        .build()
+        .unwrap();
+    let posix = RegExpBuilder::new(pattern).build().unwrap();
+
+    let mut differences = vec![];
+    let mut compare = |what: &str, actual: String, expected: String| {
+        if actual != expected {
+            differences.push(format!("{pattern:?} on {expr:?}: {what} gave {actual}, expected {expected}"));
+        }
+    };
+
+    let matches: Vec<String> = reference
+        .find_iter(expr)
+        .map(|m| String::from(m.as_str()))
+        .collect();
+    compare("global_search", format!("{:?}", perl.global_search(expr)), format!("{matches:?}"));
+    compare(
+        "captures",
+        format!("{:?}", captures(&perl, expr)),
+        format!("{:?}", reference_captures(&reference, expr)),
+    );
+    compare("is_match", format!("{}", posix.is_match(expr)), format!("{}", reference.is_match(expr)));
+    compare(
+        "find start",
+        format!("{:?}", posix.find(expr).map(|m| m.start())),
+        format!("{:?}", reference.find(expr).map(|m| m.start())),
+    );
+
+    differences
+}
+
+#[test]
+fn reference_comparison() {
+    let mut unexpected = vec![];
+    for (pattern, exprs) in CASES {
+        let differences: Vec<String> = exprs
+            .iter()
+            .flat_map(|expr| differences(pattern, expr))
+            .collect();
+        let known = KNOWN_DIFFERENCES.iter().any(|(known, _)| known == pattern);
+        match (differences.is_empty(), known) {
+            (false, false) => unexpected.extend(differences),
+            (true, true) => unexpected.push(format!("{pattern:?} is a known difference, but agrees with the reference")),
+            _ => {}
+        }
+    }
+
+    assert!(unexpected.is_empty(), "{}", unexpected.join("\n"));
+}
+
+/// A xorshift pseudo-random number generator, seeded so that generated cases are the same on every run.
+struct Rng(u64);
+
+impl Rng {
+    /// Returns a pseudo-random number below `bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+
+    /// Returns a pseudo-random element of `choices`.
+    fn pick<'a>(&mut self, choices: &[&'a str]) -> &'a str {
+        choices[self.below(choices.len())]
+    }
+}
+
+/// Returns a pseudo-random pattern over `a`, `b` and `c`, nesting groups up to `depth` levels deep.
+fn generate_pattern(rng: &mut Rng, depth: usize) -> String {
+    let count = 1 + rng.below(if depth > 0 { 3 } else { 2 });
+    let mut branches: Vec<Vec<String>> = vec![];
+    while branches.len() < count {
+        let branch: Vec<String> = (0..1 + rng.below(3))
+            .map(|_| generate_piece(rng, depth))
+            .collect();
+
+        // The `regex` crate factors out a prefix common to every alternative, which changes their priority
+        if !branches.is_empty() && branches.iter().all(|other| other[0] == branch[0]) {
+            continue;
+        }
+        branches.push(branch);
+    }
+
+    branches
+        .iter()
+        .map(|branch| branch.concat())
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Returns a pseudo-random piece of a pattern, i.e. an anchor or a possibly quantified atom, nesting groups up to `depth`
+/// levels deep.
+fn generate_piece(rng: &mut Rng, depth: usize) -> String {
+    let mut piece = match rng.below(if depth > 0 { 8 } else { 6 }) {
+        0 => return String::from(rng.pick(&["^", "$"])),
+        1 => String::from(rng.pick(&["[ab]", "[^a]", "."])),
+        2..=5 => String::from(rng.pick(&["a", "b", "c"])),
+        _ => format!("{}{})", rng.pick(&["(", "(?:"]), generate_pattern(rng, depth - 1)),
+    };
+
+    if rng.below(2) == 0 {
+        piece.push_str(rng.pick(&["*", "+", "?", "{2}", "{0,2}", "{1,}"]));
+        if rng.below(3) == 0 {
+            piece.push('?');
+        }
+    }
+
+    piece
+}
+
+/// Returns a pseudo-random expression over `a`, `b` and `c`.
+fn generate_expr(rng: &mut Rng) -> String {
+    (0..rng.below(6))
+        .map(|_| rng.pick(&["a", "b", "c"]))
+        .collect()
+}
+
+/// Number of patterns generated by [`reference_generated`], each matched against a handful of generated expressions.
+const GENERATED_PATTERNS: usize = 1000;
+
+#[test]
+fn reference_generated() {
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    let mut unexpected = vec![];
+    for _ in 0..GENERATED_PATTERNS {
+        let pattern = generate_pattern(&mut rng, 2);
+        for _ in 0..6 {
+            unexpected.extend(differences(&pattern, &generate_expr(&mut rng)));
+        }
+    }
+
+    assert!(unexpected.is_empty(), "{}", unexpected.join("\n"));
+}